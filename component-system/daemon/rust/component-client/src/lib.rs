@@ -0,0 +1,154 @@
+//! Typed client for the component daemon.
+//!
+//! ```no_run
+//! # async fn run() -> anyhow::Result<()> {
+//! use futures_util::StreamExt;
+//!
+//! let client = component_client::DaemonClient::new("http://localhost:3001")?;
+//! for component in client.components().await? {
+//!     println!("{} v{}", component.id, component.version);
+//! }
+//! let mut updates = Box::pin(client.subscribe_updates(Default::default()));
+//! while let Some(component) = updates.next().await {
+//!     println!("update {}", component.id);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request};
+use serde::de::DeserializeOwned;
+
+mod subscription;
+mod types;
+
+pub use types::{Component, ComponentType, GraphqlError, UpdateFilter};
+
+// Declared to the daemon; the highest version whose fields this client selects
+pub const API_VERSION: u32 = 2;
+
+#[derive(Clone, Debug)]
+pub struct ClientConfig {
+    // Waits between reconnect attempts, doubling up to `max_backoff`
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub request_timeout: Duration,
+    // Sent in connection_init so the daemon can track deliveries and resume
+    // this renderer after a brief disconnect
+    pub client_id: Option<String>,
+    // Sent as a bearer token when the daemon sets PUBLIC_AUTH_TOKEN
+    pub auth_token: Option<String>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            request_timeout: Duration::from_secs(10),
+            client_id: None,
+            auth_token: None,
+        }
+    }
+}
+
+// Queries go over HTTP POST to /graphql, subscriptions over graphql-transport-ws
+// on the same path. Cloning is cheap and shares the HTTP connection pool.
+#[derive(Clone)]
+pub struct DaemonClient {
+    http_url: String,
+    ws_url: String,
+    http: Client<HttpConnector>,
+    config: ClientConfig,
+}
+
+impl DaemonClient {
+    // `base_url` is the daemon root, e.g. "http://localhost:3001"
+    pub fn new(base_url: &str) -> Result<Self> {
+        Self::with_config(base_url, ClientConfig::default())
+    }
+
+    pub fn with_config(base_url: &str, config: ClientConfig) -> Result<Self> {
+        let base = base_url.trim_end_matches('/');
+        let Some(host) = base.strip_prefix("http://") else {
+            bail!("only http:// daemon URLs are supported: {base_url}");
+        };
+        Ok(Self {
+            http_url: format!("{base}/graphql"),
+            ws_url: format!("ws://{host}/graphql"),
+            http: Client::new(),
+            config,
+        })
+    }
+
+    pub async fn components(&self) -> Result<Vec<Component>> {
+        let query = format!("{{ components {{ {} }} }}", types::COMPONENT_FIELDS);
+        self.query_field("components", &query, serde_json::Value::Null).await
+    }
+
+    // Components matching a label selector, e.g. "env=prod"
+    pub async fn components_with_labels(&self, selector: &str) -> Result<Vec<Component>> {
+        let query = format!(
+            "query($labels: String) {{ components(labels: $labels) {{ {} }} }}",
+            types::COMPONENT_FIELDS
+        );
+        self.query_field("components", &query, serde_json::json!({ "labels": selector }))
+            .await
+    }
+
+    // None when the daemon doesn't have the id
+    pub async fn component(&self, id: &str) -> Result<Option<Component>> {
+        let query = format!(
+            "query($id: String!) {{ component(id: $id) {{ {} }} }}",
+            types::COMPONENT_FIELDS
+        );
+        self.query_field("component", &query, serde_json::json!({ "id": id }))
+            .await
+    }
+
+    // Runs any query or mutation and returns its `data`. GraphQL errors come
+    // back as a `GraphqlError` carrying the daemon's error code.
+    pub async fn execute(&self, query: &str, variables: serde_json::Value) -> Result<serde_json::Value> {
+        let body = serde_json::json!({ "query": query, "variables": variables });
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.http_url)
+            .header("content-type", "application/json")
+            .header("x-api-version", API_VERSION.to_string());
+        if let Some(token) = &self.config.auth_token {
+            request = request.header("authorization", format!("Bearer {token}"));
+        }
+        let request = request.body(Body::from(serde_json::to_vec(&body)?))?;
+        let response = tokio::time::timeout(self.config.request_timeout, self.http.request(request))
+            .await
+            .map_err(|_| anyhow!("no response from daemon within {:?}", self.config.request_timeout))?
+            .context("daemon request failed")?;
+        let status = response.status();
+        let bytes = hyper::body::to_bytes(response.into_body()).await?;
+        let mut reply: serde_json::Value = serde_json::from_slice(&bytes)
+            .with_context(|| format!("daemon answered {status} with a non-JSON body"))?;
+        if let Some(error) = first_error(&reply) {
+            return Err(error.into());
+        }
+        Ok(reply["data"].take())
+    }
+
+    async fn query_field<T: DeserializeOwned>(
+        &self,
+        field: &str,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<T> {
+        let mut data = self.execute(query, variables).await?;
+        serde_json::from_value(data[field].take()).with_context(|| format!("unexpected `{field}` shape"))
+    }
+}
+
+pub(crate) fn first_error(reply: &serde_json::Value) -> Option<GraphqlError> {
+    let errors = reply.get("errors")?.as_array()?;
+    serde_json::from_value(errors.first()?.clone()).ok()
+}
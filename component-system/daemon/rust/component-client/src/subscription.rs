@@ -0,0 +1,183 @@
+use anyhow::{anyhow, bail, Result};
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, Stream, StreamExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tracing::{info, warn};
+
+use crate::types::{Component, UpdateFilter, COMPONENT_FIELDS};
+use crate::{first_error, DaemonClient, API_VERSION};
+
+// ========================
+// SUBSCRIPTIONS
+// ========================
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+const OPERATION_ID: &str = "renderer-update";
+
+enum Event {
+    Update(Box<Component>),
+    // The daemon can't replay from our last seq; refetch and start over
+    ResumeUnavailable,
+    Ping,
+    Ignored,
+    Ended(String),
+}
+
+impl DaemonClient {
+    // Component updates that survive disconnects. Reconnects resume after the
+    // last seen seq, so nothing is missed or repeated while the daemon's
+    // history still covers the gap; when it doesn't, the current matching
+    // components are fetched and yielded before live updates continue.
+    pub fn subscribe_updates(&self, filter: UpdateFilter) -> impl Stream<Item = Component> + Send + 'static {
+        let client = self.clone();
+        async_stream::stream! {
+            let mut after_seq: Option<u64> = None;
+            let mut backoff = client.config.initial_backoff;
+            loop {
+                let (mut write, mut read) = match client.open(&filter, after_seq).await {
+                    Ok(socket) => {
+                        backoff = client.config.initial_backoff;
+                        socket
+                    }
+                    Err(e) => {
+                        warn!("🔌 Client: Subscription to {} failed: {:#}, retrying in {:?}", client.ws_url, e, backoff);
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(client.config.max_backoff);
+                        continue;
+                    }
+                };
+                info!("📡 Client: Subscribed to {} (after seq {:?})", client.ws_url, after_seq);
+
+                while let Some(message) = read.next().await {
+                    let text = match message {
+                        Ok(Message::Text(text)) => text,
+                        Ok(Message::Close(_)) | Err(_) => break,
+                        Ok(_) => continue,
+                    };
+                    match parse_event(&text) {
+                        Event::Update(component) => {
+                            after_seq = Some(component.seq);
+                            yield *component;
+                        }
+                        Event::ResumeUnavailable => {
+                            warn!("⏪ Client: Daemon can't resume after seq {:?}, resyncing", after_seq);
+                            after_seq = None;
+                            match client.resync(&filter).await {
+                                Ok(components) => {
+                                    after_seq = components.iter().map(|c| c.seq).max();
+                                    for component in components {
+                                        yield component;
+                                    }
+                                }
+                                Err(e) => warn!("⚠️ Client: Resync failed: {:#}", e),
+                            }
+                            break;
+                        }
+                        Event::Ping => {
+                            let _ = write.send(Message::text(r#"{"type":"pong"}"#)).await;
+                        }
+                        Event::Ignored => {}
+                        Event::Ended(reason) => {
+                            warn!("🔌 Client: Subscription ended: {}", reason);
+                            break;
+                        }
+                    }
+                }
+                let _ = write.close().await;
+                tokio::time::sleep(backoff).await;
+            }
+        }
+    }
+
+    async fn open(
+        &self,
+        filter: &UpdateFilter,
+        after_seq: Option<u64>,
+    ) -> Result<(SplitSink<Socket, Message>, SplitStream<Socket>)> {
+        let mut request = self.ws_url.as_str().into_client_request()?;
+        let headers = request.headers_mut();
+        headers.insert("Sec-WebSocket-Protocol", HeaderValue::from_static("graphql-transport-ws"));
+        if let Some(token) = &self.config.auth_token {
+            headers.insert("Authorization", HeaderValue::from_str(&format!("Bearer {token}"))?);
+        }
+        let (socket, _) = connect_async(request).await?;
+        let (mut write, mut read) = socket.split();
+
+        let init = serde_json::json!({
+            "type": "connection_init",
+            "payload": { "apiVersion": API_VERSION, "clientId": self.config.client_id },
+        });
+        write.send(Message::text(init.to_string())).await?;
+        let acked = tokio::time::timeout(self.config.request_timeout, async {
+            while let Some(message) = read.next().await {
+                if let Message::Text(text) = message? {
+                    if text.contains(r#""type":"connection_ack""#) {
+                        return Ok(());
+                    }
+                }
+            }
+            bail!("connection closed before connection_ack")
+        })
+        .await;
+        acked.map_err(|_| anyhow!("no connection_ack within {:?}", self.config.request_timeout))??;
+
+        let query = format!(
+            "subscription($minPriority: Int, $types: [ComponentType!], $labels: String, $afterSeq: Int) {{ \
+             rendererUpdate(minPriority: $minPriority, types: $types, labels: $labels, afterSeq: $afterSeq) {{ {} }} }}",
+            COMPONENT_FIELDS
+        );
+        let subscribe = serde_json::json!({
+            "id": OPERATION_ID,
+            "type": "subscribe",
+            "payload": { "query": query, "variables": filter.variables(after_seq) },
+        });
+        write.send(Message::text(subscribe.to_string())).await?;
+        Ok((write, read))
+    }
+
+    // Current components matching the filter, oldest seq first
+    async fn resync(&self, filter: &UpdateFilter) -> Result<Vec<Component>> {
+        let mut components = match &filter.labels {
+            Some(selector) => self.components_with_labels(selector).await?,
+            None => self.components().await?,
+        };
+        components.retain(|component| {
+            filter.min_priority.is_none_or(|min| component.priority.unwrap_or(0) >= min)
+                && filter.types.as_ref().is_none_or(|types| types.contains(&component.r#type))
+        });
+        components.sort_by_key(|component| component.seq);
+        Ok(components)
+    }
+}
+
+fn parse_event(text: &str) -> Event {
+    let Ok(mut message) = serde_json::from_str::<serde_json::Value>(text) else {
+        return Event::Ignored;
+    };
+    // graphql-transport-ws sends the error list itself as an `error` payload
+    let payload = match message["type"].as_str() {
+        Some("next") => message["payload"].take(),
+        Some("error") => serde_json::json!({ "errors": message["payload"].take() }),
+        Some("complete") => return Event::Ended("completed by daemon".to_string()),
+        Some("ping") => return Event::Ping,
+        _ => return Event::Ignored,
+    };
+    if let Some(error) = first_error(&payload) {
+        return match error.code() {
+            Some("RESUME_UNAVAILABLE") => Event::ResumeUnavailable,
+            _ => Event::Ended(error.to_string()),
+        };
+    }
+    match serde_json::from_value::<Component>(payload["data"]["rendererUpdate"].clone()) {
+        Ok(component) => Event::Update(Box::new(component)),
+        Err(e) => {
+            warn!("⚠️ Client: Skipping malformed update: {}", e);
+            Event::Ignored
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// ========================
+// WIRE TYPES
+// ========================
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ComponentType {
+    Card,
+    Notification,
+    Form,
+    // A type this client doesn't know yet
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Component {
+    pub id: String,
+    pub r#type: ComponentType,
+    pub data: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub priority: Option<i32>,
+    // Bumped by the daemon on every accepted write
+    #[serde(default)]
+    pub version: u64,
+    // Position in the daemon's broadcast order; used to resume subscriptions
+    #[serde(default)]
+    pub seq: u64,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub related_ids: Vec<String>,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    #[serde(default)]
+    pub deliver_at: Option<DateTime<Utc>>,
+}
+
+// Selection used for every component the client fetches
+pub(crate) const COMPONENT_FIELDS: &str =
+    "id type data createdAt priority version seq parentId relatedIds labels deliverAt";
+
+#[derive(Clone, Debug, Default)]
+pub struct UpdateFilter {
+    pub min_priority: Option<i32>,
+    pub types: Option<Vec<ComponentType>>,
+    // Label selector, e.g. "env=prod,team in (web,ops)"
+    pub labels: Option<String>,
+}
+
+impl UpdateFilter {
+    pub(crate) fn variables(&self, after_seq: Option<u64>) -> serde_json::Value {
+        serde_json::json!({
+            "minPriority": self.min_priority,
+            "types": self.types,
+            "labels": self.labels,
+            "afterSeq": after_seq,
+        })
+    }
+}
+
+// An error entry from a GraphQL response
+#[derive(Clone, Debug, Deserialize)]
+pub struct GraphqlError {
+    pub message: String,
+    #[serde(default)]
+    pub extensions: Option<serde_json::Value>,
+}
+
+impl GraphqlError {
+    pub fn code(&self) -> Option<&str> {
+        self.extensions.as_ref()?.get("code")?.as_str()
+    }
+
+    // False when the request has to change first, e.g. after a refetch
+    pub fn retryable(&self) -> bool {
+        self.extensions
+            .as_ref()
+            .and_then(|extensions| extensions.get("retryable")?.as_bool())
+            .unwrap_or(false)
+    }
+
+    // Code-specific fields, e.g. `currentVersion` on VERSION_CONFLICT
+    pub fn details(&self) -> Option<&serde_json::Value> {
+        self.extensions.as_ref()?.get("details")
+    }
+}
+
+impl std::fmt::Display for GraphqlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.code() {
+            Some(code) => write!(f, "{} ({})", self.message, code),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for GraphqlError {}
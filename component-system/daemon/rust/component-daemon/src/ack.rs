@@ -0,0 +1,41 @@
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::config::env_parse;
+
+// ========================
+// ACKNOWLEDGEMENTS
+// ========================
+
+#[derive(Clone, Debug, Serialize, Deserialize, SimpleObject, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Acknowledgement {
+    pub id: String,
+    pub by: String,
+    pub acknowledged_at: DateTime<Utc>,
+    // The notification was taken out of active state by this ack
+    pub removed: bool,
+}
+
+pub struct AckConfig {
+    // ACK_REMOVES: default for the mutation's `remove` argument
+    pub remove_by_default: bool,
+    // ACK_UPSTREAM_MUTATION: operation forwarded to the registry in
+    // bidirectional mode, with $id, $by and $acknowledgedAt variables
+    pub upstream_mutation: String,
+}
+
+impl AckConfig {
+    pub fn from_env() -> Self {
+        Self {
+            remove_by_default: env_parse("ACK_REMOVES", false),
+            upstream_mutation: std::env::var("ACK_UPSTREAM_MUTATION").unwrap_or_else(|_| {
+                "mutation Ack($id: String!, $by: String!, $acknowledgedAt: String!) { \
+                 acknowledgeNotification(id: $id, by: $by, acknowledgedAt: $acknowledgedAt) }"
+                    .to_string()
+            }),
+        }
+    }
+}
@@ -0,0 +1,562 @@
+use std::convert::Infallible;
+
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use warp::http::StatusCode;
+use warp::reply::{Reply, Response};
+use warp::Filter;
+
+use crate::audit::{AuditEntry, AuditVerification, RequestOrigin};
+use crate::flags::{Flag, FlagState};
+use crate::lifecycle::InternalEventKind;
+use crate::listeners::BearerAuth;
+use crate::logging::{self, LogLevelChange};
+use crate::quotas::UsageReport;
+use crate::replication::{self, ReplicationStatus};
+use crate::shadow::ShadowStats;
+use crate::signature::QuarantinedComponent;
+use crate::{request_origin, ComponentDaemon};
+
+// ========================
+// ADMIN API
+// ========================
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AuditQuery {
+    pub action: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct UsageQuery {
+    // One key's usage instead of every key's
+    pub key: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLevelBody {
+    pub filter: String,
+    pub ttl_seconds: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct FlagBody {
+    pub enabled: bool,
+}
+
+pub fn routes(
+    daemon: ComponentDaemon,
+    auth: &BearerAuth,
+) -> impl Filter<Extract = (Response,), Error = warp::Rejection> + Clone {
+    let with_daemon = warp::any().map(move || daemon.clone());
+
+    // Deserialization failure counts and redacted payload samples
+    let ingest_failures = warp::path!("admin" / "ingest-failures")
+        .and(warp::get())
+        .and(with_daemon.clone())
+        .and_then(ingest_failures);
+
+    let purge = warp::path!("admin" / "purge")
+        .and(warp::post())
+        .and(request_origin(auth))
+        .and(with_daemon.clone())
+        .and_then(purge);
+
+    let reconnect = warp::path!("admin" / "reconnect")
+        .and(warp::post())
+        .and(request_origin(auth))
+        .and(with_daemon.clone())
+        .and_then(reconnect);
+
+    let replication_status = warp::path!("admin" / "replication")
+        .and(warp::get())
+        .and(with_daemon.clone())
+        .and_then(replication_status);
+
+    // Fails a hot standby over by hand instead of waiting out the timeout
+    let promote = warp::path!("admin" / "replication" / "promote")
+        .and(warp::post())
+        .and(request_origin(auth))
+        .and(with_daemon.clone())
+        .and_then(promote);
+
+    let usage = warp::path!("admin" / "usage")
+        .and(warp::get())
+        .and(warp::query::<UsageQuery>())
+        .and(with_daemon.clone())
+        .and_then(usage);
+
+    let shadow = warp::path!("admin" / "shadow")
+        .and(warp::get())
+        .and(with_daemon.clone())
+        .and_then(shadow);
+
+    let audit_query = warp::path!("admin" / "audit")
+        .and(warp::get())
+        .and(warp::query::<AuditQuery>())
+        .and(with_daemon.clone())
+        .and_then(audit_query);
+
+    let audit_verify = warp::path!("admin" / "audit" / "verify")
+        .and(warp::get())
+        .and(with_daemon.clone())
+        .and_then(audit_verify);
+
+    // Re-encrypts persisted journals with the active at-rest key
+    let rewrap = warp::path!("admin" / "encryption" / "rewrap")
+        .and(warp::post())
+        .and(request_origin(auth))
+        .and(with_daemon.clone())
+        .and_then(rewrap);
+
+    let log_level_get = warp::path!("admin" / "log-level")
+        .and(warp::get())
+        .and_then(log_level_get);
+
+    let log_level_put = warp::path!("admin" / "log-level")
+        .and(warp::put())
+        .and(warp::body::json())
+        .and(request_origin(auth))
+        .and(with_daemon.clone())
+        .and_then(log_level_put);
+
+    let quarantine_list = warp::path!("admin" / "quarantine")
+        .and(warp::get())
+        .and(with_daemon.clone())
+        .and_then(quarantine_list);
+
+    let quarantine_release = warp::path!("admin" / "quarantine" / String / "release")
+        .and(warp::post())
+        .and(request_origin(auth))
+        .and(with_daemon.clone())
+        .and_then(quarantine_release);
+
+    let flags_list = warp::path!("admin" / "flags")
+        .and(warp::get())
+        .and(with_daemon.clone())
+        .and_then(flags_list);
+
+    let flag_put = warp::path!("admin" / "flags" / String)
+        .and(warp::put())
+        .and(warp::body::json())
+        .and(request_origin(auth))
+        .and(with_daemon.clone())
+        .and_then(flag_put);
+
+    let flag_delete = warp::path!("admin" / "flags" / String)
+        .and(warp::delete())
+        .and(request_origin(auth))
+        .and(with_daemon.clone())
+        .and_then(flag_delete);
+
+    let quarantine_drop = warp::path!("admin" / "quarantine" / String)
+        .and(warp::delete())
+        .and(request_origin(auth))
+        .and(with_daemon)
+        .and_then(quarantine_drop);
+
+    ingest_failures
+        .or(purge)
+        .unify()
+        .or(reconnect)
+        .unify()
+        .or(replication_status)
+        .unify()
+        .or(promote)
+        .unify()
+        .or(usage)
+        .unify()
+        .or(shadow)
+        .unify()
+        .or(audit_verify)
+        .unify()
+        .or(audit_query)
+        .unify()
+        .or(rewrap)
+        .unify()
+        .or(log_level_get)
+        .unify()
+        .or(log_level_put)
+        .unify()
+        .or(quarantine_release)
+        .unify()
+        .or(quarantine_list)
+        .unify()
+        .or(quarantine_drop)
+        .unify()
+        .or(flags_list)
+        .unify()
+        .or(flag_put)
+        .unify()
+        .or(flag_delete)
+        .unify()
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/ingest-failures",
+    tag = "admin",
+    responses((status = 200, description = "Failure counts per upstream and kind, with redacted samples"))
+)]
+async fn ingest_failures(daemon: ComponentDaemon) -> Result<Response, Infallible> {
+    let failures = daemon.ingest_failures();
+    Ok(warp::reply::json(&serde_json::json!({
+        "total": failures.total(),
+        "counts": failures.counts(),
+        "samples": failures.samples(),
+    }))
+    .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/purge",
+    tag = "admin",
+    responses((status = 200, description = "Number of components purged"))
+)]
+async fn purge(origin: RequestOrigin, daemon: ComponentDaemon) -> Result<Response, Infallible> {
+    let purged = daemon.purge();
+    daemon.audit_log().record(
+        &origin,
+        "admin.purge",
+        serde_json::json!({ "purged": purged }),
+        "ok",
+    );
+    Ok(warp::reply::json(&serde_json::json!({ "purged": purged })).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/reconnect",
+    tag = "admin",
+    responses((status = 200, description = "Registry connection is being re-established"))
+)]
+async fn reconnect(origin: RequestOrigin, daemon: ComponentDaemon) -> Result<Response, Infallible> {
+    daemon.request_reconnect();
+    daemon
+        .audit_log()
+        .record(&origin, "admin.reconnect", serde_json::Value::Null, "ok");
+    Ok(warp::reply::json(&serde_json::json!({ "reconnecting": true })).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/replication",
+    tag = "admin",
+    responses((status = 200, description = "Replication role and follower state", body = ReplicationStatus))
+)]
+async fn replication_status(daemon: ComponentDaemon) -> Result<Response, Infallible> {
+    Ok(warp::reply::json(&daemon.replication().status()).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/replication/promote",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Follower promoted, now connecting to the registry"),
+        (status = 409, description = "Already a primary")
+    )
+)]
+async fn promote(origin: RequestOrigin, daemon: ComponentDaemon) -> Result<Response, Infallible> {
+    let promoted = replication::promote(&daemon, "requested through the admin API");
+    daemon.audit_log().record(
+        &origin,
+        "admin.replication.promote",
+        serde_json::Value::Null,
+        if promoted { "ok" } else { "error" },
+    );
+    Ok(if promoted {
+        warp::reply::json(&daemon.replication().status()).into_response()
+    } else {
+        json_error(StatusCode::CONFLICT, "This daemon is already a primary".to_string())
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/usage",
+    tag = "admin",
+    params(UsageQuery),
+    responses((status = 200, description = "Usage and quota limits per API key", body = [UsageReport]))
+)]
+async fn usage(query: UsageQuery, daemon: ComponentDaemon) -> Result<Response, Infallible> {
+    Ok(warp::reply::json(&daemon.quotas().report(query.key.as_deref())).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/shadow",
+    tag = "admin",
+    responses((status = 200, description = "Divergence between the active and candidate ingest rules", body = ShadowStats))
+)]
+async fn shadow(daemon: ComponentDaemon) -> Result<Response, Infallible> {
+    Ok(warp::reply::json(&daemon.shadow().stats()).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/audit",
+    tag = "admin",
+    params(AuditQuery),
+    responses((status = 200, description = "Most recent audit entries first", body = [AuditEntry]))
+)]
+async fn audit_query(query: AuditQuery, daemon: ComponentDaemon) -> Result<Response, Infallible> {
+    let entries = daemon
+        .audit_log()
+        .query(query.action.as_deref(), query.limit.unwrap_or(100));
+    Ok(warp::reply::json(&entries).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/audit/verify",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Hash chain verification result", body = AuditVerification),
+        (status = 500, description = "Audit log could not be read"),
+    )
+)]
+async fn audit_verify(daemon: ComponentDaemon) -> Result<Response, Infallible> {
+    Ok(match daemon.audit_log().verify() {
+        Ok(verification) => warp::reply::json(&verification).into_response(),
+        Err(e) => json_error(StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")),
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/encryption/rewrap",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Persisted journals re-encrypted with the active key"),
+        (status = 500, description = "Rewrap failed"),
+    )
+)]
+async fn rewrap(origin: RequestOrigin, daemon: ComponentDaemon) -> Result<Response, Infallible> {
+    let result = daemon.audit_log().rewrap();
+    if let Ok(count) = &result {
+        daemon.lifecycle().emit(
+            InternalEventKind::JournalPersisted,
+            Some("audit log rewrapped".to_string()),
+            Some(*count as u64),
+        );
+    }
+    daemon.audit_log().record(
+        &origin,
+        "admin.encryption.rewrap",
+        serde_json::json!({ "rewrapped": result.as_ref().ok() }),
+        if result.is_ok() { "ok" } else { "error" },
+    );
+    Ok(match result {
+        Ok(count) => {
+            warp::reply::json(&serde_json::json!({ "auditEntries": count })).into_response()
+        }
+        Err(e) => json_error(StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/log-level",
+    tag = "admin",
+    responses((status = 200, description = "Active tracing filter"))
+)]
+async fn log_level_get() -> Result<Response, Infallible> {
+    Ok(
+        warp::reply::json(&serde_json::json!({ "filter": logging::current_level() }))
+            .into_response(),
+    )
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/log-level",
+    tag = "admin",
+    request_body = LogLevelBody,
+    responses(
+        (status = 200, description = "Filter applied", body = LogLevelChange),
+        (status = 400, description = "Invalid filter directive"),
+    )
+)]
+async fn log_level_put(
+    body: LogLevelBody,
+    origin: RequestOrigin,
+    daemon: ComponentDaemon,
+) -> Result<Response, Infallible> {
+    let result = logging::set_level(
+        &body.filter,
+        body.ttl_seconds.map(std::time::Duration::from_secs),
+    );
+    daemon.audit_log().record(
+        &origin,
+        "admin.logLevel",
+        serde_json::json!({ "filter": body.filter, "ttlSeconds": body.ttl_seconds }),
+        if result.is_ok() { "ok" } else { "error" },
+    );
+    Ok(match result {
+        Ok(change) => warp::reply::json(&change).into_response(),
+        Err(e) => json_error(StatusCode::BAD_REQUEST, format!("{e:#}")),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/quarantine",
+    tag = "admin",
+    responses((status = 200, description = "Held components, payloads redacted", body = [QuarantinedComponent]))
+)]
+async fn quarantine_list(daemon: ComponentDaemon) -> Result<Response, Infallible> {
+    let mut entries = daemon.quarantine().list();
+    for entry in &mut entries {
+        entry.payload = daemon.redactor().redact_component(&entry.payload);
+    }
+    Ok(warp::reply::json(&entries).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/quarantine/{id}/release",
+    tag = "admin",
+    params(("id" = String, Path, description = "Component id")),
+    responses(
+        (status = 200, description = "Component re-ingested without signature check"),
+        (status = 404, description = "Nothing quarantined under this id"),
+        (status = 422, description = "Ingest rejected the component; it stays quarantined"),
+    )
+)]
+async fn quarantine_release(
+    id: String,
+    origin: RequestOrigin,
+    daemon: ComponentDaemon,
+) -> Result<Response, Infallible> {
+    let result = daemon.release_quarantined(&id).await;
+    daemon.audit_log().record(
+        &origin,
+        "admin.quarantine.release",
+        serde_json::json!({ "id": id }),
+        if result.is_ok() { "ok" } else { "error" },
+    );
+    Ok(match result {
+        Ok(()) => warp::reply::json(&serde_json::json!({ "released": id })).into_response(),
+        Err(e) if daemon.quarantine().get(&id).is_some() => json_error(StatusCode::UNPROCESSABLE_ENTITY, format!("{e:#}")),
+        Err(e) => json_error(StatusCode::NOT_FOUND, format!("{e:#}")),
+    })
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/quarantine/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Component id")),
+    responses(
+        (status = 204, description = "Quarantined component discarded"),
+        (status = 404, description = "Nothing quarantined under this id"),
+    )
+)]
+async fn quarantine_drop(
+    id: String,
+    origin: RequestOrigin,
+    daemon: ComponentDaemon,
+) -> Result<Response, Infallible> {
+    let dropped = daemon.quarantine().take(&id).is_some();
+    daemon.audit_log().record(
+        &origin,
+        "admin.quarantine.drop",
+        serde_json::json!({ "id": id }),
+        if dropped { "ok" } else { "error" },
+    );
+    Ok(if dropped {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        json_error(
+            StatusCode::NOT_FOUND,
+            format!("No quarantined component '{id}'"),
+        )
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/flags",
+    tag = "admin",
+    responses((status = 200, description = "Every feature flag with its value and where it came from", body = [FlagState]))
+)]
+async fn flags_list(daemon: ComponentDaemon) -> Result<Response, Infallible> {
+    Ok(warp::reply::json(&daemon.flags().list()).into_response())
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/flags/{name}",
+    tag = "admin",
+    params(("name" = String, Path, description = "Flag name, e.g. `dedup`")),
+    request_body = FlagBody,
+    responses(
+        (status = 200, description = "Override applied until cleared or restart", body = FlagState),
+        (status = 404, description = "Unknown flag"),
+    )
+)]
+async fn flag_put(
+    name: String,
+    body: FlagBody,
+    origin: RequestOrigin,
+    daemon: ComponentDaemon,
+) -> Result<Response, Infallible> {
+    let Some(flag) = Flag::parse(&name) else {
+        return Ok(unknown_flag(&name));
+    };
+    let state = daemon.flags().set_override(flag, body.enabled);
+    daemon.audit_log().record(
+        &origin,
+        "admin.flags.override",
+        serde_json::json!({ "flag": flag.name(), "enabled": body.enabled }),
+        "ok",
+    );
+    Ok(warp::reply::json(&state).into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/flags/{name}",
+    tag = "admin",
+    params(("name" = String, Path, description = "Flag name, e.g. `dedup`")),
+    responses(
+        (status = 200, description = "Override cleared; the file or default value applies again", body = FlagState),
+        (status = 404, description = "Unknown flag"),
+    )
+)]
+async fn flag_delete(
+    name: String,
+    origin: RequestOrigin,
+    daemon: ComponentDaemon,
+) -> Result<Response, Infallible> {
+    let Some(flag) = Flag::parse(&name) else {
+        return Ok(unknown_flag(&name));
+    };
+    let state = daemon.flags().clear_override(flag);
+    daemon.audit_log().record(
+        &origin,
+        "admin.flags.clear",
+        serde_json::json!({ "flag": flag.name() }),
+        "ok",
+    );
+    Ok(warp::reply::json(&state).into_response())
+}
+
+fn unknown_flag(name: &str) -> Response {
+    let known: Vec<&str> = Flag::ALL.iter().map(Flag::name).collect();
+    json_error(
+        StatusCode::NOT_FOUND,
+        format!("Unknown feature flag '{name}', expected one of {}", known.join(", ")),
+    )
+}
+
+fn json_error(status: StatusCode, message: String) -> Response {
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "error": message })),
+        status,
+    )
+    .into_response()
+}
@@ -0,0 +1,121 @@
+use std::convert::Infallible;
+
+use async_graphql::{Context, Error, Object, Schema, Subscription};
+use async_stream::stream;
+use tokio::sync::broadcast;
+use tracing::warn;
+use warp::Filter;
+
+use crate::audit::{MutationAudit, RequestOrigin};
+use crate::errors::{graphql_error, missing_daemon, ErrorCode};
+use crate::forms::FormSubmission;
+use crate::lifecycle::{InternalEvent, InternalEventKind};
+use crate::listeners::BearerAuth;
+use crate::logging::{self, LogLevelChange};
+use crate::subscribers::Subscriber;
+use crate::{request_origin, ComponentDaemon, DaemonStats};
+
+// ========================
+// ADMIN GRAPHQL
+// ========================
+
+// Operational fields, served at /admin/graphql so they only answer on the
+// admin listener behind ADMIN_AUTH_*, never on the renderer-facing /graphql
+pub type AdminSchema = Schema<AdminQuery, AdminMutation, AdminSubscription>;
+
+pub fn schema(daemon: &ComponentDaemon) -> AdminSchema {
+    Schema::build(AdminQuery, AdminMutation, AdminSubscription)
+        .data(daemon.clone())
+        .extension(MutationAudit::new(daemon.audit_log_handle(), daemon.redactor.clone()))
+        .finish()
+}
+
+// Queries and mutations over POST or GET, internalEvents over a websocket
+pub fn routes(schema: AdminSchema, auth: &BearerAuth) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let subscriptions = warp::path!("admin" / "graphql").and(async_graphql_warp::graphql_subscription(schema.clone()));
+    let requests = warp::path!("admin" / "graphql")
+        .and(async_graphql_warp::graphql(schema))
+        .and(request_origin(auth))
+        .and_then(|(schema, request): (AdminSchema, async_graphql::Request), origin: RequestOrigin| async move {
+            let response = schema.execute(request.data(origin)).await;
+            Ok::<_, Infallible>(async_graphql_warp::GraphQLResponse::from(response))
+        });
+    subscriptions.or(requests)
+}
+
+pub struct AdminQuery;
+
+#[Object]
+impl AdminQuery {
+    // Renderers that identified themselves with a clientId, with their
+    // delivery cursor and recent connections
+    async fn subscribers(&self, ctx: &Context<'_>) -> Result<Vec<Subscriber>, Error> {
+        let daemon = ctx.data::<ComponentDaemon>().map_err(|_| missing_daemon())?;
+        Ok(daemon.subscribers().list())
+    }
+
+    async fn stats(&self, ctx: &Context<'_>) -> Result<DaemonStats, Error> {
+        let daemon = ctx.data::<ComponentDaemon>().map_err(|_| missing_daemon())?;
+        Ok(DaemonStats {
+            latency: daemon.latency().stats(),
+            last_registry_error: daemon.registry_errors.last(),
+        })
+    }
+
+    // Recent submissions kept in memory, newest first, with values passed
+    // through REDACTION_RULES_PATH
+    async fn form_submissions(&self, ctx: &Context<'_>, form_id: Option<String>) -> Result<Vec<FormSubmission>, Error> {
+        let daemon = ctx.data::<ComponentDaemon>().map_err(|_| missing_daemon())?;
+        let mut submissions = daemon.form_submissions().list(form_id.as_deref());
+        for submission in &mut submissions {
+            submission.values = daemon.redactor().redact_submission(&submission.values);
+        }
+        Ok(submissions)
+    }
+}
+
+pub struct AdminMutation;
+
+#[Object]
+impl AdminMutation {
+    // Swaps the tracing filter, optionally reverting after `ttl_seconds`.
+    async fn set_log_level(&self, filter: String, ttl_seconds: Option<u64>) -> Result<LogLevelChange, Error> {
+        logging::set_level(&filter, ttl_seconds.map(std::time::Duration::from_secs))
+            .map_err(|e| graphql_error(ErrorCode::InvalidArgument, format!("{e:#}"), None))
+    }
+}
+
+pub struct AdminSubscription;
+
+#[Subscription]
+impl AdminSubscription {
+    // Health-state transitions for monitoring: registry connects and
+    // disconnects, reconnect backoff, journal rewrites and eviction runs
+    async fn internal_events(
+        &self,
+        ctx: &Context<'_>,
+        kinds: Option<Vec<InternalEventKind>>,
+    ) -> Result<impl futures::Stream<Item = InternalEvent>, Error> {
+        let daemon = ctx.data::<ComponentDaemon>().map_err(|_| missing_daemon())?;
+
+        let mut receiver = daemon.lifecycle().subscribe();
+
+        let stream = stream! {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        if kinds.as_ref().is_none_or(|kinds| kinds.contains(&event.kind)) {
+                            yield event;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("🐢 Daemon: Internal event subscriber lagged, skipped {} events", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        };
+
+        Ok(stream)
+    }
+}
@@ -0,0 +1,216 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use tracing::info;
+
+// ========================
+// ENCRYPTION AT REST
+// ========================
+
+const SEALED_PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+
+// AES-256-GCM over individual records: one line of the audit journal or the
+// sink dead letters, the whole subscriber registry, or one blob. Sealed records
+// look like `enc:v1:<keyId>:<base64 nonce||ciphertext>`; anything without the
+// prefix is read as plaintext so existing files keep loading after enabling.
+pub struct AtRestCipher {
+    // The first key encrypts; the rest only decrypt until files are rewrapped.
+    keys: Vec<(String, Aes256Gcm)>,
+}
+
+impl AtRestCipher {
+    // AT_REST_KEYS: comma-separated `keyId=<base64 32-byte key>`, active key
+    // first. AT_REST_KEYS_FILE reads the same list from a file instead, e.g. a
+    // secret mounted by a KMS agent.
+    pub fn from_env() -> Result<Self> {
+        let raw = match std::env::var("AT_REST_KEYS_FILE") {
+            Ok(path) => std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read at-rest key file {path}"))?,
+            Err(_) => std::env::var("AT_REST_KEYS").unwrap_or_default(),
+        };
+        Self::parse(&raw)
+    }
+
+    // An empty list leaves records in plaintext
+    pub fn parse(raw: &str) -> Result<Self> {
+        let mut keys = Vec::new();
+        for entry in raw.split([',', '\n']).map(str::trim).filter(|e| !e.is_empty()) {
+            let (key_id, encoded) = entry
+                .split_once('=')
+                .filter(|(id, key)| !id.is_empty() && !key.is_empty())
+                .ok_or_else(|| anyhow!("At-rest keys must be `keyId=<base64 key>`"))?;
+            if key_id.contains(':') {
+                return Err(anyhow!("At-rest key id '{key_id}' must not contain ':'"));
+            }
+            let bytes = BASE64
+                .decode(encoded)
+                .with_context(|| format!("At-rest key {key_id} is not valid base64"))?;
+            if bytes.len() != 32 {
+                return Err(anyhow!("At-rest key {key_id} must be 32 bytes"));
+            }
+            let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&bytes));
+            keys.push((key_id.to_string(), cipher));
+        }
+
+        if let Some((active, _)) = keys.first() {
+            info!(
+                "🔐 Daemon: Encryption at rest enabled with key '{}' ({} key(s) loaded)",
+                active,
+                keys.len()
+            );
+        }
+        Ok(Self { keys })
+    }
+
+    pub fn active_key(&self) -> Option<&str> {
+        self.keys.first().map(|(id, _)| id.as_str())
+    }
+
+    pub fn seal(&self, plaintext: &str) -> Result<String> {
+        Ok(self.encrypt(plaintext.as_bytes())?.unwrap_or_else(|| plaintext.to_string()))
+    }
+
+    pub fn open(&self, record: &str) -> Result<String> {
+        match record.strip_prefix(SEALED_PREFIX) {
+            Some(sealed) => Ok(String::from_utf8(self.decrypt(sealed)?)?),
+            None => Ok(record.to_string()),
+        }
+    }
+
+    // Whole files such as blobs; the sealed form is the same text record
+    pub fn seal_bytes(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        Ok(self.encrypt(plaintext)?.map_or_else(|| plaintext.to_vec(), String::into_bytes))
+    }
+
+    pub fn open_bytes(&self, record: &[u8]) -> Result<Vec<u8>> {
+        match record.strip_prefix(SEALED_PREFIX.as_bytes()) {
+            Some(sealed) => self.decrypt(std::str::from_utf8(sealed).context("Malformed encrypted record")?),
+            None => Ok(record.to_vec()),
+        }
+    }
+
+    // None without keys, so callers keep the plaintext
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Option<String>> {
+        let Some((key_id, cipher)) = self.keys.first() else {
+            return Ok(None);
+        };
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let mut sealed = nonce.to_vec();
+        sealed.extend(
+            cipher
+                .encrypt(&nonce, plaintext)
+                .map_err(|_| anyhow!("Encryption with key {key_id} failed"))?,
+        );
+        Ok(Some(format!("{SEALED_PREFIX}{key_id}:{}", BASE64.encode(sealed))))
+    }
+
+    // `sealed` is a record with the prefix already stripped
+    fn decrypt(&self, sealed: &str) -> Result<Vec<u8>> {
+        let (key_id, encoded) = sealed
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Malformed encrypted record"))?;
+        let (_, cipher) = self
+            .keys
+            .iter()
+            .find(|(id, _)| id == key_id)
+            .ok_or_else(|| anyhow!("Record is encrypted with unknown key '{key_id}'"))?;
+
+        let bytes = BASE64.decode(encoded).context("Encrypted record is not valid base64")?;
+        if bytes.len() < NONCE_LEN {
+            return Err(anyhow!("Encrypted record is truncated"));
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("Record failed authentication with key '{key_id}'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> String {
+        BASE64.encode([byte; 32])
+    }
+
+    fn keyring(entries: &[(&str, u8)]) -> AtRestCipher {
+        let raw: Vec<String> = entries.iter().map(|(id, byte)| format!("{id}={}", key(*byte))).collect();
+        AtRestCipher::parse(&raw.join(",")).unwrap()
+    }
+
+    #[test]
+    fn round_trips_a_record() {
+        let cipher = keyring(&[("k1", 1)]);
+        let record = r#"{"id":"c1","data":{"title":"Hello"}}"#;
+        let sealed = cipher.seal(record).unwrap();
+        assert!(sealed.starts_with("enc:v1:k1:"));
+        assert!(!sealed.contains("Hello"));
+        assert_eq!(cipher.open(&sealed).unwrap(), record);
+
+        // Fresh nonce per record
+        assert_ne!(cipher.seal(record).unwrap(), sealed);
+    }
+
+    #[test]
+    fn round_trips_binary_files() {
+        let cipher = keyring(&[("k1", 1)]);
+        let blob = [0u8, 159, 146, 150, 255];
+        let sealed = cipher.seal_bytes(&blob).unwrap();
+        assert!(sealed.starts_with(b"enc:v1:k1:"));
+        assert_eq!(cipher.open_bytes(&sealed).unwrap(), blob);
+
+        // Blobs written before encryption was enabled still read back
+        assert_eq!(cipher.open_bytes(&blob).unwrap(), blob);
+        assert_eq!(AtRestCipher::parse("").unwrap().seal_bytes(&blob).unwrap(), blob);
+    }
+
+    #[test]
+    fn reads_plaintext_and_writes_it_without_keys() {
+        let record = r#"{"id":"c1"}"#;
+        assert_eq!(keyring(&[("k1", 1)]).open(record).unwrap(), record);
+
+        let disabled = AtRestCipher::parse("").unwrap();
+        assert_eq!(disabled.active_key(), None);
+        assert_eq!(disabled.seal(record).unwrap(), record);
+    }
+
+    #[test]
+    fn rotated_keys_still_open_older_records() {
+        let old = keyring(&[("k1", 1)]);
+        let sealed = old.seal("before rotation").unwrap();
+
+        let rotated = keyring(&[("k2", 2), ("k1", 1)]);
+        assert_eq!(rotated.active_key(), Some("k2"));
+        assert_eq!(rotated.open(&sealed).unwrap(), "before rotation");
+        assert!(rotated.seal("after rotation").unwrap().starts_with("enc:v1:k2:"));
+
+        // Once the old key is dropped its records no longer open
+        let retired = keyring(&[("k2", 2)]);
+        assert!(retired.open(&sealed).is_err());
+    }
+
+    #[test]
+    fn rejects_a_tampered_record() {
+        let cipher = keyring(&[("k1", 1)]);
+        let sealed = cipher.seal("payload").unwrap();
+        let (prefix, encoded) = sealed.rsplit_once(':').unwrap();
+        let mut bytes = BASE64.decode(encoded).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        let tampered = format!("{prefix}:{}", BASE64.encode(bytes));
+        assert!(cipher.open(&tampered).is_err());
+
+        // Same key id, different key material
+        assert!(keyring(&[("k1", 9)]).open(&sealed).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_key_lists() {
+        assert!(AtRestCipher::parse("k1").is_err());
+        assert!(AtRestCipher::parse("k1=short").is_err());
+        assert!(AtRestCipher::parse(&format!("a:b={}", key(1))).is_err());
+    }
+}
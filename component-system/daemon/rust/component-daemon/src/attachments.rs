@@ -0,0 +1,221 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use dashmap::DashMap;
+use hyper::body::HttpBody;
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Uri};
+use hyper_tls::HttpsConnector;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::{info, warn};
+use url::Url;
+
+use crate::blobs::BlobStore;
+use crate::config::env_parse;
+use crate::metrics::Metrics;
+
+// ========================
+// ATTACHMENTS
+// ========================
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AttachmentRef {
+    hash: String,
+    content_type: String,
+    bytes: usize,
+    href: String,
+}
+
+// Lets renderers load images and documents from the daemon instead of the
+// origin. String values under ATTACHMENT_FIELDS keys anywhere in a
+// component's data are fetched once if they fall under one of the
+// ATTACHMENT_ORIGINS base URLs (same scheme, host and port, and a path below
+// the base's; unset disables this), stored in the blob store, and listed
+// under `_attachments`, keyed by the original URL. Fetching happens off the
+// ingest path: a component goes out with the assets already stored, and a new
+// version follows once the rest arrive. Assets over ATTACHMENT_MAX_BYTES or
+// slower than ATTACHMENT_TIMEOUT_MS are left alone.
+pub struct Attachments {
+    origins: Vec<Url>,
+    fields: HashSet<String>,
+    max_bytes: usize,
+    timeout: Duration,
+    client: Client<HttpsConnector<HttpConnector>, Body>,
+    // Source URL → where it was stored
+    fetched: DashMap<String, AttachmentRef>,
+    metrics: Arc<Metrics>,
+}
+
+impl Attachments {
+    pub fn from_env(metrics: Arc<Metrics>) -> Result<Self> {
+        let list = |name: &str, default: &str| -> Vec<String> {
+            std::env::var(name)
+                .unwrap_or_else(|_| default.to_string())
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect()
+        };
+        let origins = list("ATTACHMENT_ORIGINS", "")
+            .iter()
+            .map(|origin| Url::parse(origin).with_context(|| format!("Invalid ATTACHMENT_ORIGINS entry '{origin}'")))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            origins,
+            fields: list("ATTACHMENT_FIELDS", "image,imageUrl,icon,attachment,document,src")
+                .into_iter()
+                .collect(),
+            max_bytes: env_parse("ATTACHMENT_MAX_BYTES", 10 * 1024 * 1024),
+            timeout: Duration::from_millis(env_parse("ATTACHMENT_TIMEOUT_MS", 5000)),
+            client: Client::builder().build(HttpsConnector::new()),
+            fetched: DashMap::new(),
+            metrics,
+        })
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.origins.is_empty()
+    }
+
+    // Adds `_attachments` for every referenced asset already in the blob
+    // store, without fetching. Returns the ones that still need fetching.
+    pub fn attach_stored(&self, data: &mut Value, blobs: &BlobStore) -> Vec<(String, Url)> {
+        if !self.enabled() || !data.is_object() {
+            return Vec::new();
+        }
+        let mut urls = Vec::new();
+        self.collect(data, &mut urls);
+        urls.sort();
+        urls.dedup();
+
+        let mut attachments = serde_json::Map::new();
+        let mut missing = Vec::new();
+        for (raw, url) in urls {
+            match self.fetched.get(&raw).filter(|stored| blobs.contains(&stored.hash)) {
+                Some(stored) => {
+                    self.count("cached");
+                    if let Ok(value) = serde_json::to_value(stored.value()) {
+                        attachments.insert(raw, value);
+                    }
+                }
+                None => missing.push((raw, url)),
+            }
+        }
+        if !attachments.is_empty() {
+            data["_attachments"] = Value::Object(attachments);
+        }
+        missing
+    }
+
+    // Fetches and stores `urls`; `attach_stored` picks up whatever succeeded
+    pub async fn fetch_all(&self, urls: Vec<(String, Url)>, blobs: &BlobStore) {
+        futures::future::join_all(urls.iter().map(|(raw, url)| self.store(raw, url, blobs))).await;
+    }
+
+    fn collect(&self, value: &Value, urls: &mut Vec<(String, Url)>) {
+        match value {
+            Value::Object(fields) => {
+                for (key, value) in fields {
+                    match value {
+                        Value::String(raw) if self.fields.contains(key) => {
+                            if let Some(url) = self.allowed(raw) {
+                                urls.push((raw.clone(), url));
+                            }
+                        }
+                        // Our own listing, not component data
+                        _ if key == "_attachments" => {}
+                        _ => self.collect(value, urls),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| self.collect(item, urls)),
+            _ => {}
+        }
+    }
+
+    // Compared on the parsed URL, so "https://cdn.example.com" doesn't let
+    // through "https://cdn.example.com.attacker.net" or a "user@host" trick
+    fn allowed(&self, raw: &str) -> Option<Url> {
+        let url = Url::parse(raw).ok()?;
+        if !url.username().is_empty() || url.password().is_some() {
+            return None;
+        }
+        self.origins
+            .iter()
+            .any(|origin| {
+                url.scheme() == origin.scheme()
+                    && url.host_str() == origin.host_str()
+                    && url.port_or_known_default() == origin.port_or_known_default()
+                    && within(origin.path(), url.path())
+            })
+            .then_some(url)
+    }
+
+    async fn store(&self, raw: &str, url: &Url, blobs: &BlobStore) {
+        // Another component may have fetched it meanwhile
+        if self.fetched.get(raw).is_some_and(|stored| blobs.contains(&stored.hash)) {
+            return;
+        }
+        match self.fetch(url.as_str()).await {
+            Ok((content_type, bytes)) => {
+                let size = bytes.len();
+                let hash = blobs.put(&content_type, bytes.into());
+                info!("📎 Daemon: Stored attachment {} ({} bytes) as {}", url, size, hash);
+                let stored = AttachmentRef {
+                    href: format!("/blobs/{hash}"),
+                    hash,
+                    content_type,
+                    bytes: size,
+                };
+                self.fetched.insert(raw.to_string(), stored);
+                self.count("fetched");
+            }
+            Err(e) => {
+                warn!("📎 Daemon: Failed to fetch attachment {}: {:#}", url, e);
+                self.count("failed");
+            }
+        }
+    }
+
+    async fn fetch(&self, url: &str) -> Result<(String, Vec<u8>)> {
+        let uri: Uri = url.parse()?;
+        let download = async {
+            let response = self.client.get(uri).await?;
+            if !response.status().is_success() {
+                bail!("origin responded with {}", response.status());
+            }
+            let content_type = response
+                .headers()
+                .get(hyper::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or("application/octet-stream")
+                .to_string();
+            let mut body = response.into_body();
+            let mut bytes = Vec::new();
+            while let Some(chunk) = body.data().await {
+                bytes.extend_from_slice(&chunk?);
+                if bytes.len() > self.max_bytes {
+                    bail!("larger than {} bytes", self.max_bytes);
+                }
+            }
+            Ok((content_type, bytes))
+        };
+        tokio::time::timeout(self.timeout, download)
+            .await
+            .map_err(|_| anyhow!("no response within {:?}", self.timeout))?
+    }
+
+    fn count(&self, outcome: &'static str) {
+        *self.metrics.attachments.entry(outcome).or_insert(0) += 1;
+    }
+}
+
+// `path` is `base` or below it, on a segment boundary
+fn within(base: &str, path: &str) -> bool {
+    let base = base.trim_end_matches('/');
+    path == base || path.strip_prefix(base).is_some_and(|rest| rest.starts_with('/'))
+}
@@ -0,0 +1,736 @@
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextParseQuery, NextRequest,
+};
+use async_graphql::parser::types::{ExecutableDocument, OperationType, Selection};
+use async_graphql::{Response, ServerError, ServerResult, Value, Variables};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use tracing::{error, info, warn};
+
+use crate::at_rest::AtRestCipher;
+use crate::blobs::{BackendStatus, DegradationPolicy};
+use crate::config::env_parse;
+use crate::redaction::Redactor;
+
+// ========================
+// AUDIT LOG
+// ========================
+
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// Who issued a request and from where, attached to GraphQL requests and
+// passed explicitly by admin routes. `actor` is the authenticated subject;
+// the client's own `x-actor` claim is only kept next to it, unverified.
+#[derive(Clone, Debug)]
+pub struct RequestOrigin {
+    pub actor: String,
+    pub claimed_actor: Option<String>,
+    pub source: String,
+}
+
+impl RequestOrigin {
+    pub fn new(actor: Option<String>, claimed_actor: Option<String>, source: Option<std::net::SocketAddr>) -> Self {
+        Self {
+            actor: actor.unwrap_or_else(|| "anonymous".to_string()),
+            claimed_actor,
+            source: source
+                .map(|addr| addr.to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub actor: String,
+    // `x-actor` as sent by the client; not verified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claimed_actor: Option<String>,
+    pub source: String,
+    pub action: String,
+    pub details: serde_json::Value,
+    pub outcome: String,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    // The hash covers every field except itself, chaining to the previous entry
+    // so edits or deletions anywhere in the log break verification.
+    fn compute_hash(&self) -> String {
+        let mut body = serde_json::json!({
+            "seq": self.seq,
+            "timestamp": self.timestamp,
+            "actor": self.actor,
+            "source": self.source,
+            "action": self.action,
+            "details": self.details,
+            "outcome": self.outcome,
+            "prevHash": self.prev_hash,
+        });
+        // Only hashed when present, so entries written before it still verify
+        if let Some(claimed) = &self.claimed_actor {
+            body["claimedActor"] = serde_json::json!(claimed);
+        }
+        let digest = Sha256::digest(body.to_string().as_bytes());
+        digest.iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditVerification {
+    pub valid: bool,
+    pub entries: u64,
+    pub first_invalid_seq: Option<u64>,
+}
+
+struct ChainHead {
+    seq: u64,
+    hash: String,
+    recent: VecDeque<AuditEntry>,
+    // Entries the journal didn't take, oldest first, while degraded
+    pending: VecDeque<AuditEntry>,
+    pending_bytes: u64,
+    since: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+pub struct AuditLog {
+    path: Option<PathBuf>,
+    cipher: Arc<AtRestCipher>,
+    capacity: usize,
+    head: Mutex<ChainHead>,
+    policy: DegradationPolicy,
+    retry_every: Duration,
+    pending_capacity: usize,
+    degraded: AtomicBool,
+    // Entries refused because AUDIT_PENDING_MAX was reached
+    pending_dropped: AtomicU64,
+    // Set by `spawn_writer`; appends then happen on its thread so the fsync
+    // never blocks the runtime
+    writer: OnceLock<mpsc::Sender<AuditEntry>>,
+    // Held while appending, verifying or rewrapping the file
+    file: Mutex<()>,
+}
+
+impl AuditLog {
+    // AUDIT_LOG_PATH enables the append-only JSONL file; without it the log only
+    // lives in memory. AUDIT_LOG_MEMORY bounds how many entries stay queryable.
+    // Lines are sealed with `cipher` when encryption at rest is configured.
+    // When an append fails, AUDIT_DEGRADATION (memory, unready or off; default
+    // memory) holds it and every later entry in memory, up to
+    // AUDIT_PENDING_MAX (default 10000), and retries every AUDIT_RETRY_SECS
+    // (default 10) so the file keeps an unbroken chain. Once that many are
+    // pending, new entries are refused before they join the chain, readiness
+    // fails and GraphQL mutations are turned away.
+    pub fn from_env(cipher: Arc<AtRestCipher>) -> Result<Self> {
+        let path = std::env::var("AUDIT_LOG_PATH").ok().map(PathBuf::from);
+        let capacity = env_parse("AUDIT_LOG_MEMORY", 1000).max(1);
+
+        let mut head = ChainHead {
+            seq: 0,
+            hash: GENESIS_HASH.to_string(),
+            recent: VecDeque::new(),
+            pending: VecDeque::new(),
+            pending_bytes: 0,
+            since: None,
+            last_error: None,
+        };
+
+        // Resume the chain from an existing file
+        if let Some(path) = path.as_ref().filter(|p| p.exists()) {
+            for entry in read_entries(path, &cipher)
+                .with_context(|| format!("Failed to load audit log {}", path.display()))?
+            {
+                head.seq = entry.seq;
+                head.hash = entry.hash.clone();
+                if head.recent.len() == capacity {
+                    head.recent.pop_front();
+                }
+                head.recent.push_back(entry);
+            }
+            info!("📜 Daemon: Resumed audit log {} at seq {}", path.display(), head.seq);
+        }
+
+        Ok(Self {
+            path,
+            cipher,
+            capacity,
+            head: Mutex::new(head),
+            policy: DegradationPolicy::from_var("AUDIT_DEGRADATION"),
+            retry_every: Duration::from_secs(env_parse("AUDIT_RETRY_SECS", 10).max(1)),
+            pending_capacity: env_parse("AUDIT_PENDING_MAX", 10_000usize),
+            degraded: AtomicBool::new(false),
+            pending_dropped: AtomicU64::new(0),
+            writer: OnceLock::new(),
+            file: Mutex::new(()),
+        })
+    }
+
+    pub fn record(
+        &self,
+        origin: &RequestOrigin,
+        action: &str,
+        details: serde_json::Value,
+        outcome: &str,
+    ) {
+        let mut head = self.head.lock().unwrap();
+        // A refused entry never gets a seq, so the chain stays unbroken
+        if self.is_full(&head) {
+            self.pending_dropped.fetch_add(1, Ordering::Relaxed);
+            error!(
+                "❌ Daemon: Pending audit entries are full, refusing {} by {} from {}",
+                action, origin.actor, origin.source
+            );
+            return;
+        }
+
+        let mut entry = AuditEntry {
+            seq: head.seq + 1,
+            timestamp: Utc::now(),
+            actor: origin.actor.clone(),
+            claimed_actor: origin.claimed_actor.clone(),
+            source: origin.source.clone(),
+            action: action.to_string(),
+            details,
+            outcome: outcome.to_string(),
+            prev_hash: head.hash.clone(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+
+        if let Some(path) = &self.path {
+            // Sent under the lock so the writer sees entries in chain order.
+            // Once degraded, entries queue behind the pending ones so the
+            // file never skips a link.
+            if let Some(writer) = self.writer.get() {
+                let _ = writer.send(entry.clone());
+            } else if self.is_degraded() {
+                self.hold(&mut head, &entry);
+            } else if let Err(e) = append_line(path, &self.cipher, &entry) {
+                self.append_failed(&mut head, &entry, &e);
+            }
+        }
+
+        info!(
+            "📜 Daemon: Audit #{} {} by {} from {} ({})",
+            entry.seq, entry.action, entry.actor, entry.source, entry.outcome
+        );
+
+        head.seq = entry.seq;
+        head.hash = entry.hash.clone();
+        if head.recent.len() == self.capacity {
+            head.recent.pop_front();
+        }
+        head.recent.push_back(entry);
+    }
+
+    fn append_failed(&self, head: &mut ChainHead, entry: &AuditEntry, e: &anyhow::Error) {
+        if self.policy == DegradationPolicy::Off {
+            error!("❌ Daemon: Failed to append audit entry {}: {:#}", entry.seq, e);
+            return;
+        }
+        head.last_error = Some(format!("{e:#}"));
+        if !self.degraded.swap(true, Ordering::Relaxed) {
+            head.since = Some(Utc::now());
+            error!("❌ Daemon: Audit journal degraded, holding entries in memory until it recovers: {:#}", e);
+        }
+        self.hold(head, entry);
+    }
+
+    fn hold(&self, head: &mut ChainHead, entry: &AuditEntry) {
+        head.pending_bytes += serde_json::to_string(entry).map_or(0, |line| line.len() as u64);
+        head.pending.push_back(entry.clone());
+    }
+
+    fn is_full(&self, head: &ChainHead) -> bool {
+        self.path.is_some() && head.pending.len() >= self.pending_capacity
+    }
+
+    // False while new entries would be refused
+    pub fn accepts(&self) -> bool {
+        !self.is_full(&self.head.lock().unwrap())
+    }
+
+    // Appends one entry on the writer thread
+    fn journal(&self, path: &PathBuf, entry: &AuditEntry) {
+        let mut head = self.head.lock().unwrap();
+        if self.is_degraded() {
+            self.hold(&mut head, entry);
+            return;
+        }
+        // Only the writer appends while healthy, so the file lock is enough
+        drop(head);
+        let appended = {
+            let _file = self.file.lock().unwrap();
+            append_line(path, &self.cipher, entry)
+        };
+        if let Err(e) = appended {
+            self.append_failed(&mut self.head.lock().unwrap(), entry, &e);
+        }
+    }
+
+    pub fn spawn_writer(self: &Arc<Self>) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        let (tx, rx) = mpsc::channel::<AuditEntry>();
+        if self.writer.set(tx).is_err() {
+            return;
+        }
+        // Ends once the log, and with it the sender, is dropped
+        let log = Arc::downgrade(self);
+        std::thread::spawn(move || {
+            for entry in rx {
+                let Some(log) = log.upgrade() else {
+                    break;
+                };
+                log.journal(&path, &entry);
+            }
+        });
+    }
+
+    // Appends pending entries, oldest first; stays degraded at the first
+    // failure. Returns how many were written.
+    fn reconcile(&self, path: &PathBuf) -> usize {
+        let mut head = self.head.lock().unwrap();
+        let mut written = 0;
+        while let Some(entry) = head.pending.front() {
+            if let Err(e) = append_line(path, &self.cipher, entry) {
+                head.last_error = Some(format!("{e:#}"));
+                return written;
+            }
+            let line = serde_json::to_string(entry).map_or(0, |line| line.len() as u64);
+            head.pending.pop_front();
+            head.pending_bytes = head.pending_bytes.saturating_sub(line);
+            written += 1;
+        }
+        self.degraded.store(false, Ordering::Relaxed);
+        head.since = None;
+        head.last_error = None;
+        written
+    }
+
+    pub fn spawn_recovery(self: &Arc<Self>) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        if self.policy == DegradationPolicy::Off {
+            return;
+        }
+        let log = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(log.retry_every);
+            loop {
+                ticker.tick().await;
+                if !log.is_degraded() {
+                    continue;
+                }
+                let log = log.clone();
+                let path = path.clone();
+                // File writes block, so they stay off the runtime threads
+                let Ok(written) = tokio::task::spawn_blocking(move || (log.reconcile(&path), log.is_degraded())).await
+                else {
+                    continue;
+                };
+                match written {
+                    (written, false) => info!("📜 Daemon: Audit journal recovered, wrote back {} pending entries", written),
+                    (written, true) if written > 0 => {
+                        warn!("⚠️ Daemon: Audit journal still degraded after writing back {} entries", written)
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    // Whether readiness should fail while degraded, or at any time new
+    // entries are being refused
+    pub fn blocks_readiness(&self) -> bool {
+        (self.policy == DegradationPolicy::Unready && self.is_degraded()) || !self.accepts()
+    }
+
+    pub fn status(&self) -> BackendStatus {
+        let head = self.head.lock().unwrap();
+        BackendStatus {
+            degraded: self.is_degraded(),
+            since: head.since,
+            last_error: head.last_error.clone(),
+            pending_writes: head.pending.len(),
+            pending_bytes: head.pending_bytes,
+        }
+    }
+
+    pub fn query(&self, action: Option<&str>, limit: usize) -> Vec<AuditEntry> {
+        let head = self.head.lock().unwrap();
+        head.recent
+            .iter()
+            .rev()
+            .filter(|entry| action.is_none_or(|a| entry.action == a))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    // Verifies the full file when persistence is on, otherwise the in-memory tail.
+    pub fn verify(&self) -> Result<AuditVerification> {
+        let (entries, mut prev_hash): (Vec<AuditEntry>, String) = match &self.path {
+            Some(path) if path.exists() => {
+                let _file = self.file.lock().unwrap();
+                (read_entries(path, &self.cipher)?, GENESIS_HASH.to_string())
+            }
+            _ => {
+                // The memory tail may start mid-chain, so trust its first link
+                let entries: Vec<AuditEntry> =
+                    self.head.lock().unwrap().recent.iter().cloned().collect();
+                let first = entries
+                    .first()
+                    .map(|e| e.prev_hash.clone())
+                    .unwrap_or_else(|| GENESIS_HASH.to_string());
+                (entries, first)
+            }
+        };
+
+        for entry in &entries {
+            if entry.prev_hash != prev_hash || entry.compute_hash() != entry.hash {
+                return Ok(AuditVerification {
+                    valid: false,
+                    entries: entries.len() as u64,
+                    first_invalid_seq: Some(entry.seq),
+                });
+            }
+            prev_hash = entry.hash.clone();
+        }
+
+        Ok(AuditVerification {
+            valid: true,
+            entries: entries.len() as u64,
+            first_invalid_seq: None,
+        })
+    }
+
+    // Re-encrypts the whole file with the active key (or decrypts it when
+    // encryption was turned off) so retired keys can be dropped. The record
+    // lock is held throughout so no append lands in the old file.
+    pub fn rewrap(&self) -> Result<usize> {
+        let _head = self.head.lock().unwrap();
+        let _file = self.file.lock().unwrap();
+        let Some(path) = self.path.as_ref().filter(|p| p.exists()) else {
+            return Ok(0);
+        };
+
+        let entries = read_entries(path, &self.cipher)?;
+        let tmp = path.with_extension("rewrap");
+        {
+            let mut file = std::fs::File::create(&tmp)?;
+            for entry in &entries {
+                writeln!(file, "{}", self.cipher.seal(&serde_json::to_string(entry)?)?)?;
+            }
+            file.sync_all()?;
+        }
+        std::fs::rename(&tmp, path)?;
+
+        info!(
+            "🔐 Daemon: Rewrapped {} audit entries with key {}",
+            entries.len(),
+            self.cipher.active_key().unwrap_or("<none>")
+        );
+        Ok(entries.len())
+    }
+}
+
+fn read_entries(path: &PathBuf, cipher: &AtRestCipher) -> Result<Vec<AuditEntry>> {
+    let file = std::fs::File::open(path)?;
+    BufReader::new(file)
+        .lines()
+        .map(|line| Ok(serde_json::from_str(&cipher.open(&line?)?)?))
+        .collect()
+}
+
+fn append_line(path: &PathBuf, cipher: &AtRestCipher, entry: &AuditEntry) -> Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", cipher.seal(&serde_json::to_string(entry)?)?)?;
+    file.sync_data()?;
+    Ok(())
+}
+
+// ========================
+// GRAPHQL MUTATION AUDITING
+// ========================
+
+pub struct MutationAudit {
+    log: Arc<AuditLog>,
+    redactor: Arc<Redactor>,
+}
+
+impl MutationAudit {
+    pub fn new(log: Arc<AuditLog>, redactor: Arc<Redactor>) -> Self {
+        Self { log, redactor }
+    }
+}
+
+impl ExtensionFactory for MutationAudit {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(MutationAuditExtension {
+            log: self.log.clone(),
+            redactor: self.redactor.clone(),
+            pending: Mutex::new(None),
+        })
+    }
+}
+
+// Root mutation fields with their redacted arguments
+type AuditedFields = Vec<(String, serde_json::Value)>;
+
+struct MutationAuditExtension {
+    log: Arc<AuditLog>,
+    redactor: Arc<Redactor>,
+    // The mutation being executed; None for queries
+    pending: Mutex<Option<(RequestOrigin, AuditedFields)>>,
+}
+
+#[async_trait::async_trait]
+impl Extension for MutationAuditExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let response = next.run(ctx).await;
+
+        let pending = self.pending.lock().unwrap().take();
+        if let Some((origin, fields)) = pending {
+            let outcome = if response.is_ok() { "ok" } else { "error" };
+            for (field, arguments) in fields {
+                self.log.record(&origin, &format!("mutation.{field}"), arguments, outcome);
+            }
+        }
+
+        response
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+
+        let fields: AuditedFields = document
+            .operations
+            .iter()
+            .filter(|(_, op)| op.node.ty == OperationType::Mutation)
+            .flat_map(|(_, op)| op.node.selection_set.node.items.iter())
+            .filter_map(|selection| match &selection.node {
+                Selection::Field(field) => {
+                    let arguments: serde_json::Map<String, serde_json::Value> = field
+                        .node
+                        .arguments
+                        .iter()
+                        .map(|(name, value)| {
+                            let value = value
+                                .node
+                                .clone()
+                                .into_const_with(|variable| Ok::<_, ()>(variables.get(&variable).cloned().unwrap_or(Value::Null)))
+                                .ok()
+                                .and_then(|value| value.into_json().ok())
+                                .unwrap_or_default();
+                            (name.node.to_string(), value)
+                        })
+                        .collect();
+                    let arguments = self.redactor.redact_arguments(&serde_json::Value::Object(arguments));
+                    Some((field.node.name.node.to_string(), arguments))
+                }
+                _ => None,
+            })
+            .collect();
+        if !fields.is_empty() {
+            // Nothing could be recorded for it, so the mutation doesn't run
+            if !self.log.accepts() {
+                return Err(ServerError::new("Audit log is full, mutations are refused until it recovers", None));
+            }
+            // Request data is only attached to the context from parsing onwards
+            let origin = ctx
+                .data_opt::<RequestOrigin>()
+                .cloned()
+                .unwrap_or_else(|| RequestOrigin::new(None, None, None));
+            *self.pending.lock().unwrap() = Some((origin, fields));
+        }
+
+        Ok(document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn log(path: Option<PathBuf>) -> AuditLog {
+        AuditLog {
+            path,
+            cipher: Arc::new(AtRestCipher::parse("").unwrap()),
+            capacity: 10,
+            head: Mutex::new(ChainHead {
+                seq: 0,
+                hash: GENESIS_HASH.to_string(),
+                recent: VecDeque::new(),
+                pending: VecDeque::new(),
+                pending_bytes: 0,
+                since: None,
+                last_error: None,
+            }),
+            policy: DegradationPolicy::Memory,
+            retry_every: Duration::from_secs(1),
+            pending_capacity: 100,
+            degraded: AtomicBool::new(false),
+            pending_dropped: AtomicU64::new(0),
+            writer: OnceLock::new(),
+            file: Mutex::new(()),
+        }
+    }
+
+    fn record(log: &AuditLog, count: u64) {
+        let origin = RequestOrigin::new(Some("alice".to_string()), None, None);
+        for i in 0..count {
+            log.record(&origin, "updateComponent", json!({ "id": format!("c{i}") }), "ok");
+        }
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("audit-test-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn chains_entries_and_detects_tampering_in_memory() {
+        let log = log(None);
+        record(&log, 3);
+        let verification = log.verify().unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.entries, 3);
+
+        log.head.lock().unwrap().recent[1].details = json!({ "id": "other" });
+        let verification = log.verify().unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.first_invalid_seq, Some(2));
+    }
+
+    #[test]
+    fn detects_edits_and_deletions_in_the_journal() {
+        let dir = scratch("journal");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("audit.jsonl");
+        let log = log(Some(path.clone()));
+        record(&log, 3);
+        assert!(log.verify().unwrap().valid);
+
+        let original = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = original.lines().collect();
+
+        let edited = lines[1].replace("\"outcome\":\"ok\"", "\"outcome\":\"denied\"");
+        assert_ne!(edited, lines[1]);
+        std::fs::write(&path, format!("{}\n{}\n{}\n", lines[0], edited, lines[2])).unwrap();
+        assert_eq!(log.verify().unwrap().first_invalid_seq, Some(2));
+
+        std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        assert_eq!(log.verify().unwrap().first_invalid_seq, Some(3));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn holds_entries_while_the_journal_fails_and_writes_them_back_in_order() {
+        let dir = scratch("degraded");
+        let path = dir.join("audit.jsonl");
+        let log = log(Some(path.clone()));
+
+        record(&log, 2);
+        assert!(log.is_degraded());
+        assert!(!log.blocks_readiness());
+        assert_eq!(log.status().pending_writes, 2);
+
+        // Still failing: nothing is written and the entries stay queued
+        assert_eq!(log.reconcile(&path), 0);
+        assert!(log.is_degraded());
+
+        std::fs::create_dir_all(&dir).unwrap();
+        record(&log, 1);
+        assert_eq!(log.status().pending_writes, 3);
+        assert_eq!(log.reconcile(&path), 3);
+        assert!(!log.is_degraded());
+        assert_eq!(log.status().pending_writes, 0);
+
+        let verification = log.verify().unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.entries, 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_entries_instead_of_breaking_the_chain_when_pending_is_full() {
+        let dir = scratch("full");
+        let path = dir.join("audit.jsonl");
+        let mut log = log(Some(path.clone()));
+        log.pending_capacity = 2;
+
+        record(&log, 3);
+        assert_eq!(log.status().pending_writes, 2);
+        assert_eq!(log.pending_dropped.load(Ordering::Relaxed), 1);
+        assert!(!log.accepts());
+        assert!(log.blocks_readiness());
+
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(log.reconcile(&path), 2);
+        assert!(log.accepts());
+        record(&log, 1);
+
+        // The refused entry took no seq, so the journal still verifies
+        let verification = log.verify().unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.entries, 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn appends_on_the_writer_thread() {
+        let dir = scratch("writer");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("audit.jsonl");
+        let log = Arc::new(log(Some(path.clone())));
+        log.spawn_writer();
+        record(&log, 3);
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        let lines = || std::fs::read_to_string(&path).map_or(0, |journal| journal.lines().count());
+        while lines() < 3 && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let verification = log.verify().unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.entries, 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
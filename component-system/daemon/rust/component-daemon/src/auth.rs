@@ -0,0 +1,354 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use dashmap::DashMap;
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request};
+use hyper_tls::HttpsConnector;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::config::env_parse;
+
+// ========================
+// AUTH PROVIDERS
+// ========================
+
+// Who presented a token, and what it allows
+#[derive(Clone, Debug, Serialize)]
+pub struct Principal {
+    pub subject: String,
+    pub scopes: HashSet<String>,
+}
+
+#[derive(Debug)]
+pub enum AuthError {
+    // Answered with 401
+    Rejected(String),
+    // The provider couldn't decide, e.g. the introspection endpoint is down;
+    // answered with 503
+    Unavailable(String),
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::Rejected(reason) => write!(f, "token rejected: {reason}"),
+            AuthError::Unavailable(reason) => write!(f, "token could not be checked: {reason}"),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+pub trait AuthProvider: Send + Sync {
+    // For logs
+    fn name(&self) -> &str;
+    async fn validate(&self, token: &str) -> Result<Principal, AuthError>;
+}
+
+// Picks the provider for one listener from `<PREFIX>_AUTH_PROVIDER`
+// (static, jwt or introspection); None leaves the listener open. Without the
+// variable, a `<PREFIX>_AUTH_TOKEN` still selects the static provider. A
+// custom provider only needs an arm here.
+pub fn provider_from_env(prefix: &str) -> Result<Option<Arc<dyn AuthProvider>>> {
+    let var = |name: &str| {
+        std::env::var(format!("{prefix}_{name}"))
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let provider: Arc<dyn AuthProvider> = match var("AUTH_PROVIDER").as_deref() {
+        None if var("AUTH_TOKEN").is_none() => return Ok(None),
+        None | Some("static") => Arc::new(StaticKeys::from_env(prefix, &var)?),
+        Some("jwt") => Arc::new(JwtProvider::from_env(prefix, &var)?),
+        Some("introspection") => Arc::new(Introspection::from_env(prefix, &var)?),
+        Some("none") => return Ok(None),
+        Some(other) => bail!("unknown {prefix}_AUTH_PROVIDER '{other}'"),
+    };
+    info!("🔐 Daemon: {} listener authenticates with the {} provider", prefix.to_lowercase(), provider.name());
+    Ok(Some(provider))
+}
+
+// ------------------------
+// Static keys
+// ------------------------
+
+#[derive(Deserialize)]
+struct KeyEntry {
+    token: String,
+    subject: String,
+    #[serde(default)]
+    scopes: HashSet<String>,
+}
+
+// `<PREFIX>_AUTH_TOKEN` is one token with every scope ("*");
+// `<PREFIX>_AUTH_KEYS_FILE` is a JSON list of `{token, subject, scopes}`.
+pub struct StaticKeys {
+    keys: HashMap<String, Principal>,
+}
+
+impl StaticKeys {
+    fn from_env(prefix: &str, var: &dyn Fn(&str) -> Option<String>) -> Result<Self> {
+        let mut keys = HashMap::new();
+        if let Some(token) = var("AUTH_TOKEN") {
+            keys.insert(
+                token,
+                Principal {
+                    subject: format!("{}-token", prefix.to_lowercase()),
+                    scopes: HashSet::from(["*".to_string()]),
+                },
+            );
+        }
+        if let Some(path) = var("AUTH_KEYS_FILE") {
+            let raw = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read auth keys {path}"))?;
+            let entries: Vec<KeyEntry> =
+                serde_json::from_str(&raw).with_context(|| format!("Invalid auth keys {path}"))?;
+            for entry in entries {
+                keys.insert(
+                    entry.token,
+                    Principal {
+                        subject: entry.subject,
+                        scopes: entry.scopes,
+                    },
+                );
+            }
+        }
+        if keys.is_empty() {
+            bail!("{prefix}_AUTH_PROVIDER=static needs {prefix}_AUTH_TOKEN or {prefix}_AUTH_KEYS_FILE");
+        }
+        Ok(Self { keys })
+    }
+}
+
+#[async_trait::async_trait]
+impl AuthProvider for StaticKeys {
+    fn name(&self) -> &str {
+        "static"
+    }
+
+    async fn validate(&self, token: &str) -> Result<Principal, AuthError> {
+        self.keys
+            .get(token)
+            .cloned()
+            .ok_or_else(|| AuthError::Rejected("unknown key".to_string()))
+    }
+}
+
+// ------------------------
+// JWT
+// ------------------------
+
+// `<PREFIX>_AUTH_JWT_SECRET` verifies HS256 tokens; otherwise
+// `<PREFIX>_AUTH_JWT_PUBLIC_KEY` is a PEM file for `<PREFIX>_AUTH_JWT_ALG`
+// (RS256 by default, also ES256 or EdDSA). `<PREFIX>_AUTH_JWT_ISSUER` and
+// `<PREFIX>_AUTH_JWT_AUDIENCE` are checked when set. Scopes come from the
+// space-separated `scope` claim or the `scp` list.
+pub struct JwtProvider {
+    key: DecodingKey,
+    validation: Validation,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    sub: Option<String>,
+    scope: Option<String>,
+    #[serde(default)]
+    scp: Vec<String>,
+}
+
+impl JwtProvider {
+    fn from_env(prefix: &str, var: &dyn Fn(&str) -> Option<String>) -> Result<Self> {
+        let (key, algorithm) = match (var("AUTH_JWT_SECRET"), var("AUTH_JWT_PUBLIC_KEY")) {
+            (Some(secret), _) => (DecodingKey::from_secret(secret.as_bytes()), Algorithm::HS256),
+            (None, Some(path)) => {
+                let pem = std::fs::read(&path).with_context(|| format!("Failed to read JWT key {path}"))?;
+                let algorithm = match var("AUTH_JWT_ALG").as_deref() {
+                    None | Some("RS256") => Algorithm::RS256,
+                    Some("ES256") => Algorithm::ES256,
+                    Some("EdDSA") => Algorithm::EdDSA,
+                    Some(other) => bail!("unsupported {prefix}_AUTH_JWT_ALG '{other}'"),
+                };
+                let key = match algorithm {
+                    Algorithm::ES256 => DecodingKey::from_ec_pem(&pem),
+                    Algorithm::EdDSA => DecodingKey::from_ed_pem(&pem),
+                    _ => DecodingKey::from_rsa_pem(&pem),
+                }
+                .with_context(|| format!("Invalid JWT key {path}"))?;
+                (key, algorithm)
+            }
+            (None, None) => bail!("{prefix}_AUTH_PROVIDER=jwt needs {prefix}_AUTH_JWT_SECRET or {prefix}_AUTH_JWT_PUBLIC_KEY"),
+        };
+        let mut validation = Validation::new(algorithm);
+        match var("AUTH_JWT_AUDIENCE") {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        if let Some(issuer) = var("AUTH_JWT_ISSUER") {
+            validation.set_issuer(&[issuer]);
+        }
+        Ok(Self { key, validation })
+    }
+}
+
+#[async_trait::async_trait]
+impl AuthProvider for JwtProvider {
+    fn name(&self) -> &str {
+        "jwt"
+    }
+
+    async fn validate(&self, token: &str) -> Result<Principal, AuthError> {
+        let claims = jsonwebtoken::decode::<Claims>(token, &self.key, &self.validation)
+            .map_err(|e| AuthError::Rejected(e.to_string()))?
+            .claims;
+        let mut scopes: HashSet<String> = claims.scp.into_iter().collect();
+        scopes.extend(claims.scope.unwrap_or_default().split_whitespace().map(str::to_string));
+        Ok(Principal {
+            subject: claims.sub.unwrap_or_default(),
+            scopes,
+        })
+    }
+}
+
+// ------------------------
+// OIDC token introspection
+// ------------------------
+
+// RFC 7662: tokens are POSTed to `<PREFIX>_AUTH_INTROSPECTION_URL`, with
+// `<PREFIX>_AUTH_CLIENT_ID` / `<PREFIX>_AUTH_CLIENT_SECRET` as basic auth.
+// Answers are cached for `<PREFIX>_AUTH_INTROSPECTION_CACHE_SECS` (default
+// 60), never past the token's `exp`; inactive tokens only for
+// `<PREFIX>_AUTH_INTROSPECTION_NEGATIVE_SECS` (default 5). At most
+// `<PREFIX>_AUTH_INTROSPECTION_CACHE_MAX` (default 10000) tokens are kept;
+// once full, expired answers are swept out and new ones go uncached until
+// there is room again.
+pub struct Introspection {
+    url: String,
+    credentials: Option<String>,
+    cache_ttl: Duration,
+    negative_ttl: Duration,
+    cache_max: usize,
+    timeout: Duration,
+    client: Client<HttpsConnector<HttpConnector>, Body>,
+    // Token → principal (None if inactive) and when the answer expires
+    cache: DashMap<String, (Option<Principal>, Instant)>,
+    // Last sweep of a full cache, so floods of new tokens don't scan it on
+    // every miss
+    swept: Mutex<Instant>,
+}
+
+#[derive(Deserialize)]
+struct IntrospectionResponse {
+    active: bool,
+    sub: Option<String>,
+    username: Option<String>,
+    scope: Option<String>,
+    exp: Option<i64>,
+}
+
+impl Introspection {
+    fn from_env(prefix: &str, var: &dyn Fn(&str) -> Option<String>) -> Result<Self> {
+        let url = var("AUTH_INTROSPECTION_URL").ok_or_else(|| {
+            anyhow!("{prefix}_AUTH_PROVIDER=introspection needs {prefix}_AUTH_INTROSPECTION_URL")
+        })?;
+        let credentials = var("AUTH_CLIENT_ID").map(|id| {
+            let secret = var("AUTH_CLIENT_SECRET").unwrap_or_default();
+            format!("Basic {}", BASE64.encode(format!("{id}:{secret}")))
+        });
+        Ok(Self {
+            url,
+            credentials,
+            cache_ttl: Duration::from_secs(env_parse(&format!("{prefix}_AUTH_INTROSPECTION_CACHE_SECS"), 60)),
+            negative_ttl: Duration::from_secs(env_parse(&format!("{prefix}_AUTH_INTROSPECTION_NEGATIVE_SECS"), 5)),
+            cache_max: env_parse(&format!("{prefix}_AUTH_INTROSPECTION_CACHE_MAX"), 10_000usize),
+            timeout: Duration::from_secs(5),
+            client: Client::builder().build(HttpsConnector::new()),
+            cache: DashMap::new(),
+            swept: Mutex::new(Instant::now()),
+        })
+    }
+
+    async fn introspect(&self, token: &str) -> Result<IntrospectionResponse> {
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("token", token)
+            .append_pair("token_type_hint", "access_token")
+            .finish();
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.url)
+            .header("content-type", "application/x-www-form-urlencoded")
+            .header("accept", "application/json");
+        if let Some(credentials) = &self.credentials {
+            request = request.header("authorization", credentials);
+        }
+        let response = tokio::time::timeout(self.timeout, self.client.request(request.body(Body::from(body))?))
+            .await
+            .map_err(|_| anyhow!("no response within {:?}", self.timeout))??;
+        if !response.status().is_success() {
+            bail!("introspection endpoint responded with {}", response.status());
+        }
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        serde_json::from_slice(&body).context("introspection endpoint answered with invalid JSON")
+    }
+
+    fn remember(&self, token: &str, principal: Option<Principal>, ttl: Duration) {
+        let now = Instant::now();
+        if self.cache.len() >= self.cache_max {
+            let mut swept = self.swept.lock().unwrap();
+            if now.duration_since(*swept) >= Duration::from_secs(1) {
+                *swept = now;
+                self.cache.retain(|_, entry| entry.1 > now);
+            }
+            if self.cache.len() >= self.cache_max {
+                return;
+            }
+        }
+        self.cache.insert(token.to_string(), (principal, now + ttl));
+    }
+}
+
+#[async_trait::async_trait]
+impl AuthProvider for Introspection {
+    fn name(&self) -> &str {
+        "introspection"
+    }
+
+    async fn validate(&self, token: &str) -> Result<Principal, AuthError> {
+        let now = Instant::now();
+        let cached = self.cache.get(token).filter(|entry| entry.1 > now).map(|entry| entry.0.clone());
+        if cached.is_none() {
+            self.cache.remove_if(token, |_, entry| entry.1 <= now);
+        }
+        let principal = match cached {
+            Some(principal) => principal,
+            None => {
+                let answer = self
+                    .introspect(token)
+                    .await
+                    .map_err(|e| AuthError::Unavailable(format!("{e:#}")))?;
+                let mut ttl = if answer.active { self.cache_ttl } else { self.negative_ttl };
+                if let Some(exp) = answer.exp {
+                    let left = (exp - chrono::Utc::now().timestamp()).max(0) as u64;
+                    ttl = ttl.min(Duration::from_secs(left));
+                }
+                let principal = answer.active.then(|| Principal {
+                    subject: answer.sub.or(answer.username).unwrap_or_default(),
+                    scopes: answer
+                        .scope
+                        .unwrap_or_default()
+                        .split_whitespace()
+                        .map(str::to_string)
+                        .collect(),
+                });
+                self.remember(token, principal.clone(), ttl);
+                principal
+            }
+        };
+        principal.ok_or_else(|| AuthError::Rejected("token is not active".to_string()))
+    }
+}
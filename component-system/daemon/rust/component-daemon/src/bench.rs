@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+
+use crate::history::{History, HistoryEvent};
+use crate::{Component, ComponentDaemon, ComponentType, Source};
+
+// ========================
+// INGEST BENCHMARK
+// ========================
+
+// `component-daemon bench-ingest [messages] [tasks]`
+//
+// Pushes synthetic components through the ingest path from several tasks at
+// once and prints the throughput of:
+// - the old hot path (an async Mutex around an unbounded Vec),
+// - the history buffer that replaced it,
+// - the full ingest pipeline, minus the registry socket.
+pub async fn run(args: &[String]) -> Result<()> {
+    let messages: usize = match args.first() {
+        Some(raw) => raw.parse().context("messages must be a number")?,
+        None => 200_000,
+    };
+    let tasks: usize = match args.get(1) {
+        Some(raw) => raw.parse().context("tasks must be a number")?,
+        None => 8,
+    };
+    let per_task = messages / tasks.max(1);
+    println!(
+        "bench-ingest: {} messages across {} tasks",
+        per_task * tasks,
+        tasks
+    );
+
+    let baseline = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+    let elapsed = measure(tasks, per_task, move |component| {
+        let baseline = baseline.clone();
+        async move { baseline.lock().await.push(component) }
+    })
+    .await;
+    report("mutex<vec> (previous)", per_task * tasks, elapsed);
+
+    let history = Arc::new(History::from_env());
+    let elapsed = measure(tasks, per_task, move |component| {
+        let history = history.clone();
+        async move { history.record(HistoryEvent::Upsert(component)) }
+    })
+    .await;
+    report("history buffer", per_task * tasks, elapsed);
+
+    let daemon = ComponentDaemon::new()?;
+    let elapsed = measure(tasks, per_task, move |component| {
+        let daemon = daemon.clone();
+        async move {
+            let _ = daemon
+                .handle_component_from_registry("bench", component, Source::Trusted)
+                .await;
+        }
+    })
+    .await;
+    report("full ingest", per_task * tasks, elapsed);
+
+    Ok(())
+}
+
+async fn measure<F, Fut>(tasks: usize, per_task: usize, ingest: F) -> Duration
+where
+    F: Fn(Component) -> Fut + Clone + Send + 'static,
+    Fut: std::future::Future<Output = ()> + Send,
+{
+    let started = Instant::now();
+    let handles: Vec<_> = (0..tasks)
+        .map(|task| {
+            let ingest = ingest.clone();
+            tokio::spawn(async move {
+                for i in 0..per_task {
+                    ingest(synthetic(task, i)).await;
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        let _ = handle.await;
+    }
+    started.elapsed()
+}
+
+fn synthetic(task: usize, i: usize) -> Component {
+    Component {
+        id: format!("bench-{}-{}", task, i),
+        r#type: ComponentType::Card,
+        data: serde_json::json!({ "title": "Bench", "content": format!("message {}", i) }),
+        created_at: Utc::now(),
+        priority: None,
+        version: 0,
+        seq: 0,
+        upstream_seq: None,
+        parent_id: None,
+        related_ids: Vec::new(),
+        labels: Default::default(),
+        deliver_at: None,
+        acknowledged: None,
+        stamps: Default::default(),
+    }
+}
+
+fn report(label: &str, messages: usize, elapsed: Duration) {
+    println!(
+        "  {:<24} {:>10.0} msg/s  ({:?})",
+        label,
+        messages as f64 / elapsed.as_secs_f64(),
+        elapsed
+    );
+}
@@ -0,0 +1,411 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hyper::body::Bytes;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::{error, info, warn};
+use warp::http::{header, StatusCode};
+use warp::reply::{Reply, Response};
+use warp::Filter;
+
+use crate::at_rest::AtRestCipher;
+use crate::config::env_parse;
+
+// ========================
+// BLOB STORE
+// ========================
+
+#[derive(Clone, Debug)]
+pub struct Blob {
+    pub content_type: String,
+    pub bytes: Bytes,
+}
+
+struct MemoryBlobs {
+    blobs: HashMap<String, Blob>,
+    // Insertion order, oldest first, for eviction
+    order: VecDeque<String>,
+    bytes: u64,
+}
+
+// What to do while a storage backend (BLOB_DIR, the audit journal, the
+// subscriber registry file) can't be written
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DegradationPolicy {
+    // Serve from memory, report degraded but ready, write back on recovery
+    Memory,
+    // As above, but fail readiness so traffic moves to a healthy daemon
+    Unready,
+    // Log the failed write and drop it from disk, as before
+    Off,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendStatus {
+    pub degraded: bool,
+    pub since: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    // Blobs held in memory until the disk takes them again
+    pub pending_writes: usize,
+    pub pending_bytes: u64,
+}
+
+#[derive(Default)]
+struct Pending {
+    // Oldest first, so recovery writes them back in order
+    blobs: VecDeque<(String, Blob)>,
+    bytes: u64,
+    since: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+// Content-addressed bytes served at GET /blobs/<sha256>. Up to
+// BLOB_MEMORY_BYTES stay in memory, oldest dropped first; with BLOB_DIR set
+// every blob is also written there, sealed when AT_REST_KEYS is set, and
+// read back once evicted.
+//
+// When a write to BLOB_DIR fails, the store degrades according to
+// BLOB_DEGRADATION (memory, unready or off; default memory): failed and later
+// writes are pinned in memory, up to BLOB_PENDING_BYTES (default 64 MiB),
+// and retried every BLOB_RETRY_SECS (default 10) until the disk recovers.
+pub struct BlobStore {
+    dir: Option<PathBuf>,
+    capacity: u64,
+    memory: Mutex<MemoryBlobs>,
+    policy: DegradationPolicy,
+    retry_every: Duration,
+    pending_capacity: u64,
+    degraded: AtomicBool,
+    pending: Mutex<Pending>,
+    write_failures: AtomicU64,
+    // Pending writes dropped because BLOB_PENDING_BYTES was reached
+    pending_dropped: AtomicU64,
+    // Seals blob files on disk when encryption at rest is on
+    cipher: Arc<AtRestCipher>,
+}
+
+impl DegradationPolicy {
+    pub fn from_var(name: &str) -> Self {
+        match std::env::var(name).as_deref().map(str::trim) {
+            Ok("memory") | Err(_) => DegradationPolicy::Memory,
+            Ok("unready") => DegradationPolicy::Unready,
+            Ok("off") => DegradationPolicy::Off,
+            Ok(other) => {
+                warn!("⚠️ Daemon: Ignoring invalid {}='{}'", name, other);
+                DegradationPolicy::Memory
+            }
+        }
+    }
+}
+
+impl BlobStore {
+    pub fn from_env(cipher: Arc<AtRestCipher>) -> Self {
+        let dir = std::env::var("BLOB_DIR").ok().map(PathBuf::from);
+        if let Some(dir) = &dir {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                warn!("⚠️ Daemon: Can't create BLOB_DIR {}, keeping blobs in memory: {}", dir.display(), e);
+            }
+        }
+        Self {
+            dir,
+            capacity: env_parse("BLOB_MEMORY_BYTES", 64 * 1024 * 1024),
+            memory: Mutex::new(MemoryBlobs {
+                blobs: HashMap::new(),
+                order: VecDeque::new(),
+                bytes: 0,
+            }),
+            policy: DegradationPolicy::from_var("BLOB_DEGRADATION"),
+            retry_every: Duration::from_secs(env_parse("BLOB_RETRY_SECS", 10).max(1)),
+            pending_capacity: env_parse("BLOB_PENDING_BYTES", 64 * 1024 * 1024),
+            degraded: AtomicBool::new(false),
+            pending: Mutex::new(Pending::default()),
+            write_failures: AtomicU64::new(0),
+            pending_dropped: AtomicU64::new(0),
+            cipher,
+        }
+    }
+
+    // Stores the bytes and returns their hash
+    pub fn put(&self, content_type: &str, bytes: Bytes) -> String {
+        let hash = hex(&Sha256::digest(&bytes));
+        if let Some(dir) = &self.dir {
+            // While degraded, new blobs queue behind the pending ones instead
+            // of each waiting on a disk that is known to fail
+            if !self.hold_while_degraded(&hash, content_type, &bytes) {
+                if let Err(e) = write_blob(dir, &self.cipher, &hash, content_type, &bytes) {
+                    self.write_failed(&hash, content_type, &bytes, &e);
+                }
+            }
+        }
+
+        let mut memory = self.memory.lock().unwrap();
+        if memory.blobs.contains_key(&hash) || bytes.len() as u64 > self.capacity {
+            return hash;
+        }
+        while memory.bytes + bytes.len() as u64 > self.capacity {
+            let Some(oldest) = memory.order.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = memory.blobs.remove(&oldest) {
+                memory.bytes -= evicted.bytes.len() as u64;
+            }
+        }
+        memory.bytes += bytes.len() as u64;
+        memory.order.push_back(hash.clone());
+        memory.blobs.insert(
+            hash.clone(),
+            Blob {
+                content_type: content_type.to_string(),
+                bytes,
+            },
+        );
+        hash
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.memory.lock().unwrap().blobs.contains_key(hash)
+            || self.pending_blob(hash).is_some()
+            || self.dir.as_ref().is_some_and(|dir| is_hash(hash) && dir.join(hash).exists())
+    }
+
+    pub fn get(&self, hash: &str) -> Option<Blob> {
+        if let Some(blob) = self.memory.lock().unwrap().blobs.get(hash) {
+            return Some(blob.clone());
+        }
+        if let Some(blob) = self.pending_blob(hash) {
+            return Some(blob);
+        }
+        if !is_hash(hash) {
+            return None;
+        }
+        let dir = self.dir.as_ref()?;
+        let raw = std::fs::read(dir.join(hash)).ok()?;
+        let bytes = match self.cipher.open_bytes(&raw) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                error!("❌ Daemon: Failed to decrypt blob {}: {:#}", hash, e);
+                return None;
+            }
+        };
+        let content_type = std::fs::read_to_string(dir.join(format!("{hash}.type")))
+            .unwrap_or_else(|_| "application/octet-stream".to_string());
+        Some(Blob {
+            content_type,
+            bytes: bytes.into(),
+        })
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    // Whether readiness should fail while degraded
+    pub fn blocks_readiness(&self) -> bool {
+        self.policy == DegradationPolicy::Unready && self.is_degraded()
+    }
+
+    pub fn status(&self) -> BackendStatus {
+        let pending = self.pending.lock().unwrap();
+        BackendStatus {
+            degraded: self.is_degraded(),
+            since: pending.since,
+            last_error: pending.last_error.clone(),
+            pending_writes: pending.blobs.len(),
+            pending_bytes: pending.bytes,
+        }
+    }
+
+    fn pending_blob(&self, hash: &str) -> Option<Blob> {
+        let pending = self.pending.lock().unwrap();
+        pending.blobs.iter().find(|(pending, _)| pending == hash).map(|(_, blob)| blob.clone())
+    }
+
+    // Degraded state only changes under the pending lock, so a blob is never
+    // held after recovery has already emptied the queue
+    fn hold_while_degraded(&self, hash: &str, content_type: &str, bytes: &Bytes) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        if !self.is_degraded() {
+            return false;
+        }
+        self.hold(&mut pending, hash, content_type, bytes);
+        true
+    }
+
+    fn write_failed(&self, hash: &str, content_type: &str, bytes: &Bytes, e: &std::io::Error) {
+        self.write_failures.fetch_add(1, Ordering::Relaxed);
+        if self.policy == DegradationPolicy::Off {
+            warn!("⚠️ Daemon: Failed to write blob {} to disk: {}", hash, e);
+            return;
+        }
+        let mut pending = self.pending.lock().unwrap();
+        pending.last_error = Some(e.to_string());
+        if !self.degraded.swap(true, Ordering::Relaxed) {
+            pending.since = Some(Utc::now());
+            error!(
+                "❌ Daemon: Blob store degraded, serving from memory until BLOB_DIR recovers: {}",
+                e
+            );
+        }
+        self.hold(&mut pending, hash, content_type, bytes);
+    }
+
+    fn hold(&self, pending: &mut Pending, hash: &str, content_type: &str, bytes: &Bytes) {
+        if pending.blobs.iter().any(|(pending, _)| pending == hash) {
+            return;
+        }
+        if pending.bytes + bytes.len() as u64 > self.pending_capacity {
+            self.pending_dropped.fetch_add(1, Ordering::Relaxed);
+            warn!("⚠️ Daemon: Pending blob writes are full, {} won't be written to disk", hash);
+            return;
+        }
+        pending.bytes += bytes.len() as u64;
+        pending.blobs.push_back((
+            hash.to_string(),
+            Blob {
+                content_type: content_type.to_string(),
+                bytes: bytes.clone(),
+            },
+        ));
+    }
+
+    // Writes pending blobs back, oldest first; stays degraded at the first
+    // failure. Returns how many were written.
+    fn reconcile(&self, dir: &Path) -> usize {
+        let mut written = 0;
+        loop {
+            let Some((hash, blob)) = self.pending.lock().unwrap().blobs.front().cloned() else {
+                break;
+            };
+            if let Err(e) = write_blob(dir, &self.cipher, &hash, &blob.content_type, &blob.bytes) {
+                self.write_failures.fetch_add(1, Ordering::Relaxed);
+                self.pending.lock().unwrap().last_error = Some(e.to_string());
+                return written;
+            }
+            let mut pending = self.pending.lock().unwrap();
+            pending.blobs.pop_front();
+            pending.bytes -= blob.bytes.len() as u64;
+            written += 1;
+        }
+        // Checked under the lock, so a blob held meanwhile isn't stranded
+        let mut pending = self.pending.lock().unwrap();
+        if pending.blobs.is_empty() {
+            self.degraded.store(false, Ordering::Relaxed);
+            pending.since = None;
+            pending.last_error = None;
+        }
+        written
+    }
+
+    pub fn spawn_recovery(self: &Arc<Self>) {
+        let Some(dir) = self.dir.clone() else {
+            return;
+        };
+        if self.policy == DegradationPolicy::Off {
+            return;
+        }
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(store.retry_every);
+            loop {
+                ticker.tick().await;
+                if !store.is_degraded() {
+                    continue;
+                }
+                let store = store.clone();
+                let dir = dir.clone();
+                // File writes block, so they stay off the runtime threads
+                let Ok(written) = tokio::task::spawn_blocking(move || (store.reconcile(&dir), store.is_degraded())).await
+                else {
+                    continue;
+                };
+                match written {
+                    (written, false) => info!("💾 Daemon: Blob store recovered, wrote back {} pending blobs", written),
+                    (written, true) if written > 0 => {
+                        warn!("⚠️ Daemon: Blob store still degraded after writing back {} blobs", written)
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    pub fn render(&self, out: &mut String) {
+        let status = self.status();
+        out.push_str("# HELP daemon_blob_store_degraded Whether blobs are being served from memory because BLOB_DIR fails.\n");
+        out.push_str("# TYPE daemon_blob_store_degraded gauge\n");
+        let _ = writeln!(out, "daemon_blob_store_degraded {}", u8::from(status.degraded));
+        out.push_str("# HELP daemon_blob_pending_writes Blobs waiting to be written back to BLOB_DIR.\n");
+        out.push_str("# TYPE daemon_blob_pending_writes gauge\n");
+        let _ = writeln!(out, "daemon_blob_pending_writes {}", status.pending_writes);
+        out.push_str("# HELP daemon_blob_write_failures_total Failed writes to BLOB_DIR.\n");
+        out.push_str("# TYPE daemon_blob_write_failures_total counter\n");
+        let _ = writeln!(out, "daemon_blob_write_failures_total {}", self.write_failures.load(Ordering::Relaxed));
+        out.push_str("# HELP daemon_blob_pending_dropped_total Blob writes dropped because BLOB_PENDING_BYTES was full.\n");
+        out.push_str("# TYPE daemon_blob_pending_dropped_total counter\n");
+        let _ = writeln!(out, "daemon_blob_pending_dropped_total {}", self.pending_dropped.load(Ordering::Relaxed));
+    }
+}
+
+fn write_blob(dir: &Path, cipher: &AtRestCipher, hash: &str, content_type: &str, bytes: &[u8]) -> std::io::Result<()> {
+    let sealed = cipher.seal_bytes(bytes).map_err(|e| std::io::Error::other(format!("{e:#}")))?;
+    std::fs::write(dir.join(hash), sealed).and_then(|_| std::fs::write(dir.join(format!("{hash}.type")), content_type))
+}
+
+// Hashes are hex, so a valid one never leaves the blob directory
+fn is_hash(hash: &str) -> bool {
+    !hash.is_empty() && hash.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+// Served inline; everything else is a download, so a stored HTML or SVG
+// file can't run script on the daemon's origin
+const INLINE_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp", "image/avif"];
+
+pub fn routes(
+    blobs: Arc<BlobStore>,
+) -> impl Filter<Extract = (Response,), Error = warp::Rejection> + Clone {
+    warp::path!("blobs" / String)
+        .and(warp::get())
+        .and(warp::header::optional::<String>("if-none-match"))
+        .and_then(move |hash: String, if_none_match: Option<String>| {
+            let blobs = blobs.clone();
+            async move {
+                let etag = format!("\"{hash}\"");
+                // Evicted blobs are read back from disk, which blocks
+                let blob = tokio::task::spawn_blocking(move || blobs.get(&hash)).await.ok().flatten();
+                let Some(blob) = blob else {
+                    return Ok::<_, warp::Rejection>(StatusCode::NOT_FOUND.into_response());
+                };
+                // Content never changes under a hash, so caches can keep it forever
+                if crate::rest::etag_matches(if_none_match.as_deref(), &etag) {
+                    return Ok(crate::rest::not_modified(&etag));
+                }
+                let inline = INLINE_TYPES.contains(&blob.content_type.as_str());
+                let mut response = Response::new(blob.bytes.into());
+                let headers = response.headers_mut();
+                if let Ok(content_type) = blob.content_type.parse() {
+                    headers.insert(header::CONTENT_TYPE, content_type);
+                }
+                headers.insert(header::X_CONTENT_TYPE_OPTIONS, header::HeaderValue::from_static("nosniff"));
+                if !inline {
+                    headers.insert(header::CONTENT_DISPOSITION, header::HeaderValue::from_static("attachment"));
+                }
+                headers.insert(header::ETAG, etag.parse().unwrap());
+                headers.insert(
+                    header::CACHE_CONTROL,
+                    header::HeaderValue::from_static("public, max-age=31536000, immutable"),
+                );
+                Ok(response)
+            }
+        })
+}
@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use async_graphql::Union;
+use dashmap::DashMap;
+use futures::future::select_all;
+use tokio::sync::broadcast::{self, error::RecvError, error::TryRecvError};
+
+use crate::{Component, ComponentRemoval, ComponentType};
+
+// ========================
+// UPDATE CHANNELS
+// ========================
+
+const TYPES: [ComponentType; 3] = [
+    ComponentType::Card,
+    ComponentType::Notification,
+    ComponentType::Form,
+];
+
+// One broadcast ring per component type plus an "all" ring. A subscriber
+// that only wants Notifications listens on the Notification ring, so a burst
+// of Cards can't push it into lag and cost it updates.
+pub struct UpdateChannels {
+    all: broadcast::Sender<Component>,
+    by_type: [broadcast::Sender<Component>; 3],
+}
+
+impl UpdateChannels {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            all: broadcast::channel(capacity).0,
+            by_type: TYPES.map(|_| broadcast::channel(capacity).0),
+        }
+    }
+
+    // Rings without receivers are skipped, which saves the clone
+    pub fn send(&self, component: Component) {
+        let typed = &self.by_type[index(component.r#type)];
+        if typed.receiver_count() > 0 {
+            let _ = typed.send(component.clone());
+        }
+        if self.all.receiver_count() > 0 {
+            let _ = self.all.send(component);
+        }
+    }
+
+    pub fn subscribe_all(&self) -> broadcast::Receiver<Component> {
+        self.all.subscribe()
+    }
+
+    // No types, or every type, uses the "all" ring so ordering across types
+    // is kept for subscribers that want everything.
+    pub fn subscribe(&self, types: Option<&[ComponentType]>) -> UpdateReceiver {
+        let receivers = match types {
+            Some(types) if !TYPES.iter().all(|t| types.contains(t)) => TYPES
+                .iter()
+                .filter(|t| types.contains(t))
+                .map(|t| self.by_type[index(*t)].subscribe())
+                .collect(),
+            _ => vec![self.all.subscribe()],
+        };
+        UpdateReceiver { receivers }
+    }
+}
+
+fn index(r#type: ComponentType) -> usize {
+    match r#type {
+        ComponentType::Card => 0,
+        ComponentType::Notification => 1,
+        ComponentType::Form => 2,
+    }
+}
+
+// Receives from one or more rings. An empty type list subscribes to nothing
+// and simply never yields.
+pub struct UpdateReceiver {
+    receivers: Vec<broadcast::Receiver<Component>>,
+}
+
+impl UpdateReceiver {
+    pub async fn recv(&mut self) -> Result<Component, RecvError> {
+        match self.receivers.as_mut_slice() {
+            [] => std::future::pending().await,
+            [receiver] => receiver.recv().await,
+            receivers => {
+                // broadcast::Receiver::recv is cancel-safe, so losing the race drops nothing
+                let pending = receivers
+                    .iter_mut()
+                    .map(|receiver| Box::pin(receiver.recv()));
+                select_all(pending).await.0
+            }
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Result<Component, TryRecvError> {
+        let mut result = Err(TryRecvError::Empty);
+        for receiver in &mut self.receivers {
+            match receiver.try_recv() {
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Closed) => result = Err(TryRecvError::Closed),
+                other => return other,
+            }
+        }
+        result
+    }
+}
+
+// ========================
+// PER-ID CHANNELS
+// ========================
+
+#[derive(Clone, Debug, Union)]
+pub enum ComponentChange {
+    Updated(Component),
+    Removed(ComponentRemoval),
+}
+
+impl ComponentChange {
+    fn id(&self) -> &str {
+        match self {
+            ComponentChange::Updated(component) => &component.id,
+            ComponentChange::Removed(removal) => &removal.id,
+        }
+    }
+}
+
+// Small broadcast rings keyed by component id, created on first watch and
+// dropped with the last watcher, so single-component subscribers don't sit
+// on the firehose.
+#[derive(Clone, Default)]
+pub struct IdChannels {
+    senders: Arc<DashMap<String, broadcast::Sender<ComponentChange>>>,
+}
+
+const ID_CHANNEL_CAPACITY: usize = 16;
+
+impl IdChannels {
+    pub fn is_watched(&self, id: &str) -> bool {
+        self.senders.contains_key(id)
+    }
+
+    pub fn send(&self, change: ComponentChange) {
+        if let Some(sender) = self.senders.get(change.id()) {
+            let _ = sender.send(change);
+        }
+    }
+
+    pub fn subscribe(&self, id: &str) -> IdReceiver {
+        let receiver = self
+            .senders
+            .entry(id.to_string())
+            .or_insert_with(|| broadcast::channel(ID_CHANNEL_CAPACITY).0)
+            .subscribe();
+        IdReceiver {
+            id: id.to_string(),
+            receiver: Some(receiver),
+            channels: self.clone(),
+        }
+    }
+
+    pub fn watched_count(&self) -> usize {
+        self.senders.len()
+    }
+}
+
+pub struct IdReceiver {
+    id: String,
+    receiver: Option<broadcast::Receiver<ComponentChange>>,
+    channels: IdChannels,
+}
+
+impl IdReceiver {
+    pub async fn recv(&mut self) -> Result<ComponentChange, RecvError> {
+        match &mut self.receiver {
+            Some(receiver) => receiver.recv().await,
+            None => Err(RecvError::Closed),
+        }
+    }
+}
+
+impl Drop for IdReceiver {
+    fn drop(&mut self) {
+        // Our receiver has to be gone before counting the remaining ones
+        drop(self.receiver.take());
+        self.channels
+            .senders
+            .remove_if(&self.id, |_, sender| sender.receiver_count() == 0);
+    }
+}
@@ -0,0 +1,112 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tracing::warn;
+
+use crate::config::env_parse;
+use crate::ComponentDaemon;
+
+// ========================
+// FAULT INJECTION
+// ========================
+
+// Only compiled with `--features chaos`. Each upstream `data` message rolls
+// once against these probabilities, in order:
+// CHAOS_DROP_RATE       dropped
+// CHAOS_DUPLICATE_RATE  handled twice
+// CHAOS_REORDER_RATE    held back until after the next data message
+// CHAOS_DELAY_RATE      handled after up to CHAOS_DELAY_MAX_MS
+// CHAOS_DISCONNECT_SECS drops the registry connection on that period (0 = off)
+// CHAOS_SEED makes a run repeatable.
+pub struct Chaos {
+    drop_rate: f64,
+    duplicate_rate: f64,
+    reorder_rate: f64,
+    delay_rate: f64,
+    delay_max: Duration,
+    disconnect_every: Option<Duration>,
+    state: Mutex<ChaosState>,
+}
+
+struct ChaosState {
+    rng: StdRng,
+    held: Option<String>,
+}
+
+impl Chaos {
+    pub fn from_env() -> Self {
+        let rng = match std::env::var("CHAOS_SEED").ok().and_then(|seed| seed.trim().parse().ok()) {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let disconnect_secs = env_parse("CHAOS_DISCONNECT_SECS", 0u64);
+        Self {
+            drop_rate: env_parse("CHAOS_DROP_RATE", 0.0),
+            duplicate_rate: env_parse("CHAOS_DUPLICATE_RATE", 0.0),
+            reorder_rate: env_parse("CHAOS_REORDER_RATE", 0.0),
+            delay_rate: env_parse("CHAOS_DELAY_RATE", 0.0),
+            delay_max: Duration::from_millis(env_parse("CHAOS_DELAY_MAX_MS", 2000)),
+            disconnect_every: (disconnect_secs > 0).then(|| Duration::from_secs(disconnect_secs)),
+            state: Mutex::new(ChaosState { rng, held: None }),
+        }
+    }
+
+    // The messages to handle now, in order. Control messages pass untouched
+    // so the connection itself still comes up.
+    pub async fn perturb(&self, text: &str) -> Vec<String> {
+        let is_data = serde_json::from_str::<serde_json::Value>(text)
+            .is_ok_and(|message| message["type"] == "data");
+        if !is_data {
+            return vec![text.to_string()];
+        }
+
+        let (messages, delay) = {
+            let mut state = self.state.lock().unwrap();
+            let roll: f64 = state.rng.gen();
+            let duplicate_at = self.drop_rate + self.duplicate_rate;
+            let reorder_at = duplicate_at + self.reorder_rate;
+            let delay_at = reorder_at + self.delay_rate;
+            let mut messages = vec![text.to_string()];
+            let mut delay = None;
+            if roll < self.drop_rate {
+                warn!("🐒 Daemon: Chaos dropped an upstream message");
+                return Vec::new();
+            } else if roll < duplicate_at {
+                warn!("🐒 Daemon: Chaos duplicated an upstream message");
+                messages.push(text.to_string());
+            } else if roll < reorder_at {
+                if state.held.is_none() {
+                    warn!("🐒 Daemon: Chaos holding an upstream message back");
+                    state.held = Some(text.to_string());
+                    return Vec::new();
+                }
+            } else if roll < delay_at {
+                delay = Some(self.delay_max.mul_f64(state.rng.gen()));
+            }
+            // A held message goes out right after the one that overtook it
+            messages.extend(state.held.take());
+            (messages, delay)
+        };
+        if let Some(delay) = delay {
+            warn!("🐒 Daemon: Chaos delaying an upstream message by {:?}", delay);
+            tokio::time::sleep(delay).await;
+        }
+        messages
+    }
+
+    pub fn spawn_disconnects(&self, daemon: &ComponentDaemon) {
+        let Some(every) = self.disconnect_every else {
+            return;
+        };
+        let daemon = daemon.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(every).await;
+                warn!("🐒 Daemon: Chaos dropping the registry connection");
+                daemon.request_reconnect();
+            }
+        });
+    }
+}
@@ -0,0 +1,177 @@
+use std::io::Write;
+
+use flate2::write::GzEncoder;
+use hyper::body::HttpBody;
+use tracing::warn;
+use warp::http::header::{HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, VARY};
+use warp::hyper::Body;
+use warp::reply::Response;
+use warp::Filter;
+
+use crate::config::env_parse;
+
+// ========================
+// RESPONSE COMPRESSION
+// ========================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    fn token(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    fn parse(token: &str) -> Option<Self> {
+        match token {
+            "br" => Some(Encoding::Brotli),
+            "gzip" | "x-gzip" => Some(Encoding::Gzip),
+            _ => None,
+        }
+    }
+}
+
+// HTTP_COMPRESSION lists the encodings offered, in preference order
+// ("br,gzip" by default, "off" to disable). Bodies smaller than
+// HTTP_COMPRESSION_MIN_BYTES go out as they are. Streamed bodies such as
+// incremental delivery are never buffered, so they are left alone too.
+#[derive(Clone, Debug)]
+pub struct CompressionConfig {
+    encodings: Vec<Encoding>,
+    min_bytes: u64,
+}
+
+impl CompressionConfig {
+    pub fn from_env() -> Self {
+        let raw = std::env::var("HTTP_COMPRESSION").unwrap_or_else(|_| "br,gzip".to_string());
+        let mut encodings = Vec::new();
+        if !matches!(raw.trim(), "off" | "false" | "0" | "") {
+            for token in raw.split(',').map(str::trim) {
+                match Encoding::parse(token) {
+                    Some(encoding) if !encodings.contains(&encoding) => encodings.push(encoding),
+                    Some(_) => {}
+                    None => warn!("⚠️ Daemon: Ignoring unknown encoding '{}' in HTTP_COMPRESSION", token),
+                }
+            }
+        }
+        Self {
+            encodings,
+            min_bytes: env_parse("HTTP_COMPRESSION_MIN_BYTES", 1024),
+        }
+    }
+
+    // The first configured encoding the client accepts with a non-zero q
+    fn negotiate(&self, accept_encoding: &str) -> Option<Encoding> {
+        let accepted: Vec<(&str, f32)> = accept_encoding
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';').map(str::trim);
+                let token = parts.next().filter(|token| !token.is_empty())?;
+                let quality = parts
+                    .find_map(|param| param.strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.parse().ok())?;
+                Some((token, quality))
+            })
+            .collect();
+        let quality = |encoding: Encoding| {
+            accepted
+                .iter()
+                .find(|(token, _)| token.eq_ignore_ascii_case(encoding.token()))
+                .or_else(|| accepted.iter().find(|(token, _)| *token == "*"))
+                .map_or(0.0, |(_, quality)| *quality)
+        };
+        self.encodings.iter().copied().find(|encoding| quality(*encoding) > 0.0)
+    }
+
+    async fn apply(&self, accept_encoding: Option<String>, response: Response) -> Response {
+        let compressible = !self.encodings.is_empty()
+            && !response.headers().contains_key(CONTENT_ENCODING)
+            && response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(compressible_type);
+        if !compressible {
+            return response;
+        }
+
+        let (mut parts, body) = response.into_parts();
+        parts.headers.append(VARY, HeaderValue::from_static("accept-encoding"));
+        let encoding = accept_encoding.as_deref().and_then(|accepted| self.negotiate(accepted));
+        let large_enough = body.size_hint().exact().is_some_and(|size| size >= self.min_bytes);
+        let Some(encoding) = encoding.filter(|_| large_enough) else {
+            return Response::from_parts(parts, body);
+        };
+
+        // Exact-size bodies are already in memory, so this never waits on a stream
+        let bytes = match hyper::body::to_bytes(body).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("⚠️ Daemon: Failed to read response body for compression: {}", e);
+                return Response::from_parts(parts, Body::empty());
+            }
+        };
+        match compress(encoding, &bytes) {
+            Ok(compressed) if compressed.len() < bytes.len() => {
+                parts.headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding.token()));
+                parts.headers.remove(CONTENT_LENGTH);
+                Response::from_parts(parts, Body::from(compressed))
+            }
+            Ok(_) => Response::from_parts(parts, Body::from(bytes)),
+            Err(e) => {
+                warn!("⚠️ Daemon: {} compression failed, sending uncompressed: {}", encoding.token(), e);
+                Response::from_parts(parts, Body::from(bytes))
+            }
+        }
+    }
+}
+
+fn compressible_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    mime.starts_with("text/")
+        || mime.ends_with("json")
+        || mime.ends_with("javascript")
+        || mime.ends_with("xml")
+}
+
+fn compress(encoding: Encoding, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        Encoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(bytes)?;
+            encoder.finish()
+        }
+        Encoding::Brotli => {
+            // Quality 5 keeps most of brotli's gain at a fraction of the CPU of 11
+            let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+            encoder.write_all(bytes)?;
+            Ok(encoder.into_inner())
+        }
+    }
+}
+
+// Compresses replies from `routes` for clients that accept it
+pub fn wrap<F, R>(
+    config: CompressionConfig,
+    routes: F,
+) -> impl Filter<Extract = (Response,), Error = warp::Rejection> + Clone
+where
+    F: Filter<Extract = (R,), Error = warp::Rejection> + Clone + Send + Sync + 'static,
+    R: warp::Reply,
+{
+    warp::header::optional::<String>(ACCEPT_ENCODING.as_str())
+        .and(routes)
+        .and_then(move |accept_encoding: Option<String>, reply: R| {
+            let config = config.clone();
+            async move {
+                let response = warp::Reply::into_response(reply);
+                Ok::<_, warp::Rejection>(config.apply(accept_encoding, response).await)
+            }
+        })
+}
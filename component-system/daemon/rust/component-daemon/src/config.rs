@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use tracing::warn;
+
+use crate::ComponentType;
+
+// ========================
+// ENVIRONMENT HELPERS
+// ========================
+
+pub fn env_parse<T: FromStr>(name: &str, default: T) -> T {
+    match std::env::var(name) {
+        Ok(raw) => raw.trim().parse().unwrap_or_else(|_| {
+            warn!("⚠️ Daemon: Ignoring invalid {}='{}'", name, raw);
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+// Parses per-type settings of the form "CARD=a,NOTIFICATION=b".
+pub fn per_type_overrides<T>(
+    name: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> HashMap<ComponentType, T> {
+    let mut overrides = HashMap::new();
+    let Ok(raw) = std::env::var(name) else {
+        return overrides;
+    };
+
+    for pair in raw.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let parsed = pair.split_once('=').and_then(|(ty, value)| {
+            let ty = serde_json::from_value(serde_json::Value::String(ty.trim().to_string())).ok()?;
+            Some((ty, parse(value.trim())?))
+        });
+        match parsed {
+            Some((ty, value)) => {
+                overrides.insert(ty, value);
+            }
+            None => warn!("⚠️ Daemon: Ignoring invalid {} entry '{}'", name, pair),
+        }
+    }
+
+    overrides
+}
+
+// Accepts "90", "90s", "15m", "1h" or "2d"; bare numbers are seconds.
+pub fn parse_duration(raw: &str) -> Option<Duration> {
+    let raw = raw.trim();
+    let (number, unit) = raw.split_at(raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len()));
+    let number: u64 = number.parse().ok()?;
+    let seconds = match unit {
+        "" | "s" => number,
+        "m" => number * 60,
+        "h" => number * 60 * 60,
+        "d" => number * 60 * 60 * 24,
+        _ => return None,
+    };
+    Some(Duration::from_secs(seconds))
+}
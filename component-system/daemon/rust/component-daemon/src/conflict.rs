@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use crate::config::per_type_overrides;
+use crate::{Component, ComponentType};
+
+// ========================
+// CONFLICT RESOLUTION
+// ========================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    LastWriteWins,
+    // Deep-merge incoming `data` over the stored object
+    MergeData,
+    // Keep the stored component if the incoming one has an older createdAt
+    RejectOlder,
+}
+
+impl ConflictPolicy {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "lww" | "last-write-wins" => Some(ConflictPolicy::LastWriteWins),
+            "merge" => Some(ConflictPolicy::MergeData),
+            "reject-older" => Some(ConflictPolicy::RejectOlder),
+            _ => None,
+        }
+    }
+}
+
+pub enum Resolution {
+    Apply(Box<Component>),
+    Reject { reason: String },
+}
+
+pub struct ConflictConfig {
+    default: ConflictPolicy,
+    per_type: HashMap<ComponentType, ConflictPolicy>,
+}
+
+impl ConflictConfig {
+    // CONFLICT_RESOLUTION_DEFAULT sets the fallback, CONFLICT_RESOLUTION holds
+    // per-type overrides, e.g. "CARD=merge,FORM=reject-older".
+    pub fn from_env() -> Self {
+        let default = std::env::var("CONFLICT_RESOLUTION_DEFAULT")
+            .ok()
+            .and_then(|raw| ConflictPolicy::parse(raw.trim()))
+            .unwrap_or(ConflictPolicy::LastWriteWins);
+        Self {
+            default,
+            per_type: per_type_overrides("CONFLICT_RESOLUTION", ConflictPolicy::parse),
+        }
+    }
+
+    pub fn policy_for(&self, r#type: ComponentType) -> ConflictPolicy {
+        self.per_type.get(&r#type).copied().unwrap_or(self.default)
+    }
+
+    pub fn resolve(&self, existing: Option<&Component>, incoming: Component) -> Resolution {
+        let Some(existing) = existing else {
+            return Resolution::Apply(Box::new(incoming));
+        };
+
+        match self.policy_for(incoming.r#type) {
+            ConflictPolicy::LastWriteWins => Resolution::Apply(Box::new(incoming)),
+            ConflictPolicy::MergeData => {
+                let mut merged = incoming;
+                let mut data = existing.data.clone();
+                merge_json(&mut data, merged.data);
+                merged.data = data;
+                Resolution::Apply(Box::new(merged))
+            }
+            ConflictPolicy::RejectOlder => {
+                if incoming.created_at < existing.created_at {
+                    Resolution::Reject {
+                        reason: format!(
+                            "createdAt {} is older than stored {}",
+                            incoming.created_at, existing.created_at
+                        ),
+                    }
+                } else {
+                    Resolution::Apply(Box::new(incoming))
+                }
+            }
+        }
+    }
+}
+
+// Objects merge key by key; anything else (including arrays) is replaced.
+fn merge_json(target: &mut serde_json::Value, patch: serde_json::Value) {
+    use serde_json::Value;
+
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                match target.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn component(r#type: &str, created_at: &str, data: Value) -> Component {
+        serde_json::from_value(json!({ "id": "c1", "type": r#type, "data": data, "createdAt": created_at })).unwrap()
+    }
+
+    fn config(default: ConflictPolicy, per_type: &[(ComponentType, ConflictPolicy)]) -> ConflictConfig {
+        ConflictConfig {
+            default,
+            per_type: per_type.iter().copied().collect(),
+        }
+    }
+
+    fn applied(resolution: Resolution) -> Component {
+        match resolution {
+            Resolution::Apply(component) => *component,
+            Resolution::Reject { reason } => panic!("unexpected reject: {reason}"),
+        }
+    }
+
+    #[test]
+    fn first_write_is_always_applied() {
+        let incoming = component("CARD", "2024-01-01T00:00:00Z", json!({ "title": "New" }));
+        let config = config(ConflictPolicy::RejectOlder, &[]);
+        assert_eq!(applied(config.resolve(None, incoming)).data, json!({ "title": "New" }));
+    }
+
+    #[test]
+    fn last_write_wins_replaces_data() {
+        let stored = component("CARD", "2024-01-02T00:00:00Z", json!({ "title": "Old", "body": "kept?" }));
+        let incoming = component("CARD", "2024-01-01T00:00:00Z", json!({ "title": "New" }));
+        let config = config(ConflictPolicy::LastWriteWins, &[]);
+        assert_eq!(applied(config.resolve(Some(&stored), incoming)).data, json!({ "title": "New" }));
+    }
+
+    #[test]
+    fn merge_deep_merges_objects_and_replaces_everything_else() {
+        let stored = component(
+            "CARD",
+            "2024-01-01T00:00:00Z",
+            json!({ "title": "Old", "meta": { "a": 1, "b": 2 }, "tags": ["x", "y"] }),
+        );
+        let incoming = component(
+            "CARD",
+            "2024-01-02T00:00:00Z",
+            json!({ "meta": { "b": 3, "c": 4 }, "tags": ["z"] }),
+        );
+        let config = config(ConflictPolicy::MergeData, &[]);
+        let merged = applied(config.resolve(Some(&stored), incoming));
+        assert_eq!(
+            merged.data,
+            json!({ "title": "Old", "meta": { "a": 1, "b": 3, "c": 4 }, "tags": ["z"] })
+        );
+        assert_eq!(merged.created_at.to_rfc3339(), "2024-01-02T00:00:00+00:00");
+    }
+
+    #[test]
+    fn reject_older_keeps_the_stored_component() {
+        let stored = component("FORM", "2024-01-02T00:00:00Z", json!({ "title": "Stored" }));
+        let config = config(ConflictPolicy::RejectOlder, &[]);
+
+        let older = component("FORM", "2024-01-01T00:00:00Z", json!({ "title": "Older" }));
+        assert!(matches!(config.resolve(Some(&stored), older), Resolution::Reject { .. }));
+
+        let same = component("FORM", "2024-01-02T00:00:00Z", json!({ "title": "Same" }));
+        assert_eq!(applied(config.resolve(Some(&stored), same)).data, json!({ "title": "Same" }));
+    }
+
+    #[test]
+    fn per_type_overrides_the_default() {
+        let config = config(
+            ConflictPolicy::LastWriteWins,
+            &[(ComponentType::Form, ConflictPolicy::RejectOlder)],
+        );
+        assert_eq!(config.policy_for(ComponentType::Card), ConflictPolicy::LastWriteWins);
+        assert_eq!(config.policy_for(ComponentType::Form), ConflictPolicy::RejectOlder);
+        assert_eq!(ConflictPolicy::parse("last-write-wins"), Some(ConflictPolicy::LastWriteWins));
+        assert_eq!(ConflictPolicy::parse("newest"), None);
+    }
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+
+use crate::config::env_parse;
+use crate::Component;
+
+// ========================
+// DEBOUNCE
+// ========================
+
+pub enum Debounced {
+    // Debouncing is off; publish right away
+    Immediate(Box<Component>),
+    // First update for this id in the window; caller schedules the flush
+    Scheduled,
+    // Replaced a pending update that will now never be broadcast
+    Coalesced,
+}
+
+struct PendingUpdate {
+    component: Component,
+    suppressed: u64,
+}
+
+// Coalesces bursts per component id. The window opens on the first update and
+// closes on a fixed timer, so a producer that never stops flapping still gets
+// its latest value out once per window instead of being starved.
+pub struct Debouncer {
+    window: Duration,
+    pending: DashMap<String, PendingUpdate>,
+}
+
+impl Debouncer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: DashMap::new(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(Duration::from_millis(env_parse("DEBOUNCE_WINDOW_MS", 0)))
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub fn offer(&self, component: Component) -> Debounced {
+        if self.window.is_zero() {
+            return Debounced::Immediate(Box::new(component));
+        }
+
+        match self.pending.entry(component.id.clone()) {
+            Entry::Occupied(mut entry) => {
+                let pending = entry.get_mut();
+                pending.component = component;
+                pending.suppressed += 1;
+                Debounced::Coalesced
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(PendingUpdate {
+                    component,
+                    suppressed: 0,
+                });
+                Debounced::Scheduled
+            }
+        }
+    }
+
+    // Returns the latest value for the id and how many updates it replaced.
+    pub fn take(&self, id: &str) -> Option<(Component, u64)> {
+        self.pending
+            .remove(id)
+            .map(|(_, pending)| (pending.component, pending.suppressed))
+    }
+
+    pub fn is_pending(&self, id: &str) -> bool {
+        self.pending.contains_key(id)
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use async_graphql::SimpleObject;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{Component, ComponentType};
+
+// ========================
+// DELTA UPDATES
+// ========================
+
+// One update as sent by rendererDelta. A renderer applies `set` and `unset`
+// to the data it holds for the id, or replaces it when `full` is true.
+#[derive(Clone, Debug, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentDelta {
+    pub id: String,
+    pub r#type: ComponentType,
+    pub version: u64,
+    pub seq: u64,
+    pub full: bool,
+    // Top-level data fields that are new or changed, or all data when full
+    pub set: Value,
+    // Top-level data fields that were removed
+    pub unset: Vec<String>,
+}
+
+// What one subscription has sent so far, per id. Diffs are taken against
+// that rather than the previous stored version, so skipped updates (lag,
+// filters) still leave the renderer with the right state.
+#[derive(Default)]
+pub struct DeltaTracker {
+    sent: HashMap<String, Value>,
+}
+
+impl DeltaTracker {
+    // None when the data is what the renderer already has
+    pub fn delta(&mut self, component: &Component) -> Option<ComponentDelta> {
+        let previous = self.sent.insert(component.id.clone(), component.data.clone());
+        let (full, set, unset) = match (previous, &component.data) {
+            (Some(Value::Object(previous)), Value::Object(current)) => {
+                let set: Map<String, Value> = current
+                    .iter()
+                    .filter(|(key, value)| previous.get(*key) != Some(value))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect();
+                let unset: Vec<String> = previous
+                    .keys()
+                    .filter(|key| !current.contains_key(*key))
+                    .cloned()
+                    .collect();
+                if set.is_empty() && unset.is_empty() {
+                    return None;
+                }
+                (false, Value::Object(set), unset)
+            }
+            (Some(previous), current) if previous == *current => return None,
+            (_, current) => (true, current.clone(), Vec::new()),
+        };
+        Some(ComponentDelta {
+            id: component.id.clone(),
+            r#type: component.r#type,
+            version: component.version,
+            seq: component.seq,
+            full,
+            set,
+            unset,
+        })
+    }
+
+    // The next update for a removed id starts over with full data
+    pub fn forget(&mut self, id: &str) {
+        self.sent.remove(id);
+    }
+}
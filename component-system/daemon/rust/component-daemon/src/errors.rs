@@ -0,0 +1,69 @@
+use async_graphql::{Error, ErrorExtensions};
+use serde_json::Value;
+
+// ========================
+// ERROR CODES
+// ========================
+
+// Machine-readable error codes shared by GraphQL and REST. Every GraphQL
+// error carries `extensions { code, retryable, details }`, so renderers can
+// branch on the code instead of parsing messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    Internal,
+    NotFound,
+    VersionConflict,
+    Invalid,
+    InvalidArgument,
+    InvalidSelector,
+    PayloadTooLarge,
+    FeatureDisabled,
+    StateVersionExpired,
+    HistoryUnavailable,
+    ResumeUnavailable,
+    QuotaExceeded,
+    // A replication follower; writes go to the primary
+    ReadOnly,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::Internal => "INTERNAL",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::VersionConflict => "VERSION_CONFLICT",
+            ErrorCode::Invalid => "INVALID",
+            ErrorCode::InvalidArgument => "INVALID_ARGUMENT",
+            ErrorCode::InvalidSelector => "INVALID_SELECTOR",
+            ErrorCode::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            ErrorCode::FeatureDisabled => "FEATURE_DISABLED",
+            ErrorCode::StateVersionExpired => "STATE_VERSION_EXPIRED",
+            ErrorCode::HistoryUnavailable => "HISTORY_UNAVAILABLE",
+            ErrorCode::ResumeUnavailable => "RESUME_UNAVAILABLE",
+            ErrorCode::QuotaExceeded => "QUOTA_EXCEEDED",
+            ErrorCode::ReadOnly => "READ_ONLY",
+        }
+    }
+
+    // Whether sending the same request again may succeed. The others need a
+    // changed request first, e.g. a refetch or a fresh cursor. Over quota,
+    // it succeeds once `details.resetsAt` has passed.
+    pub fn retryable(&self) -> bool {
+        matches!(self, ErrorCode::Internal | ErrorCode::QuotaExceeded)
+    }
+}
+
+// `details` is an object with code-specific fields, empty when there are none
+pub fn graphql_error(code: ErrorCode, message: impl Into<String>, details: Option<Value>) -> Error {
+    let details = details.unwrap_or_else(|| Value::Object(Default::default()));
+    let details = async_graphql::Value::from_json(details).unwrap_or_default();
+    Error::new(message).extend_with(|_, ext| {
+        ext.set("code", code.as_str());
+        ext.set("retryable", code.retryable());
+        ext.set("details", details.clone());
+    })
+}
+
+pub fn missing_daemon() -> Error {
+    graphql_error(ErrorCode::Internal, "ComponentDaemon not found in context", None)
+}
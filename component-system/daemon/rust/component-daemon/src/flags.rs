@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use utoipa::ToSchema;
+
+// ========================
+// FEATURE FLAGS
+// ========================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Flag {
+    // Registry updates identical to the stored component aren't re-broadcast
+    Dedup,
+    // The rendererDelta subscription
+    DeltaUpdates,
+    // The GraphQL IDE page
+    Playground,
+    // Outbound sinks such as interaction webhooks
+    Sinks,
+}
+
+impl Flag {
+    pub const ALL: [Flag; 4] = [Flag::Dedup, Flag::DeltaUpdates, Flag::Playground, Flag::Sinks];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Flag::Dedup => "dedup",
+            Flag::DeltaUpdates => "deltaUpdates",
+            Flag::Playground => "playground",
+            Flag::Sinks => "sinks",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Flag> {
+        Flag::ALL.into_iter().find(|flag| flag.name() == name)
+    }
+
+    // Without a config file or override: the behaviors that predate flags
+    // stay on, the newer ones are opt-in
+    fn default_enabled(&self) -> bool {
+        matches!(self, Flag::Playground | Flag::Sinks)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FlagSource {
+    Default,
+    File,
+    Override,
+}
+
+impl FlagSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FlagSource::Default => "default",
+            FlagSource::File => "file",
+            FlagSource::Override => "override",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FlagState {
+    pub flag: Flag,
+    pub enabled: bool,
+    pub source: FlagSource,
+}
+
+// Lets one build run with different capabilities per deployment.
+// FEATURE_FLAGS_PATH points at a JSON object of flag name → bool, e.g.
+// `{"dedup": true, "playground": false}`; flags it leaves out keep their
+// defaults. Overrides set through the admin API win until cleared and are
+// not persisted. Every check reads the current value, so changes apply to
+// the next request or update.
+#[derive(Default)]
+pub struct FeatureFlags {
+    file: HashMap<Flag, bool>,
+    overrides: DashMap<Flag, bool>,
+}
+
+impl FeatureFlags {
+    pub fn from_env() -> Result<Self> {
+        let Ok(path) = std::env::var("FEATURE_FLAGS_PATH") else {
+            return Ok(Self::default());
+        };
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read feature flags {path}"))?;
+        let file: HashMap<Flag, bool> = serde_json::from_str(&raw)
+            .with_context(|| format!("Invalid feature flags {path}"))?;
+        info!("🚩 Daemon: Loaded {} feature flags from {}", file.len(), path);
+        Ok(Self {
+            file,
+            overrides: DashMap::new(),
+        })
+    }
+
+    pub fn enabled(&self, flag: Flag) -> bool {
+        self.state(flag).enabled
+    }
+
+    pub fn state(&self, flag: Flag) -> FlagState {
+        let (enabled, source) = match (self.overrides.get(&flag), self.file.get(&flag)) {
+            (Some(enabled), _) => (*enabled, FlagSource::Override),
+            (None, Some(enabled)) => (*enabled, FlagSource::File),
+            (None, None) => (flag.default_enabled(), FlagSource::Default),
+        };
+        FlagState {
+            flag,
+            enabled,
+            source,
+        }
+    }
+
+    pub fn list(&self) -> Vec<FlagState> {
+        Flag::ALL.into_iter().map(|flag| self.state(flag)).collect()
+    }
+
+    pub fn set_override(&self, flag: Flag, enabled: bool) -> FlagState {
+        info!("🚩 Daemon: Feature {} overridden to {}", flag.name(), enabled);
+        self.overrides.insert(flag, enabled);
+        self.state(flag)
+    }
+
+    // Back to the file or default value
+    pub fn clear_override(&self, flag: Flag) -> FlagState {
+        if self.overrides.remove(&flag).is_some() {
+            info!("🚩 Daemon: Feature {} override cleared", flag.name());
+        }
+        self.state(flag)
+    }
+
+    pub fn render(&self, out: &mut String) {
+        out.push_str("# HELP daemon_feature_enabled Whether a feature flag is on (1) or off (0).\n");
+        out.push_str("# TYPE daemon_feature_enabled gauge\n");
+        for state in self.list() {
+            let _ = writeln!(
+                out,
+                "daemon_feature_enabled{{flag=\"{}\",source=\"{}\"}} {}",
+                state.flag.name(),
+                state.source.label(),
+                u8::from(state.enabled)
+            );
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_graphql::{Enum, SimpleObject};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::config::env_parse;
+use crate::payload::{FormData, FormField, TypedComponent};
+use crate::validation::Violation;
+use crate::{ComponentDaemon, ComponentType, WriteError};
+
+// ========================
+// FORM SUBMISSIONS
+// ========================
+
+#[derive(Clone, Copy, Debug, Serialize, Enum, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SubmissionStatus {
+    // Kept locally only; bidirectional mode is off
+    Stored,
+    Forwarded,
+    UpstreamFailed,
+}
+
+#[derive(Clone, Debug, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct FormSubmission {
+    pub id: String,
+    pub form_id: String,
+    pub form_version: u64,
+    pub values: serde_json::Value,
+    pub submitted_at: DateTime<Utc>,
+    pub status: SubmissionStatus,
+}
+
+#[derive(Clone, Debug, SimpleObject)]
+pub struct SubmitFormResult {
+    pub success: bool,
+    // Absent when the values failed validation
+    pub submission: Option<FormSubmission>,
+    pub violations: Vec<Violation>,
+    pub upstream_error: Option<String>,
+}
+
+// Keeps the latest FORM_SUBMISSIONS_RETAIN submissions in memory.
+// FORM_UPSTREAM_MUTATION is sent to the registry in bidirectional mode with
+// $formId, $submissionId, $values and $submittedAt, and the renderer gets the
+// registry's answer (or FORM_UPSTREAM_TIMEOUT_MS passing) back as the result.
+pub struct FormSubmissions {
+    recent: Mutex<VecDeque<FormSubmission>>,
+    retain: usize,
+    upstream_mutation: String,
+    upstream_timeout: Duration,
+}
+
+impl FormSubmissions {
+    pub fn from_env() -> Self {
+        Self {
+            recent: Mutex::new(VecDeque::new()),
+            retain: env_parse("FORM_SUBMISSIONS_RETAIN", 1000),
+            upstream_mutation: std::env::var("FORM_UPSTREAM_MUTATION").unwrap_or_else(|_| {
+                "mutation Submit($formId: String!, $submissionId: String!, $values: JSON!, $submittedAt: String!) { \
+                 submitForm(formId: $formId, submissionId: $submissionId, values: $values, submittedAt: $submittedAt) }"
+                    .to_string()
+            }),
+            upstream_timeout: Duration::from_millis(env_parse("FORM_UPSTREAM_TIMEOUT_MS", 5000)),
+        }
+    }
+
+    fn store(&self, submission: FormSubmission) {
+        let mut recent = self.recent.lock().unwrap();
+        recent.push_back(submission);
+        while recent.len() > self.retain {
+            recent.pop_front();
+        }
+    }
+
+    // Newest first
+    pub fn list(&self, form_id: Option<&str>) -> Vec<FormSubmission> {
+        self.recent
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|submission| form_id.is_none_or(|id| submission.form_id == id))
+            .cloned()
+            .collect()
+    }
+}
+
+pub async fn submit(
+    daemon: &ComponentDaemon,
+    id: &str,
+    values: serde_json::Value,
+) -> Result<SubmitFormResult, WriteError> {
+    let form = daemon.get_component(id).ok_or(WriteError::NotFound)?;
+    if form.r#type != ComponentType::Form {
+        return Err(WriteError::Invalid(format!("{id} is not a form")));
+    }
+    let TypedComponent::Form(definition) = TypedComponent::from_component(&form) else {
+        return Err(WriteError::Invalid(format!(
+            "{id} has no usable field definitions"
+        )));
+    };
+
+    let violations = validate_values(&definition, &values);
+    if !violations.is_empty() {
+        return Ok(SubmitFormResult {
+            success: false,
+            submission: None,
+            violations,
+            upstream_error: None,
+        });
+    }
+
+    let mut submission = FormSubmission {
+        id: uuid::Uuid::new_v4().to_string(),
+        form_id: form.id.clone(),
+        form_version: form.version,
+        values,
+        submitted_at: Utc::now(),
+        status: SubmissionStatus::Stored,
+    };
+    let mut upstream_error = None;
+    let submissions = daemon.form_submissions();
+    if daemon.upstream().enabled() {
+        let variables = serde_json::json!({
+            "formId": submission.form_id,
+            "submissionId": submission.id,
+            "values": submission.values,
+            "submittedAt": submission.submitted_at.to_rfc3339(),
+        });
+        let response = daemon
+            .upstream()
+            .request(
+                &submissions.upstream_mutation,
+                variables,
+                submissions.upstream_timeout,
+            )
+            .await;
+        upstream_error = match response {
+            Ok(payload) => payload.get("errors").map(|errors| {
+                errors[0]["message"]
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| errors.to_string())
+            }),
+            Err(e) => Some(e),
+        };
+        submission.status = match upstream_error {
+            None => SubmissionStatus::Forwarded,
+            Some(_) => SubmissionStatus::UpstreamFailed,
+        };
+    }
+
+    match &upstream_error {
+        None => info!("📝 Daemon: Form {} submitted ({:?})", id, submission.status),
+        Some(e) => warn!(
+            "📝 Daemon: Form {} submission not accepted upstream: {}",
+            id, e
+        ),
+    }
+    submissions.store(submission.clone());
+    Ok(SubmitFormResult {
+        success: upstream_error.is_none(),
+        submission: Some(submission),
+        violations: Vec::new(),
+        upstream_error,
+    })
+}
+
+// Checks submitted values against the form's fields: no unknown names,
+// required fields present and non-empty, and values matching the field type.
+pub fn validate_values(form: &FormData, values: &serde_json::Value) -> Vec<Violation> {
+    let Some(values) = values.as_object() else {
+        return vec![Violation::new(
+            "",
+            "submission.shape",
+            "Values must be an object keyed by field name",
+        )];
+    };
+    let mut violations = Vec::new();
+    for name in values.keys() {
+        if !form.fields.iter().any(|field| &field.name == name) {
+            violations.push(Violation::new(
+                format!("/{name}"),
+                "submission.unknown_field",
+                format!("Form has no field '{name}'"),
+            ));
+        }
+    }
+    for field in &form.fields {
+        let path = format!("/{}", field.name);
+        match values.get(&field.name) {
+            None | Some(serde_json::Value::Null) => {
+                if field.required {
+                    violations.push(Violation::new(
+                        path,
+                        "submission.required",
+                        "Field is required",
+                    ));
+                }
+            }
+            Some(serde_json::Value::String(text)) if text.trim().is_empty() && field.required => {
+                violations.push(Violation::new(
+                    path,
+                    "submission.required",
+                    "Field is required",
+                ));
+            }
+            Some(value) => {
+                if let Some(message) = type_mismatch(field, value) {
+                    violations.push(Violation::new(path, "submission.type", message));
+                }
+            }
+        }
+    }
+    violations
+}
+
+fn type_mismatch(field: &FormField, value: &serde_json::Value) -> Option<String> {
+    let ok = match field.r#type.as_str() {
+        "number" | "range" => {
+            value.is_number()
+                || value
+                    .as_str()
+                    .is_some_and(|v| v.trim().parse::<f64>().is_ok())
+        }
+        "checkbox" => value.is_boolean(),
+        "email" => value.as_str().is_some_and(|v| {
+            v.split_once('@')
+                .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'))
+        }),
+        "date" => value
+            .as_str()
+            .is_some_and(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d").is_ok()),
+        // text, textarea, password, tel, select and anything newer
+        _ => value.is_string(),
+    };
+    (!ok).then(|| format!("Expected a {} value", field.r#type))
+}
@@ -0,0 +1,97 @@
+use std::collections::{BTreeSet, HashSet};
+
+use async_graphql::SimpleObject;
+use dashmap::DashMap;
+
+use crate::{Component, ComponentDaemon};
+
+// ========================
+// RELATIONSHIP GRAPH
+// ========================
+
+// Parent -> children index over stored components. Children may arrive
+// before their parent; the link is kept and resolves once the parent exists.
+#[derive(Default)]
+pub struct ComponentGraph {
+    children: DashMap<String, BTreeSet<String>>,
+    parents: DashMap<String, String>,
+}
+
+impl ComponentGraph {
+    // Called on every publish; moves the child if its parent changed
+    pub fn link(&self, id: &str, parent_id: Option<&str>) {
+        let previous = match parent_id {
+            Some(parent_id) if parent_id != id => {
+                self.parents.insert(id.to_string(), parent_id.to_string())
+            }
+            _ => self.parents.remove(id).map(|(_, parent)| parent),
+        };
+        if previous.as_deref() == parent_id {
+            return;
+        }
+        if let Some(previous) = previous {
+            self.detach(id, &previous);
+        }
+        if let Some(parent_id) = parent_id.filter(|parent_id| *parent_id != id) {
+            self.children
+                .entry(parent_id.to_string())
+                .or_default()
+                .insert(id.to_string());
+        }
+    }
+
+    // Children of a removed component keep their parentId, so they reattach
+    // if it comes back.
+    pub fn remove(&self, id: &str) {
+        if let Some((_, parent)) = self.parents.remove(id) {
+            self.detach(id, &parent);
+        }
+    }
+
+    pub fn children_of(&self, id: &str) -> Vec<String> {
+        self.children
+            .get(id)
+            .map(|children| children.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn detach(&self, id: &str, parent: &str) {
+        self.children.remove_if_mut(parent, |_, children| {
+            children.remove(id);
+            children.is_empty()
+        });
+    }
+}
+
+#[derive(Clone, Debug, SimpleObject)]
+pub struct ComponentTreeNode {
+    pub component: Component,
+    // 0 for the root
+    pub depth: u32,
+}
+
+// Depth-first, children ordered by id. Cycles are cut at the first repeat.
+pub fn tree(
+    daemon: &ComponentDaemon,
+    root_id: &str,
+    max_depth: u32,
+) -> Option<Vec<ComponentTreeNode>> {
+    let root = daemon.get_component(root_id)?;
+    let mut visited = HashSet::from([root.id.clone()]);
+    let mut nodes = Vec::new();
+    let mut stack = vec![(root, 0)];
+    while let Some((component, depth)) = stack.pop() {
+        if depth < max_depth {
+            let children = daemon.graph().children_of(&component.id);
+            for child_id in children.into_iter().rev() {
+                if visited.insert(child_id.clone()) {
+                    if let Some(child) = daemon.get_component(&child_id) {
+                        stack.push((child, depth + 1));
+                    }
+                }
+            }
+        }
+        nodes.push(ComponentTreeNode { component, depth });
+    }
+    Some(nodes)
+}
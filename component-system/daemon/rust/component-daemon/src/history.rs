@@ -0,0 +1,384 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use chrono::{DateTime, Utc};
+use crossbeam_queue::SegQueue;
+
+use crate::config::env_parse;
+use crate::memory::{approx_size, ByteGauge};
+use crate::{Component, ComponentRemoval};
+
+// ========================
+// BROADCAST HISTORY
+// ========================
+
+pub enum HistoryEvent {
+    Upsert(Component),
+    Removed(ComponentRemoval),
+}
+
+struct Recorded {
+    at: DateTime<Utc>,
+    // Cached approximate size of `event`
+    bytes: u64,
+    event: HistoryEvent,
+}
+
+struct Buffer {
+    entries: VecDeque<Recorded>,
+    // Highest sequence number dropped from the front so far
+    evicted_through: u64,
+    // Component set as of the last evicted event, so time-travel queries can
+    // replay the buffer on top of it.
+    base: HashMap<String, Component>,
+    base_at: Option<DateTime<Utc>>,
+}
+
+impl HistoryEvent {
+    fn approx_size(&self) -> u64 {
+        match self {
+            HistoryEvent::Upsert(component) => approx_size(component),
+            HistoryEvent::Removed(removal) => {
+                (std::mem::size_of::<ComponentRemoval>() + removal.id.len()) as u64
+            }
+        }
+    }
+}
+
+impl Buffer {
+    fn apply(state: &mut HashMap<String, Component>, event: &HistoryEvent) {
+        match event {
+            HistoryEvent::Upsert(component) => {
+                state.insert(component.id.clone(), component.clone());
+            }
+            HistoryEvent::Removed(removal) => {
+                state.remove(&removal.id);
+            }
+        }
+    }
+}
+
+// Bounded buffer of recent broadcasts and removals, used to resume
+// subscriptions and to answer point-in-time queries.
+pub struct History {
+    // Ingest appends here without blocking; whoever next holds `buffer`
+    // folds staged events in.
+    staged: SegQueue<Recorded>,
+    buffer: Mutex<Buffer>,
+    capacity: usize,
+    next_seq: AtomicU64,
+    received: AtomicU64,
+    // Every recorded upsert or removal; identifies the current component state
+    recorded: AtomicU64,
+    bytes: ByteGauge,
+}
+
+pub struct ResumeUnavailable {
+    pub oldest_seq: u64,
+    pub latest_seq: u64,
+    // A component removed after the cursor; replays only carry upserts
+    pub removed: Option<String>,
+}
+
+impl std::fmt::Display for ResumeUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.removed {
+            Some(id) => write!(f, "{} was removed since", id),
+            None => write!(f, "history covers {}..={}", self.oldest_seq, self.latest_seq),
+        }
+    }
+}
+
+impl History {
+    // HISTORY_CAPACITY bounds how many events a resuming renderer or a
+    // time-travel query can look back over.
+    pub fn from_env() -> Self {
+        Self::new(env_parse("HISTORY_CAPACITY", 1000))
+    }
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            staged: SegQueue::new(),
+            buffer: Mutex::new(Buffer {
+                entries: VecDeque::new(),
+                evicted_through: 0,
+                base: HashMap::new(),
+                base_at: None,
+            }),
+            capacity,
+            next_seq: AtomicU64::new(1),
+            received: AtomicU64::new(0),
+            recorded: AtomicU64::new(0),
+            bytes: ByteGauge::default(),
+        }
+    }
+
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn latest_seq(&self) -> u64 {
+        self.next_seq.load(Ordering::Relaxed) - 1
+    }
+
+    // Keeps seqs handed out here ahead of ones replicated from a primary
+    pub fn advance_past(&self, seq: u64) {
+        self.next_seq.fetch_max(seq + 1, Ordering::Relaxed);
+    }
+
+    pub fn record(&self, event: HistoryEvent) {
+        if matches!(event, HistoryEvent::Upsert(_)) {
+            self.received.fetch_add(1, Ordering::Relaxed);
+        }
+        let bytes = event.approx_size();
+        self.bytes.add(bytes);
+        self.recorded.fetch_add(1, Ordering::Relaxed);
+        self.staged.push(Recorded {
+            at: Utc::now(),
+            bytes,
+            event,
+        });
+        // Never wait on the buffer here: if it's busy, the holder or the next
+        // reader picks this event up.
+        if let Ok(mut buffer) = self.buffer.try_lock() {
+            self.fold_staged(&mut buffer);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Buffer> {
+        let mut buffer = self.buffer.lock().unwrap();
+        self.fold_staged(&mut buffer);
+        buffer
+    }
+
+    fn fold_staged(&self, buffer: &mut Buffer) {
+        while let Some(recorded) = self.staged.pop() {
+            buffer.entries.push_back(recorded);
+        }
+        while buffer.entries.len() > self.capacity {
+            let Some(evicted) = buffer.entries.pop_front() else {
+                break;
+            };
+            buffer.base_at = Some(evicted.at);
+            // Evicted events move into the base snapshot; an upsert keeps its
+            // bytes there, and whatever it replaces or removes is released.
+            let replaced = match evicted.event {
+                HistoryEvent::Upsert(component) => {
+                    buffer.evicted_through = buffer.evicted_through.max(component.seq);
+                    buffer.base.insert(component.id.clone(), component)
+                }
+                HistoryEvent::Removed(removal) => {
+                    self.bytes.sub(evicted.bytes);
+                    buffer.base.remove(&removal.id)
+                }
+            };
+            if let Some(replaced) = replaced {
+                self.bytes.sub(approx_size(&replaced));
+            }
+        }
+    }
+
+    // Buffered events plus the base snapshot
+    pub fn approx_bytes(&self) -> u64 {
+        self.bytes.get()
+    }
+
+    pub fn state_version(&self) -> u64 {
+        self.recorded.load(Ordering::Relaxed)
+    }
+
+    pub fn received_total(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    // Everything broadcast after `after_seq`, oldest first. Fails when part of
+    // that range was already evicted, when `after_seq` comes from before a
+    // restart, or when a component was removed in between, since the
+    // renderer then has to refetch instead.
+    pub fn replay_after(&self, after_seq: u64) -> Result<Vec<Component>, ResumeUnavailable> {
+        let buffer = self.lock();
+        let latest_seq = self.latest_seq();
+        let unavailable = |removed| ResumeUnavailable {
+            oldest_seq: buffer.evicted_through + 1,
+            latest_seq,
+            removed,
+        };
+        if after_seq < buffer.evicted_through || after_seq > latest_seq {
+            return Err(unavailable(None));
+        }
+
+        // Removals carry no seq; any recorded after the last upsert the
+        // renderer already has falls in the gap
+        let delivered = buffer.entries.iter().rposition(|recorded| {
+            matches!(&recorded.event, HistoryEvent::Upsert(component) if component.seq <= after_seq)
+        });
+        let gap = delivered.map_or(0, |position| position + 1);
+        if let Some(removal) = buffer.entries.iter().skip(gap).find_map(|recorded| match &recorded.event {
+            HistoryEvent::Removed(removal) => Some(removal),
+            HistoryEvent::Upsert(_) => None,
+        }) {
+            return Err(unavailable(Some(removal.id.clone())));
+        }
+
+        let mut replay: Vec<Component> = buffer
+            .entries
+            .iter()
+            .filter_map(|recorded| match &recorded.event {
+                HistoryEvent::Upsert(component) if component.seq > after_seq => {
+                    Some(component.clone())
+                }
+                _ => None,
+            })
+            .collect();
+        replay.sort_by_key(|component| component.seq);
+        Ok(replay)
+    }
+
+    // Every version of each id still known, newest first; ids without any
+    // map to an empty list
+    pub fn versions_of(&self, ids: &[&str]) -> HashMap<String, Vec<Component>> {
+        let buffer = self.lock();
+        let mut versions: HashMap<String, Vec<Component>> =
+            ids.iter().map(|id| (id.to_string(), Vec::new())).collect();
+        let upserts = buffer.base.values().chain(buffer.entries.iter().filter_map(|recorded| match &recorded.event {
+            HistoryEvent::Upsert(component) => Some(component),
+            HistoryEvent::Removed(_) => None,
+        }));
+        for component in upserts {
+            if let Some(list) = versions.get_mut(&component.id) {
+                list.push(component.clone());
+            }
+        }
+        for list in versions.values_mut() {
+            list.sort_by_key(|component| std::cmp::Reverse(component.seq));
+        }
+        versions
+    }
+
+    // The component set as it stood at `at`. Err carries the earliest moment
+    // the buffer can still reconstruct.
+    pub fn state_at(&self, at: DateTime<Utc>) -> Result<Vec<Component>, DateTime<Utc>> {
+        let buffer = self.lock();
+        if let Some(base_at) = buffer.base_at.filter(|base_at| at < *base_at) {
+            return Err(base_at);
+        }
+
+        let mut state = buffer.base.clone();
+        // Concurrent writers may stage events slightly out of time order
+        for recorded in buffer.entries.iter().filter(|recorded| recorded.at <= at) {
+            Buffer::apply(&mut state, &recorded.event);
+        }
+        let mut components: Vec<Component> = state.into_values().collect();
+        components.sort_by_key(|component| component.seq);
+        Ok(components)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::RemovalReason;
+
+    fn upsert(history: &History, id: &str, title: &str) -> u64 {
+        let mut component: Component = serde_json::from_value(json!({
+            "id": id,
+            "type": "CARD",
+            "data": { "title": title },
+            "createdAt": Utc::now(),
+        }))
+        .unwrap();
+        component.seq = history.next_seq();
+        component.version = component.seq;
+        let seq = component.seq;
+        history.record(HistoryEvent::Upsert(component));
+        seq
+    }
+
+    fn remove(history: &History, id: &str) {
+        history.record(HistoryEvent::Removed(ComponentRemoval {
+            id: id.to_string(),
+            reason: RemovalReason::Deleted,
+            removed_at: Utc::now(),
+        }));
+    }
+
+    fn seqs(components: &[Component]) -> Vec<u64> {
+        components.iter().map(|component| component.seq).collect()
+    }
+
+    #[test]
+    fn replays_upserts_after_a_seq_in_order() {
+        let history = History::new(10);
+        upsert(&history, "a", "first");
+        upsert(&history, "b", "first");
+        upsert(&history, "a", "second");
+
+        assert_eq!(seqs(&history.replay_after(0).ok().unwrap()), [1, 2, 3]);
+        assert_eq!(seqs(&history.replay_after(1).ok().unwrap()), [2, 3]);
+        assert!(history.replay_after(3).ok().unwrap().is_empty());
+
+        // A cursor from before a restart is ahead of anything handed out
+        let ahead = history.replay_after(4).err().unwrap();
+        assert_eq!(ahead.latest_seq, 3);
+    }
+
+    #[test]
+    fn refuses_to_resume_across_a_removal() {
+        let history = History::new(10);
+        upsert(&history, "a", "first");
+        upsert(&history, "b", "first");
+        remove(&history, "a");
+
+        // Replays carry only upserts, so the removal would be lost
+        for after_seq in [0, 1, 2] {
+            let removed = history.replay_after(after_seq).err().unwrap();
+            assert_eq!(removed.removed.as_deref(), Some("a"));
+        }
+
+        // A renderer that already saw what followed the removal can resume
+        upsert(&history, "c", "first");
+        assert!(history.replay_after(3).ok().unwrap().is_empty());
+        assert!(history.replay_after(2).is_err());
+    }
+
+    #[test]
+    fn refuses_to_resume_across_evicted_events() {
+        let history = History::new(2);
+        for id in ["a", "b", "c"] {
+            upsert(&history, id, "first");
+        }
+
+        let evicted = history.replay_after(0).err().unwrap();
+        assert_eq!(evicted.oldest_seq, 2);
+        assert_eq!(evicted.latest_seq, 3);
+        assert_eq!(seqs(&history.replay_after(1).ok().unwrap()), [2, 3]);
+    }
+
+    #[test]
+    fn keeps_evicted_state_in_the_base_snapshot() {
+        let before = Utc::now() - chrono::Duration::seconds(1);
+        let history = History::new(2);
+        upsert(&history, "a", "first");
+        upsert(&history, "b", "first");
+        upsert(&history, "a", "second");
+        remove(&history, "b");
+        upsert(&history, "c", "first");
+
+        // Only the removal and the last upsert are still buffered
+        let state = history.state_at(Utc::now()).unwrap();
+        let titles: Vec<(&str, &serde_json::Value)> = state
+            .iter()
+            .map(|component| (component.id.as_str(), &component.data["title"]))
+            .collect();
+        assert_eq!(titles, [("a", &json!("second")), ("c", &json!("first"))]);
+        assert!(history.state_at(before).is_err());
+
+        let versions = history.versions_of(&["a", "b", "z"]);
+        assert_eq!(seqs(&versions["a"]), [3]);
+        assert_eq!(seqs(&versions["b"]), [2]);
+        assert!(versions["z"].is_empty());
+    }
+}
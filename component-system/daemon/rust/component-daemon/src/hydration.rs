@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request};
+use tracing::warn;
+
+use crate::config::env_parse;
+
+// ========================
+// STARTUP HYDRATION
+// ========================
+
+// Operation id of the hydration query when it goes over the registry socket
+pub const OPERATION_ID: &str = "registry-hydrate";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HydrationMode {
+    Off,
+    // Sent over the registry WebSocket next to the subscription
+    Ws,
+    // POSTed to the registry's HTTP GraphQL endpoint
+    Http,
+}
+
+// On every registry connection the daemon loads the registry's current
+// components with REGISTRY_HYDRATION_QUERY, so renderers see the full state
+// after a restart rather than only what changes afterwards.
+// REGISTRY_HYDRATION picks the transport (ws, http or off). The daemon reports
+// ready once the first hydration finished, or after
+// REGISTRY_HYDRATION_TIMEOUT_SECS if the registry never answers.
+pub struct Hydration {
+    mode: HydrationMode,
+    query: String,
+    pub timeout: Duration,
+    hydrated: AtomicBool,
+    client: Client<HttpConnector>,
+}
+
+impl Hydration {
+    pub fn from_env() -> Self {
+        let mode = match std::env::var("REGISTRY_HYDRATION").as_deref().map(str::trim) {
+            Ok("off") => HydrationMode::Off,
+            Ok("http") => HydrationMode::Http,
+            Ok("ws") | Err(_) => HydrationMode::Ws,
+            Ok(other) => {
+                warn!("⚠️ Daemon: Ignoring invalid REGISTRY_HYDRATION='{}'", other);
+                HydrationMode::Ws
+            }
+        };
+        Self {
+            mode,
+            query: std::env::var("REGISTRY_HYDRATION_QUERY")
+                .unwrap_or_else(|_| "{ components { id type data createdAt } }".to_string()),
+            timeout: Duration::from_secs(env_parse("REGISTRY_HYDRATION_TIMEOUT_SECS", 30)),
+            hydrated: AtomicBool::new(false),
+            client: Client::new(),
+        }
+    }
+
+    pub fn mode(&self) -> HydrationMode {
+        self.mode
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.mode == HydrationMode::Off || self.hydrated.load(Ordering::Relaxed)
+    }
+
+    // True the first time only
+    pub fn mark_ready(&self) -> bool {
+        !self.hydrated.swap(true, Ordering::Relaxed)
+    }
+
+    // subscriptions-transport-ws `start` for the hydration query
+    pub fn start_message(&self) -> String {
+        serde_json::json!({
+            "id": OPERATION_ID,
+            "type": "start",
+            "payload": { "query": self.query },
+        })
+        .to_string()
+    }
+
+    // `upstream` is the registry's WebSocket URL; the HTTP endpoint shares its path
+    pub async fn fetch_http(&self, upstream: &str) -> Result<Vec<serde_json::Value>> {
+        let url = upstream.replacen("ws://", "http://", 1);
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(&url)
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_vec(&serde_json::json!({ "query": self.query }))?))?;
+        let response = tokio::time::timeout(self.timeout, self.client.request(request))
+            .await
+            .map_err(|_| anyhow!("no response from {} within {:?}", url, self.timeout))??;
+        if !response.status().is_success() {
+            bail!("{} responded with {}", url, response.status());
+        }
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        let payload: serde_json::Value =
+            serde_json::from_slice(&body).context("registry answered with invalid JSON")?;
+        components_in(&payload)
+    }
+}
+
+// The component list under the query's single root field
+pub fn components_in(payload: &serde_json::Value) -> Result<Vec<serde_json::Value>> {
+    if let Some(errors) = payload.get("errors") {
+        bail!("registry returned errors: {}", errors);
+    }
+    let data = payload["data"].as_object().ok_or_else(|| anyhow!("response has no data"))?;
+    match data.values().next() {
+        Some(serde_json::Value::Array(components)) => Ok(components.clone()),
+        _ => bail!("expected a list of components in the response"),
+    }
+}
@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use async_graphql::{InputObject, SimpleObject};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::labels::Labels;
+use crate::{Component, ComponentDaemon, ComponentType, Source};
+
+// ========================
+// IMPORT & SEED
+// ========================
+
+// Same shape as a registry component; `createdAt` defaults to now.
+#[derive(Clone, Debug, Deserialize, InputObject)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentInput {
+    pub id: String,
+    pub r#type: ComponentType,
+    pub data: serde_json::Value,
+    pub created_at: Option<DateTime<Utc>>,
+    pub priority: Option<i32>,
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    #[graphql(default)]
+    pub related_ids: Vec<String>,
+    #[serde(default)]
+    #[graphql(default)]
+    pub labels: Labels,
+    // Hold the component back until this time
+    pub deliver_at: Option<DateTime<Utc>>,
+}
+
+impl From<ComponentInput> for Component {
+    fn from(input: ComponentInput) -> Self {
+        Component {
+            id: input.id,
+            r#type: input.r#type,
+            data: input.data,
+            created_at: input.created_at.unwrap_or_else(Utc::now),
+            priority: input.priority,
+            version: 0,
+            seq: 0,
+            upstream_seq: None,
+            parent_id: input.parent_id,
+            related_ids: input.related_ids,
+            labels: input.labels,
+            deliver_at: input.deliver_at,
+            acknowledged: None,
+            stamps: Default::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, SimpleObject)]
+pub struct ImportRejection {
+    pub id: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default, SimpleObject)]
+pub struct ImportResult {
+    pub imported: u32,
+    pub rejected: Vec<ImportRejection>,
+}
+
+// Runs each component through the normal ingest pipeline, so validation,
+// priorities, labels and debouncing apply exactly as for registry updates.
+pub async fn import(
+    daemon: &ComponentDaemon,
+    upstream: &str,
+    components: Vec<ComponentInput>,
+) -> ImportResult {
+    let mut result = ImportResult::default();
+    let limits = daemon.size_limits();
+    let mut batch_bytes = 0;
+    for input in components {
+        let id = input.id.clone();
+        // Components that would take the batch past BATCH_MAX_BYTES are
+        // turned away; smaller ones after them can still land
+        if limits.batch_max > 0 {
+            let bytes = limits.measure(&input.data);
+            if batch_bytes + bytes > limits.batch_max {
+                warn!("📏 Daemon: Import batch over {} bytes, rejecting {}", limits.batch_max, id);
+                result.rejected.push(ImportRejection {
+                    id,
+                    reason: format!("Batch is over the {} byte limit", limits.batch_max),
+                });
+                continue;
+            }
+            batch_bytes += bytes;
+        }
+        match daemon.ingest(upstream, input.into(), Source::Local).await {
+            Ok(()) => result.imported += 1,
+            Err(reason) => result.rejected.push(ImportRejection { id, reason }),
+        }
+    }
+    result
+}
+
+// Accepts a JSON array or JSON lines
+pub fn read_seed(path: &Path) -> Result<Vec<ComponentInput>> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read seed file {}", path.display()))?;
+    if raw.trim_start().starts_with('[') {
+        return serde_json::from_str(&raw)
+            .with_context(|| format!("Invalid seed file {}", path.display()));
+    }
+    raw.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Invalid seed file {} line {}", path.display(), n + 1))
+        })
+        .collect()
+}
+
+pub async fn seed(daemon: &ComponentDaemon, path: &Path) -> Result<()> {
+    let components = read_seed(path)?;
+    let total = components.len();
+    let result = import(daemon, "seed", components).await;
+    info!(
+        "🌱 Daemon: Seeded {} of {} components from {}",
+        result.imported,
+        total,
+        path.display()
+    );
+    for rejection in &result.rejected {
+        warn!(
+            "🌱 Daemon: Seed component {} rejected: {}",
+            rejection.id, rejection.reason
+        );
+    }
+    Ok(())
+}
+
+// `--seed <file>` from the command line
+pub fn seed_arg(args: &[String]) -> Result<Option<PathBuf>> {
+    match args.iter().position(|arg| arg == "--seed") {
+        Some(i) => args
+            .get(i + 1)
+            .map(|path| Some(PathBuf::from(path)))
+            .ok_or_else(|| anyhow!("--seed needs a file path")),
+        None => Ok(None),
+    }
+}
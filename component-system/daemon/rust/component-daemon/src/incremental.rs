@@ -0,0 +1,821 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+use async_graphql::parser::parse_query;
+use async_graphql::parser::types::{
+    Directive, DocumentOperations, ExecutableDocument, Field, OperationDefinition, OperationType,
+    Selection, SelectionSet,
+};
+use async_graphql::{
+    Context, Executor, Name, Positioned, QueryPathSegment, Request, Value as ConstValue, Variables,
+};
+use async_graphql_value::Value as QueryValue;
+use serde_json::{json, Value};
+use warp::http::header;
+use warp::reply::{Reply, Response};
+use warp::Filter;
+
+use crate::audit::RequestOrigin;
+use crate::auth::Principal;
+use crate::listeners::BearerAuth;
+use crate::quotas::ApiKey;
+use crate::versioning::ApiVersion;
+use crate::{api_version, request_origin, ComponentDaemon};
+
+// ========================
+// INCREMENTAL DELIVERY (@defer / @stream)
+// ========================
+
+const BOUNDARY: &str = "-";
+// Streamed list items sent per multipart part
+const STREAM_BATCH: usize = 50;
+
+// POST /graphql from clients that accept `multipart/mixed`. Queries using
+// @defer or @stream get an initial payload without the deferred fragments and
+// with streamed lists cut to `initialCount`, followed by incremental payloads
+// (deferSpec=20220824). Anything else is answered as plain JSON. The initial
+// part is executed and sent first; each deferred fragment and each batch of a
+// streamed list is then executed as its own follow-up. Every execution reads
+// the same pinned view, whose stateVersion goes out with the initial part.
+pub fn routes<E: Executor>(
+    executor: E,
+    daemon: ComponentDaemon,
+    auth: BearerAuth,
+) -> impl Filter<Extract = (Response,), Error = warp::Rejection> + Clone {
+    let accepts_multipart = warp::header::<String>("accept")
+        .and_then(|accept: String| async move {
+            if accept.contains("multipart/mixed") {
+                Ok(())
+            } else {
+                Err(warp::reject())
+            }
+        })
+        .untuple_one();
+
+    warp::path!("graphql")
+        .and(warp::post())
+        .and(accepts_multipart)
+        .and(warp::body::json())
+        .and(request_origin(&auth))
+        .and(api_version())
+        .and(auth.principal())
+        .and_then(move |request: Request, origin: RequestOrigin, version: ApiVersion, principal: Option<Principal>| {
+            let executor = executor.clone();
+            let daemon = daemon.clone();
+            let key = ApiKey::from_principal(principal.as_ref());
+            async move { Ok::<_, Infallible>(respond(executor, daemon, request, origin, version, key).await) }
+        })
+}
+
+async fn respond<E: Executor>(
+    executor: E,
+    daemon: ComponentDaemon,
+    request: Request,
+    origin: RequestOrigin,
+    version: ApiVersion,
+    key: ApiKey,
+) -> Response {
+    let Some(plan) = Plan::new(&request) else {
+        let response = executor.execute(request.data(origin).data(version).data(key)).await;
+        return async_graphql_warp::GraphQLResponse::from(response).into_response();
+    };
+
+    // Every directive is switched off: answer with one plain result
+    if plan.sites.is_empty() {
+        let response = executor
+            .execute(derive(&request, plan.print(Pass::Initial), &origin, version, &key))
+            .await;
+        return async_graphql_warp::GraphQLResponse::from(response).into_response();
+    }
+
+    let view = daemon.pages().view(&daemon);
+    let body = async_stream::stream! {
+        let execute = |query: String, windows: Arc<StreamWindows>| {
+            let request = derive(&request, query, &origin, version, &key).data(view.clone()).data(windows);
+            let executor = executor.clone();
+            async move { to_json(executor.execute(request).await) }
+        };
+
+        let windows = Arc::new(StreamWindows::new(plan.sites.iter().filter_map(|site| match site.kind {
+            SiteKind::Stream { initial, .. } => Some((site.path(), 0..initial)),
+            SiteKind::Defer { .. } => None,
+        })));
+        let mut initial = execute(plan.print(Pass::Initial), windows.clone()).await;
+        if let Some(data) = initial.get_mut("data").filter(|data| data.is_object()) {
+            for site in &plan.sites {
+                if let SiteKind::Stream { initial: count, .. } = site.kind {
+                    cut(data, site, 0..count, windows.applied(&site.path()));
+                }
+            }
+        }
+        initial["hasNext"] = json!(true);
+        initial["extensions"]["stateVersion"] = json!(view.state_version);
+        yield Ok::<_, Infallible>(part(&initial));
+
+        for site in &plan.sites {
+            match &site.kind {
+                SiteKind::Defer { label, keys, .. } => {
+                    let mut response = execute(plan.print(Pass::Site(site)), Arc::default()).await;
+                    let mut incremental = Vec::new();
+                    if let Some(data) = response.get_mut("data").filter(|data| data.is_object()) {
+                        walk(data, &site.keys, false, &mut Vec::new(), &mut |object, path| {
+                            let Value::Object(object) = object else {
+                                return;
+                            };
+                            let data: serde_json::Map<String, Value> = keys
+                                .iter()
+                                .filter_map(|key| object.get(key).map(|value| (key.clone(), value.clone())))
+                                .collect();
+                            let mut payload = json!({ "data": data, "path": path });
+                            if let Some(label) = label {
+                                payload["label"] = json!(label);
+                            }
+                            incremental.push(payload);
+                        });
+                    }
+                    attach_errors(&response, &mut incremental);
+                    if !incremental.is_empty() {
+                        yield Ok(part(&json!({ "incremental": incremental, "hasNext": true })));
+                    }
+                }
+                SiteKind::Stream { initial, label } => {
+                    let mut start = *initial;
+                    loop {
+                        let window = start..start + STREAM_BATCH;
+                        let windows = Arc::new(StreamWindows::new([(site.path(), window.clone())]));
+                        let mut response = execute(plan.print(Pass::Site(site)), windows.clone()).await;
+                        let mut incremental = Vec::new();
+                        // Another batch only while some occurrence filled this one
+                        let mut more = false;
+                        if let Some(data) = response.get_mut("data").filter(|data| data.is_object()) {
+                            for (mut path, items) in cut(data, site, window, windows.applied(&site.path())) {
+                                more |= items.len() == STREAM_BATCH;
+                                if items.is_empty() {
+                                    continue;
+                                }
+                                path.push(json!(start));
+                                let mut batch = json!({ "items": items, "path": path });
+                                if let Some(label) = label {
+                                    batch["label"] = json!(label);
+                                }
+                                incremental.push(batch);
+                            }
+                        }
+                        let failed = response.get("errors").is_some();
+                        attach_errors(&response, &mut incremental);
+                        if !incremental.is_empty() {
+                            yield Ok(part(&json!({ "incremental": incremental, "hasNext": true })));
+                        }
+                        if !more || failed {
+                            break;
+                        }
+                        start += STREAM_BATCH;
+                    }
+                }
+            }
+        }
+        yield Ok(part(&json!({ "hasNext": false })));
+        yield Ok(format!("\r\n--{BOUNDARY}--\r\n").into_bytes());
+    };
+
+    warp::http::Response::builder()
+        .header(
+            header::CONTENT_TYPE,
+            format!("multipart/mixed; boundary=\"{BOUNDARY}\"; deferSpec=20220824"),
+        )
+        .body(warp::hyper::Body::wrap_stream(body))
+        .unwrap_or_else(|_| warp::http::StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+fn derive(base: &Request, query: String, origin: &RequestOrigin, version: ApiVersion, key: &ApiKey) -> Request {
+    let mut request = Request::new(query).variables(base.variables.clone());
+    if let Some(name) = &base.operation_name {
+        request = request.operation_name(name);
+    }
+    request.extensions = base.extensions.clone();
+    request.data(origin.clone()).data(version).data(key.clone())
+}
+
+fn to_json(response: async_graphql::Response) -> Value {
+    serde_json::to_value(&response)
+        .unwrap_or_else(|e| json!({ "data": null, "errors": [{ "message": e.to_string() }] }))
+}
+
+fn part(payload: &Value) -> Vec<u8> {
+    format!("\r\n--{BOUNDARY}\r\nContent-Type: application/json; charset=utf-8\r\n\r\n{payload}")
+        .into_bytes()
+}
+
+// A follow-up's errors go on its first payload, or on one of their own
+fn attach_errors(response: &Value, incremental: &mut Vec<Value>) {
+    let Some(errors) = response.get("errors") else {
+        return;
+    };
+    match incremental.first_mut() {
+        Some(first) => first["errors"] = errors.clone(),
+        None => incremental.push(json!({ "data": null, "path": [], "errors": errors })),
+    }
+}
+
+// Calls `visit` with each value found under `keys` and its response path,
+// descending through lists on the way. `keep_lists` hands a list at the end
+// of `keys` over as is instead of visiting its items.
+fn walk(
+    value: &mut Value,
+    keys: &[String],
+    keep_lists: bool,
+    path: &mut Vec<Value>,
+    visit: &mut dyn FnMut(&mut Value, &[Value]),
+) {
+    match value {
+        Value::Array(items) if !(keys.is_empty() && keep_lists) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                path.push(json!(i));
+                walk(item, keys, keep_lists, path, visit);
+                path.pop();
+            }
+        }
+        _ if keys.is_empty() => visit(value, path),
+        Value::Object(object) => {
+            if let Some(child) = object.get_mut(&keys[0]) {
+                path.push(json!(keys[0]));
+                walk(child, &keys[1..], keep_lists, path, visit);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+// Cuts every occurrence of a streamed list down to `window`, unless its
+// resolver already did, and returns them with their paths
+fn cut(data: &mut Value, site: &Site, window: Range<usize>, applied: bool) -> Vec<(Vec<Value>, Vec<Value>)> {
+    let mut found = Vec::new();
+    walk(data, &site.keys, true, &mut Vec::new(), &mut |list, path| {
+        let Value::Array(items) = list else {
+            return;
+        };
+        if !applied {
+            let end = window.end.min(items.len());
+            let start = window.start.min(end);
+            *items = items.drain(start..end).collect();
+        }
+        found.push((path.to_vec(), items.clone()));
+    });
+    found
+}
+
+// ========================
+// STREAM WINDOWS
+// ========================
+
+// Which items of each streamed list one execution should produce, keyed by
+// response path without list indices. Resolvers that can slice before
+// resolving items call `window`; lists they return whole are cut afterwards.
+#[derive(Default)]
+pub struct StreamWindows {
+    windows: HashMap<String, Range<usize>>,
+    applied: Mutex<HashSet<String>>,
+}
+
+impl StreamWindows {
+    fn new(windows: impl IntoIterator<Item = (String, Range<usize>)>) -> Self {
+        Self {
+            windows: windows.into_iter().collect(),
+            applied: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn applied(&self, path: &str) -> bool {
+        self.applied.lock().unwrap().contains(path)
+    }
+}
+
+// The part of `items` the current field is asked for, or all of it outside
+// a streamed execution
+pub fn window<T>(ctx: &Context<'_>, items: Vec<T>) -> Vec<T> {
+    let (Some(windows), Some(node)) = (ctx.data_opt::<Arc<StreamWindows>>(), ctx.path_node.as_ref()) else {
+        return items;
+    };
+    let mut keys: Vec<&str> = std::iter::once(node)
+        .chain(node.parents())
+        .filter_map(|node| match node.segment {
+            QueryPathSegment::Name(name) => Some(name),
+            QueryPathSegment::Index(_) => None,
+        })
+        .collect();
+    keys.reverse();
+    let path = keys.join(".");
+    let Some(window) = windows.windows.get(&path) else {
+        return items;
+    };
+    windows.applied.lock().unwrap().insert(path);
+    items.into_iter().skip(window.start).take(window.len()).collect()
+}
+
+// ========================
+// PLAN
+// ========================
+
+// An @defer fragment or @stream field that isn't inside another one; nested
+// directives are delivered along with the enclosing site
+struct Site {
+    // Response keys from the root, without list indices: down to the object
+    // a deferred fragment applies to, or to the streamed field itself
+    keys: Vec<String>,
+    kind: SiteKind,
+}
+
+enum SiteKind {
+    Defer {
+        // Address of the fragment's selection, which with `keys` tells
+        // spreads of the same fragment apart
+        node: usize,
+        label: Option<String>,
+        // Response keys the fragment selects
+        keys: Vec<String>,
+    },
+    Stream {
+        initial: usize,
+        label: Option<String>,
+    },
+}
+
+impl Site {
+    fn path(&self) -> String {
+        self.keys.join(".")
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Pass<'a> {
+    // Everything outside deferred fragments
+    Initial,
+    // Only the selections leading to one site, and the site itself
+    Site(&'a Site),
+}
+
+struct Fragment<'a> {
+    name: Option<&'a Name>,
+    condition: Option<&'a Name>,
+    directives: &'a [Positioned<Directive>],
+    set: &'a SelectionSet,
+}
+
+struct Plan {
+    doc: ExecutableDocument,
+    operation_name: Option<String>,
+    variables: Variables,
+    sites: Vec<Site>,
+}
+
+impl Plan {
+    // None when the request is not a query mentioning @defer or @stream, or
+    // does not parse; those go through normal execution.
+    fn new(request: &Request) -> Option<Self> {
+        let doc = parse_query(&request.query).ok()?;
+        let mut plan = Self {
+            doc,
+            operation_name: request.operation_name.clone(),
+            variables: request.variables.clone(),
+            sites: Vec::new(),
+        };
+        let operation = plan.find_operation()?;
+        if operation.ty != OperationType::Query
+            || !plan.mentions(&operation.selection_set.node, &mut HashSet::new())
+        {
+            return None;
+        }
+        let mut sites = Vec::new();
+        plan.collect_sites(&operation.selection_set.node, &mut Vec::new(), &mut Vec::new(), &mut sites);
+        plan.sites = sites;
+        Some(plan)
+    }
+
+    fn find_operation(&self) -> Option<&OperationDefinition> {
+        match (&self.doc.operations, self.operation_name.as_deref()) {
+            (DocumentOperations::Single(operation), _) => Some(&operation.node),
+            (DocumentOperations::Multiple(operations), Some(name)) => operations
+                .iter()
+                .find(|(op_name, _)| op_name.as_str() == name)
+                .map(|(_, operation)| &operation.node),
+            (DocumentOperations::Multiple(operations), None) if operations.len() == 1 => {
+                operations.values().next().map(|operation| &operation.node)
+            }
+            _ => None,
+        }
+    }
+
+    fn operation(&self) -> &OperationDefinition {
+        self.find_operation().expect("checked in Plan::new")
+    }
+
+    fn directive<'a>(directives: &'a [Positioned<Directive>], name: &str) -> Option<&'a Directive> {
+        directives
+            .iter()
+            .map(|directive| &directive.node)
+            .find(|directive| directive.name.node.as_str() == name)
+    }
+
+    fn argument(&self, directive: &Directive, name: &str) -> Option<ConstValue> {
+        directive
+            .get_argument(name)?
+            .node
+            .clone()
+            .into_const_with(|var| self.variables.get(&var).cloned().ok_or(()))
+            .ok()
+    }
+
+    fn label(&self, directive: &Directive) -> Option<String> {
+        match self.argument(directive, "label") {
+            Some(ConstValue::String(label)) => Some(label),
+            _ => None,
+        }
+    }
+
+    // Label of an active @defer; `if: false` turns it into a plain fragment
+    fn deferred(&self, directives: &[Positioned<Directive>]) -> Option<Option<String>> {
+        let defer = Self::directive(directives, "defer")?;
+        if matches!(self.argument(defer, "if"), Some(ConstValue::Boolean(false))) {
+            return None;
+        }
+        Some(self.label(defer))
+    }
+
+    // (initialCount, label) of an active @stream
+    fn streamed(&self, directives: &[Positioned<Directive>]) -> Option<(usize, Option<String>)> {
+        let stream = Self::directive(directives, "stream")?;
+        if matches!(
+            self.argument(stream, "if"),
+            Some(ConstValue::Boolean(false))
+        ) {
+            return None;
+        }
+        let initial = match self.argument(stream, "initialCount") {
+            Some(ConstValue::Number(n)) => n.as_u64().unwrap_or(0) as usize,
+            _ => 0,
+        };
+        Some((initial, self.label(stream)))
+    }
+
+    fn fragment_set(&self, name: &Name) -> Option<&SelectionSet> {
+        self.doc
+            .fragments
+            .get(name)
+            .map(|fragment| &fragment.node.selection_set.node)
+    }
+
+    // None for fields and spreads of undefined fragments
+    fn fragment<'a>(&'a self, selection: &'a Selection) -> Option<Fragment<'a>> {
+        match selection {
+            Selection::Field(_) => None,
+            Selection::FragmentSpread(spread) => {
+                let name = &spread.node.fragment_name.node;
+                let definition = &self.doc.fragments.get(name)?.node;
+                Some(Fragment {
+                    name: Some(name),
+                    condition: Some(&definition.type_condition.node.on.node),
+                    directives: &spread.node.directives,
+                    set: &definition.selection_set.node,
+                })
+            }
+            Selection::InlineFragment(inline) => Some(Fragment {
+                name: None,
+                condition: inline.node.type_condition.as_ref().map(|condition| &condition.node.on.node),
+                directives: &inline.node.directives,
+                set: &inline.node.selection_set.node,
+            }),
+        }
+    }
+
+    // Whether either directive is written anywhere, active or not
+    fn mentions<'a>(&'a self, set: &'a SelectionSet, seen: &mut HashSet<&'a Name>) -> bool {
+        set.items.iter().any(|selection| {
+            let (directives, child) = match &selection.node {
+                Selection::Field(field) => (&field.node.directives, Some(&field.node.selection_set.node)),
+                Selection::FragmentSpread(spread) => {
+                    let name = &spread.node.fragment_name.node;
+                    (&spread.node.directives, seen.insert(name).then(|| self.fragment_set(name)).flatten())
+                }
+                Selection::InlineFragment(inline) => {
+                    (&inline.node.directives, Some(&inline.node.selection_set.node))
+                }
+            };
+            ["defer", "stream"]
+                .iter()
+                .any(|name| Self::directive(directives, name).is_some())
+                || child.is_some_and(|child| self.mentions(child, seen))
+        })
+    }
+
+    fn collect_sites<'a>(
+        &'a self,
+        set: &'a SelectionSet,
+        keys: &mut Vec<String>,
+        expanding: &mut Vec<&'a Name>,
+        out: &mut Vec<Site>,
+    ) {
+        for selection in &set.items {
+            if let Selection::Field(field) = &selection.node {
+                let field = &field.node;
+                keys.push(field.response_key().node.to_string());
+                match self.streamed(&field.directives) {
+                    Some((initial, label)) => out.push(Site {
+                        keys: keys.clone(),
+                        kind: SiteKind::Stream { initial, label },
+                    }),
+                    None => self.collect_sites(&field.selection_set.node, keys, expanding, out),
+                }
+                keys.pop();
+                continue;
+            }
+            let Some(fragment) = self.fragment(&selection.node) else {
+                continue;
+            };
+            if let Some(label) = self.deferred(fragment.directives) {
+                let mut fields = Vec::new();
+                self.response_keys(fragment.set, &mut fields, &mut HashSet::new());
+                out.push(Site {
+                    keys: keys.clone(),
+                    kind: SiteKind::Defer {
+                        node: address(&selection.node),
+                        label,
+                        keys: fields,
+                    },
+                });
+                continue;
+            }
+            if fragment.name.is_some_and(|name| expanding.contains(&name)) {
+                continue;
+            }
+            expanding.extend(fragment.name);
+            self.collect_sites(fragment.set, keys, expanding, out);
+            if fragment.name.is_some() {
+                expanding.pop();
+            }
+        }
+    }
+
+    fn response_keys<'a>(
+        &'a self,
+        set: &'a SelectionSet,
+        keys: &mut Vec<String>,
+        seen: &mut HashSet<&'a Name>,
+    ) {
+        for selection in &set.items {
+            match &selection.node {
+                Selection::Field(field) => {
+                    keys.push(field.node.response_key().node.to_string());
+                }
+                Selection::FragmentSpread(spread) => {
+                    let name = &spread.node.fragment_name.node;
+                    if let Some(set) = seen.insert(name).then(|| self.fragment_set(name)).flatten()
+                    {
+                        self.response_keys(set, keys, seen);
+                    }
+                }
+                Selection::InlineFragment(inline) => {
+                    self.response_keys(&inline.node.selection_set.node, keys, seen);
+                }
+            }
+        }
+    }
+
+    // ========================
+    // PRINTING
+    // ========================
+
+    // Prints the selected operation for one pass without @defer/@stream, plus
+    // only the fragments and variable definitions it still uses, so
+    // validation passes.
+    fn print(&self, pass: Pass<'_>) -> String {
+        let operation = self.operation();
+        let mut printer = Printer {
+            plan: self,
+            pass,
+            fragments: Vec::new(),
+            variables: HashSet::new(),
+            expanding: Vec::new(),
+        };
+
+        let mut body = String::new();
+        printer.directives(&operation.directives, &mut body);
+        printer.set(&operation.selection_set.node, &mut Vec::new(), false, &mut body);
+
+        let mut fragments = String::new();
+        let mut printed = HashSet::new();
+        while let Some(name) = printer.fragments.pop() {
+            if !printed.insert(name.clone()) {
+                continue;
+            }
+            let Some(fragment) = self.doc.fragments.get(&name) else {
+                continue;
+            };
+            let _ = write!(
+                fragments,
+                "\nfragment {} on {}",
+                name, fragment.node.type_condition.node.on.node
+            );
+            printer.directives(&fragment.node.directives, &mut fragments);
+            printer.set(&fragment.node.selection_set.node, &mut Vec::new(), true, &mut fragments);
+        }
+
+        let mut out = operation.ty.to_string();
+        if let Some(name) = &self.operation_name {
+            let _ = write!(out, " {name}");
+        }
+        let definitions: Vec<String> = operation
+            .variable_definitions
+            .iter()
+            .map(|definition| &definition.node)
+            .filter(|definition| printer.variables.contains(&definition.name.node))
+            .map(|definition| {
+                let mut printed =
+                    format!("${}: {}", definition.name.node, definition.var_type.node);
+                if let Some(default) = &definition.default_value {
+                    let _ = write!(printed, " = {}", default.node);
+                }
+                printed
+            })
+            .collect();
+        if !definitions.is_empty() {
+            let _ = write!(out, "({})", definitions.join(", "));
+        }
+        out.push_str(&body);
+        out.push_str(&fragments);
+        out
+    }
+}
+
+fn address(selection: &Selection) -> usize {
+    selection as *const Selection as usize
+}
+
+struct Printer<'a> {
+    plan: &'a Plan,
+    pass: Pass<'a>,
+    fragments: Vec<Name>,
+    variables: HashSet<Name>,
+    // Named fragments being expanded inline, to stop at cycles
+    expanding: Vec<&'a Name>,
+}
+
+impl<'a> Printer<'a> {
+    // `inside` is set within a site (or a fragment definition), where
+    // everything is printed as written. Outside, named spreads are expanded
+    // inline since one fragment can be spread both inside and outside a site.
+    // Returns false, leaving `out` untouched, when nothing in `set` belongs
+    // to the pass.
+    fn set(&mut self, set: &'a SelectionSet, keys: &mut Vec<String>, inside: bool, out: &mut String) -> bool {
+        let start = out.len();
+        out.push_str(" {");
+        let mut any = false;
+        for selection in &set.items {
+            any |= self.selection(&selection.node, keys, inside, out);
+        }
+        if !any {
+            match self.pass {
+                // Every selection was deferred; an empty set is not valid GraphQL
+                Pass::Initial => out.push_str(" __typename"),
+                Pass::Site(_) => {
+                    out.truncate(start);
+                    return false;
+                }
+            }
+        }
+        out.push_str(" }");
+        true
+    }
+
+    fn selection(&mut self, selection: &'a Selection, keys: &mut Vec<String>, inside: bool, out: &mut String) -> bool {
+        if let Selection::Field(field) = selection {
+            let field = &field.node;
+            keys.push(field.response_key().node.to_string());
+            let printed = self.field(field, keys, inside, out);
+            keys.pop();
+            return printed;
+        }
+        if let (true, Selection::FragmentSpread(spread)) = (inside, selection) {
+            let _ = write!(out, " ...{}", spread.node.fragment_name.node);
+            self.directives(&spread.node.directives, out);
+            self.fragments.push(spread.node.fragment_name.node.clone());
+            return true;
+        }
+        let Some(fragment) = self.plan.fragment(selection) else {
+            return false;
+        };
+        if inside {
+            return self.inline(&fragment, keys, true, out);
+        }
+        if self.plan.deferred(fragment.directives).is_some() {
+            return match self.pass {
+                Pass::Site(Site { keys: site_keys, kind: SiteKind::Defer { node, .. } })
+                    if *node == address(selection) && site_keys == keys =>
+                {
+                    self.inline(&fragment, keys, true, out)
+                }
+                _ => false,
+            };
+        }
+        if let Some(name) = fragment.name {
+            if self.expanding.contains(&name) {
+                // A cycle; left for validation to report
+                let _ = write!(out, " ...{name}");
+                self.fragments.push(name.clone());
+                return true;
+            }
+            self.expanding.push(name);
+        }
+        let printed = self.inline(&fragment, keys, false, out);
+        if fragment.name.is_some() {
+            self.expanding.pop();
+        }
+        printed
+    }
+
+    fn field(&mut self, field: &'a Field, keys: &mut Vec<String>, inside: bool, out: &mut String) -> bool {
+        let streamed = !inside && self.plan.streamed(&field.directives).is_some();
+        let (keep, nested_inside) = match self.pass {
+            _ if inside => (true, true),
+            Pass::Initial => (true, streamed),
+            Pass::Site(site) if streamed => (matches!(site.kind, SiteKind::Stream { .. }) && site.keys == *keys, true),
+            Pass::Site(site) => (site.keys.starts_with(keys), false),
+        };
+        if !keep {
+            return false;
+        }
+
+        let mark = self.mark(out);
+        out.push(' ');
+        if let Some(alias) = &field.alias {
+            let _ = write!(out, "{}: ", alias.node);
+        }
+        out.push_str(field.name.node.as_str());
+        self.arguments(&field.arguments, out);
+        self.directives(&field.directives, out);
+        if !field.selection_set.node.items.is_empty()
+            && !self.set(&field.selection_set.node, keys, nested_inside, out)
+        {
+            self.reset(mark, out);
+            return false;
+        }
+        true
+    }
+
+    fn inline(&mut self, fragment: &Fragment<'a>, keys: &mut Vec<String>, inside: bool, out: &mut String) -> bool {
+        let mark = self.mark(out);
+        out.push_str(" ...");
+        if let Some(condition) = fragment.condition {
+            let _ = write!(out, " on {condition}");
+        }
+        self.directives(fragment.directives, out);
+        if !self.set(fragment.set, keys, inside, out) {
+            self.reset(mark, out);
+            return false;
+        }
+        true
+    }
+
+    // What a dropped selection has to undo, so it leaves no unused variable
+    // or fragment behind
+    fn mark(&self, out: &str) -> (usize, usize, HashSet<Name>) {
+        (out.len(), self.fragments.len(), self.variables.clone())
+    }
+
+    fn reset(&mut self, (len, fragments, variables): (usize, usize, HashSet<Name>), out: &mut String) {
+        out.truncate(len);
+        self.fragments.truncate(fragments);
+        self.variables = variables;
+    }
+
+    fn arguments(&mut self, arguments: &[(Positioned<Name>, Positioned<QueryValue>)], out: &mut String) {
+        if arguments.is_empty() {
+            return;
+        }
+        let printed: Vec<String> = arguments
+            .iter()
+            .map(|(name, value)| {
+                // Only to record which variables are referenced
+                let _ = value.node.clone().into_const_with(|var| {
+                    self.variables.insert(var);
+                    Ok::<_, ()>(ConstValue::Null)
+                });
+                format!("{}: {}", name.node, value.node)
+            })
+            .collect();
+        let _ = write!(out, "({})", printed.join(", "));
+    }
+
+    fn directives(&mut self, directives: &[Positioned<Directive>], out: &mut String) {
+        for directive in directives.iter().map(|directive| &directive.node) {
+            if matches!(directive.name.node.as_str(), "defer" | "stream") {
+                continue;
+            }
+            let _ = write!(out, " @{}", directive.name.node);
+            self.arguments(&directive.arguments, out);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use crate::config::env_parse;
+use crate::flags::Flag;
+use crate::payload::TypedComponent;
+use crate::sinks::{Sink, SinkConfig, SinkWorker, WebhookSink};
+use crate::{ComponentDaemon, ComponentType, WriteError};
+
+// ========================
+// INTERACTIONS
+// ========================
+
+#[derive(Clone, Debug, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct Interaction {
+    pub id: String,
+    pub component_id: String,
+    pub component_type: ComponentType,
+    pub action: String,
+    pub payload: Option<serde_json::Value>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+// Interactions fan out to `interactionEvents` subscribers, to every
+// INTERACTION_WEBHOOK_URLS endpoint (comma-separated, plain http) as a JSON
+// POST, and in bidirectional mode to the registry as
+// INTERACTION_UPSTREAM_MUTATION with $id, $componentId, $action, $payload and
+// $occurredAt.
+pub struct Interactions {
+    tx: broadcast::Sender<Interaction>,
+    upstream_mutation: String,
+    webhooks: Vec<String>,
+    webhook_timeout: Duration,
+    // One delivery worker per webhook, fed straight from `record`
+    workers: OnceLock<Vec<SinkWorker>>,
+}
+
+impl Interactions {
+    pub fn from_env(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self {
+            tx,
+            upstream_mutation: std::env::var("INTERACTION_UPSTREAM_MUTATION").unwrap_or_else(|_| {
+                "mutation Interact($id: String!, $componentId: String!, $action: String!, $payload: JSON, $occurredAt: String!) { \
+                 interact(id: $id, componentId: $componentId, action: $action, payload: $payload, occurredAt: $occurredAt) }"
+                    .to_string()
+            }),
+            webhooks: std::env::var("INTERACTION_WEBHOOK_URLS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(str::to_string)
+                .collect(),
+            webhook_timeout: Duration::from_millis(env_parse("INTERACTION_WEBHOOK_TIMEOUT_MS", 5000)),
+            workers: OnceLock::new(),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Interaction> {
+        self.tx.subscribe()
+    }
+}
+
+// Checks the action against the component and publishes the interaction.
+// Cards with buttons only accept their buttons' actions (the button text
+// when a button has no action).
+pub fn record(
+    daemon: &ComponentDaemon,
+    component_id: &str,
+    action: &str,
+    payload: Option<serde_json::Value>,
+) -> Result<Interaction, WriteError> {
+    let component = daemon.get_component(component_id).ok_or(WriteError::NotFound)?;
+    if let TypedComponent::Card(card) = TypedComponent::from_component(&component) {
+        let mut actions = card
+            .buttons
+            .iter()
+            .map(|button| button.action.as_deref().unwrap_or(&button.text));
+        if !card.buttons.is_empty() && !actions.any(|known| known == action) {
+            return Err(WriteError::Invalid(format!(
+                "card {component_id} has no button with action '{action}'"
+            )));
+        }
+    }
+
+    let interaction = Interaction {
+        id: uuid::Uuid::new_v4().to_string(),
+        component_id: component.id.clone(),
+        component_type: component.r#type,
+        action: action.to_string(),
+        payload,
+        occurred_at: Utc::now(),
+    };
+    info!("👆 Daemon: {} on {}", interaction.action, interaction.component_id);
+
+    let interactions = daemon.interactions();
+    let _ = interactions.tx.send(interaction.clone());
+    // With sinks switched off, events are dropped rather than queued
+    if let Some(workers) = interactions.workers.get().filter(|_| daemon.flags().enabled(Flag::Sinks)) {
+        let event = serde_json::to_value(&interaction).unwrap_or_default();
+        for worker in workers {
+            worker.offer(event.clone());
+        }
+    }
+    if daemon.upstream().enabled() {
+        daemon.upstream().send_operation(
+            &interactions.upstream_mutation,
+            serde_json::json!({
+                "id": interaction.id,
+                "componentId": interaction.component_id,
+                "action": interaction.action,
+                "payload": interaction.payload,
+                "occurredAt": interaction.occurred_at.to_rfc3339(),
+            }),
+        );
+    }
+    Ok(interaction)
+}
+
+// Starts one delivery worker per webhook. `record` offers each interaction
+// to them directly; offers never wait, so a slow webhook only fills its own
+// queue, and a full queue dead-letters instead of skipping silently.
+pub fn spawn_webhooks(daemon: &ComponentDaemon) {
+    let interactions = daemon.interactions();
+    if interactions.webhooks.is_empty() {
+        return;
+    }
+    let config = SinkConfig::from_env();
+    let mut workers = Vec::new();
+    for url in &interactions.webhooks {
+        match WebhookSink::new(format!("interaction-webhook-{}", workers.len()), url, interactions.webhook_timeout) {
+            Ok(sink) => {
+                info!("🪝 Daemon: Interaction webhook {} enabled for {}", sink.name(), url);
+                let worker = SinkWorker::spawn(Arc::new(sink), &config, daemon.redactor.clone(), daemon.at_rest.clone());
+                daemon.metrics().sinks.insert(worker.name().to_string(), worker.stats());
+                workers.push(worker);
+            }
+            Err(e) => warn!("⚠️ Daemon: Skipping interaction webhook: {:#}", e),
+        }
+    }
+    let _ = interactions.workers.set(workers);
+}
@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{anyhow, Result};
+use tracing::warn;
+
+use crate::{Component, ComponentType};
+
+// ========================
+// LABELS
+// ========================
+
+pub type Labels = BTreeMap<String, String>;
+
+// LABEL_RULES adds default labels by component type, `*` matching every type,
+// e.g. "*:env=prod;NOTIFICATION:team=ops,tier=1". Rules never override labels
+// the payload carries itself.
+pub struct LabelRules {
+    rules: Vec<(Option<ComponentType>, Labels)>,
+}
+
+impl LabelRules {
+    pub fn from_env() -> Self {
+        Self::from_var("LABEL_RULES")
+    }
+
+    pub fn from_var(name: &str) -> Self {
+        let raw = std::env::var(name).unwrap_or_default();
+        let mut rules = Vec::new();
+        for rule in raw.split(';').map(str::trim).filter(|r| !r.is_empty()) {
+            match parse_rule(rule) {
+                Some(rule) => rules.push(rule),
+                None => warn!("⚠️ Daemon: Ignoring invalid {} entry '{}'", name, rule),
+            }
+        }
+        Self { rules }
+    }
+
+    // Explicit `labels` win, then string values under `data.labels`, then rules
+    pub fn resolve(&self, component: &Component) -> Labels {
+        let mut labels = Labels::new();
+        for (r#type, defaults) in &self.rules {
+            if r#type.is_none_or(|t| t == component.r#type) {
+                labels.extend(defaults.clone());
+            }
+        }
+        if let Some(from_data) = component.data.get("labels").and_then(|l| l.as_object()) {
+            labels.extend(
+                from_data
+                    .iter()
+                    .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string()))),
+            );
+        }
+        labels.extend(component.labels.clone());
+        labels
+    }
+}
+
+fn parse_rule(rule: &str) -> Option<(Option<ComponentType>, Labels)> {
+    let (r#type, pairs) = rule.split_once(':')?;
+    let r#type = match r#type.trim() {
+        "*" => None,
+        t => Some(serde_json::from_value(serde_json::Value::String(t.to_string())).ok()?),
+    };
+    let mut labels = Labels::new();
+    for pair in pairs.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=')?;
+        labels.insert(key.trim().to_string(), value.trim().to_string());
+    }
+    Some((r#type, labels))
+}
+
+// ========================
+// SELECTORS
+// ========================
+
+#[derive(Clone, Debug, PartialEq)]
+enum Requirement {
+    Equals(String, String),
+    NotEquals(String, String),
+    In(String, BTreeSet<String>),
+    NotIn(String, BTreeSet<String>),
+    Exists(String),
+    DoesNotExist(String),
+}
+
+impl Requirement {
+    fn matches(&self, labels: &Labels) -> bool {
+        match self {
+            Requirement::Equals(key, value) => labels.get(key) == Some(value),
+            // As in Kubernetes, a missing key satisfies != and notin
+            Requirement::NotEquals(key, value) => labels.get(key) != Some(value),
+            Requirement::In(key, values) => labels.get(key).is_some_and(|v| values.contains(v)),
+            Requirement::NotIn(key, values) => labels.get(key).is_none_or(|v| !values.contains(v)),
+            Requirement::Exists(key) => labels.contains_key(key),
+            Requirement::DoesNotExist(key) => !labels.contains_key(key),
+        }
+    }
+}
+
+// Kubernetes-style label selector: comma-separated requirements that must all
+// hold, e.g. "env=prod,team!=web,tier in (1,2),!legacy".
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LabelSelector {
+    requirements: Vec<Requirement>,
+}
+
+impl LabelSelector {
+    pub fn parse(raw: &str) -> Result<Self> {
+        let mut requirements = Vec::new();
+        for term in split_terms(raw) {
+            requirements.push(
+                parse_requirement(&term)
+                    .ok_or_else(|| anyhow!("Invalid label selector term '{term}'"))?,
+            );
+        }
+        Ok(Self { requirements })
+    }
+
+    pub fn matches(&self, labels: &Labels) -> bool {
+        self.requirements.iter().all(|r| r.matches(labels))
+    }
+}
+
+// Splits on commas outside of `in (...)` value lists
+fn split_terms(raw: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    for c in raw.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                terms.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    terms.push(current);
+    terms
+        .into_iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+fn parse_requirement(term: &str) -> Option<Requirement> {
+    if let Some(key) = term.strip_prefix('!') {
+        return valid_key(key.trim()).map(Requirement::DoesNotExist);
+    }
+    if let Some((key, value)) = term.split_once("!=") {
+        return Some(Requirement::NotEquals(
+            valid_key(key.trim())?,
+            valid_value(value.trim())?,
+        ));
+    }
+    if let Some((key, value)) = term.split_once("==").or_else(|| term.split_once('=')) {
+        return Some(Requirement::Equals(
+            valid_key(key.trim())?,
+            valid_value(value.trim())?,
+        ));
+    }
+    let mut words = term.splitn(2, char::is_whitespace);
+    let key = valid_key(words.next()?.trim())?;
+    let Some(rest) = words.next().map(str::trim) else {
+        return Some(Requirement::Exists(key));
+    };
+    let (negated, list) = if let Some(list) = rest.strip_prefix("notin") {
+        (true, list)
+    } else {
+        (false, rest.strip_prefix("in")?)
+    };
+    let values = list
+        .trim()
+        .strip_prefix('(')?
+        .strip_suffix(')')?
+        .split(',')
+        .map(|v| valid_value(v.trim()))
+        .collect::<Option<_>>()?;
+    Some(if negated {
+        Requirement::NotIn(key, values)
+    } else {
+        Requirement::In(key, values)
+    })
+}
+
+fn valid_key(key: &str) -> Option<String> {
+    let valid = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+    valid.then(|| key.to_string())
+}
+
+fn valid_value(value: &str) -> Option<String> {
+    value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .then(|| value.to_string())
+}
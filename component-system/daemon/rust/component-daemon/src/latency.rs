@@ -0,0 +1,292 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_graphql::SimpleObject;
+use dashmap::DashMap;
+use serde::Serialize;
+
+use crate::config::env_parse;
+use crate::metrics::{escape_label, HistogramVec};
+
+// ========================
+// DELIVERY LATENCY
+// ========================
+
+// Where a component was on its way from the registry to a renderer. Never
+// serialized, and stripped from stored copies, so only live deliveries are
+// timed: replays, snapshots and held (deliverAt) components aren't.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stamps {
+    // Deserialized from a registry message
+    pub received: Option<Instant>,
+    // Handed to the update channels
+    pub broadcast: Option<Instant>,
+}
+
+pub const LATENCY_BUCKETS: &[f64] = &[0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+const QUANTILES: [f64; 3] = [0.5, 0.9, 0.99];
+
+#[derive(Clone, Debug, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct Percentiles {
+    // Samples the percentiles are taken over, at most LATENCY_WINDOW
+    pub samples: u64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Clone, Debug, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriberLatency {
+    // The renderer's clientId, "anonymous" without one, or "push:<url>"
+    pub subscriber: String,
+    pub deliveries: u64,
+    // Registry receive to delivery; missing for local writes
+    pub end_to_end: Option<Percentiles>,
+    // Broadcast to delivery, i.e. time spent queued for this subscriber
+    pub delivery: Option<Percentiles>,
+}
+
+#[derive(Clone, Debug, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyStats {
+    pub slo_ms: u64,
+    // Whether the end-to-end p99 is within the SLO (true without samples)
+    pub within_slo: bool,
+    // End-to-end deliveries slower than the SLO
+    pub slo_breaches: u64,
+    // Registry receive to broadcast
+    pub ingest: Option<Percentiles>,
+    pub end_to_end: Option<Percentiles>,
+    pub subscribers: Vec<SubscriberLatency>,
+}
+
+// The last LATENCY_WINDOW samples, in milliseconds
+struct Window {
+    capacity: usize,
+    samples: Mutex<VecDeque<f64>>,
+}
+
+impl Window {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            samples: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    fn push(&self, elapsed: Duration) {
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(elapsed.as_secs_f64() * 1000.0);
+    }
+
+    // Nearest-rank percentiles over a sorted copy
+    fn percentiles(&self) -> Option<Percentiles> {
+        let mut sorted: Vec<f64> = self.samples.lock().unwrap().iter().copied().collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_by(f64::total_cmp);
+        let rank = |q: f64| sorted[((q * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len()) - 1];
+        Some(Percentiles {
+            samples: sorted.len() as u64,
+            p50_ms: rank(QUANTILES[0]),
+            p90_ms: rank(QUANTILES[1]),
+            p99_ms: rank(QUANTILES[2]),
+            max_ms: sorted[sorted.len() - 1],
+        })
+    }
+}
+
+struct SubscriberWindows {
+    // Milliseconds since the tracker started
+    last_delivery_ms: AtomicU64,
+    deliveries: AtomicU64,
+    // Cumulative, for the summary's _sum
+    end_to_end_micros: AtomicU64,
+    end_to_end_count: AtomicU64,
+    end_to_end: Window,
+    delivery: Window,
+}
+
+// Times each live delivery against LATENCY_SLO_MS (default 100). Stage
+// histograms cover every delivery; percentiles per subscriber are taken
+// over its last LATENCY_WINDOW (default 1000) deliveries. Subscribers are
+// named by their unauthenticated clientId, so one idle for
+// LATENCY_SUBSCRIBER_IDLE_SECS (default 3600) is forgotten, and past
+// LATENCY_SUBSCRIBER_LIMIT (default 200) new ones share an "other" entry.
+pub struct LatencyTracker {
+    slo: Duration,
+    window: usize,
+    started: Instant,
+    subscriber_idle: Duration,
+    subscriber_limit: usize,
+    stages: HistogramVec,
+    ingest: Window,
+    end_to_end: Window,
+    breaches: AtomicU64,
+    subscribers: DashMap<String, SubscriberWindows>,
+}
+
+impl LatencyTracker {
+    pub fn from_env() -> Self {
+        let window = env_parse("LATENCY_WINDOW", 1000usize).max(1);
+        Self {
+            slo: Duration::from_millis(env_parse("LATENCY_SLO_MS", 100)),
+            window,
+            started: Instant::now(),
+            subscriber_idle: Duration::from_secs(env_parse("LATENCY_SUBSCRIBER_IDLE_SECS", 3600)),
+            subscriber_limit: env_parse("LATENCY_SUBSCRIBER_LIMIT", 200usize).max(1),
+            stages: HistogramVec::new(LATENCY_BUCKETS, &["stage"]),
+            ingest: Window::new(window),
+            end_to_end: Window::new(window),
+            breaches: AtomicU64::new(0),
+            subscribers: DashMap::new(),
+        }
+    }
+
+    // Stamps the broadcast and records the receive → broadcast stage
+    pub fn broadcast(&self, stamps: &mut Stamps) {
+        let now = Instant::now();
+        stamps.broadcast = Some(now);
+        if let Some(received) = stamps.received {
+            let elapsed = now - received;
+            self.stages.observe(&["ingest"], elapsed.as_secs_f64());
+            self.ingest.push(elapsed);
+        }
+    }
+
+    pub fn delivered(&self, subscriber: Option<&str>, stamps: &Stamps) {
+        let Some(broadcast) = stamps.broadcast else {
+            return;
+        };
+        let now = Instant::now();
+        let mut subscriber = subscriber.unwrap_or("anonymous");
+        if !self.subscribers.contains_key(subscriber) && self.subscribers.len() >= self.subscriber_limit {
+            self.prune();
+            if self.subscribers.len() >= self.subscriber_limit {
+                subscriber = "other";
+            }
+        }
+        let windows = match self.subscribers.get(subscriber) {
+            Some(windows) => windows,
+            None => self
+                .subscribers
+                .entry(subscriber.to_string())
+                .or_insert_with(|| SubscriberWindows {
+                    last_delivery_ms: AtomicU64::new(0),
+                    deliveries: AtomicU64::new(0),
+                    end_to_end_micros: AtomicU64::new(0),
+                    end_to_end_count: AtomicU64::new(0),
+                    end_to_end: Window::new(self.window),
+                    delivery: Window::new(self.window),
+                })
+                .downgrade(),
+        };
+        windows.deliveries.fetch_add(1, Ordering::Relaxed);
+        windows.last_delivery_ms.store(self.elapsed_ms(now), Ordering::Relaxed);
+
+        let queued = now - broadcast;
+        self.stages.observe(&["delivery"], queued.as_secs_f64());
+        windows.delivery.push(queued);
+
+        if let Some(received) = stamps.received {
+            let elapsed = now - received;
+            self.stages.observe(&["end_to_end"], elapsed.as_secs_f64());
+            self.end_to_end.push(elapsed);
+            windows.end_to_end.push(elapsed);
+            windows.end_to_end_micros.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+            windows.end_to_end_count.fetch_add(1, Ordering::Relaxed);
+            if elapsed > self.slo {
+                self.breaches.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn elapsed_ms(&self, at: Instant) -> u64 {
+        (at - self.started).as_millis() as u64
+    }
+
+    // Drops subscribers without a delivery in LATENCY_SUBSCRIBER_IDLE_SECS
+    fn prune(&self) {
+        let cutoff = self.elapsed_ms(Instant::now()).saturating_sub(self.subscriber_idle.as_millis() as u64);
+        self.subscribers
+            .retain(|_, windows| windows.last_delivery_ms.load(Ordering::Relaxed) >= cutoff);
+    }
+
+    pub fn stats(&self) -> LatencyStats {
+        self.prune();
+        let end_to_end = self.end_to_end.percentiles();
+        let slo_ms = self.slo.as_millis() as u64;
+        let mut subscribers: Vec<SubscriberLatency> = self
+            .subscribers
+            .iter()
+            .map(|entry| SubscriberLatency {
+                subscriber: entry.key().clone(),
+                deliveries: entry.deliveries.load(Ordering::Relaxed),
+                end_to_end: entry.end_to_end.percentiles(),
+                delivery: entry.delivery.percentiles(),
+            })
+            .collect();
+        subscribers.sort_by(|a, b| a.subscriber.cmp(&b.subscriber));
+        LatencyStats {
+            slo_ms,
+            within_slo: end_to_end.as_ref().is_none_or(|p| p.p99_ms <= slo_ms as f64),
+            slo_breaches: self.breaches.load(Ordering::Relaxed),
+            ingest: self.ingest.percentiles(),
+            end_to_end,
+            subscribers,
+        }
+    }
+
+    pub fn render(&self, out: &mut String) {
+        self.prune();
+        self.stages.render(
+            out,
+            "daemon_latency_seconds",
+            "Component propagation time by stage: ingest, delivery or end_to_end.",
+        );
+
+        out.push_str("# HELP daemon_latency_slo_seconds End-to-end delivery latency objective.\n");
+        out.push_str("# TYPE daemon_latency_slo_seconds gauge\n");
+        let _ = writeln!(out, "daemon_latency_slo_seconds {}", self.slo.as_secs_f64());
+
+        out.push_str("# HELP daemon_latency_slo_breaches_total End-to-end deliveries slower than the objective.\n");
+        out.push_str("# TYPE daemon_latency_slo_breaches_total counter\n");
+        let _ = writeln!(out, "daemon_latency_slo_breaches_total {}", self.breaches.load(Ordering::Relaxed));
+
+        out.push_str("# HELP daemon_subscriber_latency_seconds End-to-end delivery latency per subscriber over its recent deliveries.\n");
+        out.push_str("# TYPE daemon_subscriber_latency_seconds summary\n");
+        for entry in self.subscribers.iter() {
+            let subscriber = escape_label(entry.key());
+            if let Some(p) = entry.end_to_end.percentiles() {
+                for (quantile, ms) in QUANTILES.iter().zip([p.p50_ms, p.p90_ms, p.p99_ms]) {
+                    let _ = writeln!(
+                        out,
+                        "daemon_subscriber_latency_seconds{{subscriber=\"{subscriber}\",quantile=\"{quantile}\"}} {}",
+                        ms / 1000.0
+                    );
+                }
+            }
+            let _ = writeln!(
+                out,
+                "daemon_subscriber_latency_seconds_sum{{subscriber=\"{subscriber}\"}} {}",
+                entry.end_to_end_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+            );
+            let _ = writeln!(
+                out,
+                "daemon_subscriber_latency_seconds_count{{subscriber=\"{subscriber}\"}} {}",
+                entry.end_to_end_count.load(Ordering::Relaxed)
+            );
+        }
+    }
+}
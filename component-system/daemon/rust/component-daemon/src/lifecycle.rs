@@ -0,0 +1,86 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use async_graphql::{Enum, SimpleObject};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::metrics::Metrics;
+
+// ========================
+// LIFECYCLE EVENTS
+// ========================
+
+#[derive(Clone, Copy, Debug, Serialize, Enum, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum InternalEventKind {
+    UpstreamConnected,
+    UpstreamDisconnected,
+    // Waiting before the next registry connection attempt
+    BackoffEntered,
+    // A journal on disk was rewritten, e.g. by an at-rest key rewrap
+    JournalPersisted,
+    RetentionRan,
+    MemoryEvictionRan,
+    // Current registry state loaded after connecting
+    Hydrated,
+}
+
+impl InternalEventKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            InternalEventKind::UpstreamConnected => "upstream_connected",
+            InternalEventKind::UpstreamDisconnected => "upstream_disconnected",
+            InternalEventKind::BackoffEntered => "backoff_entered",
+            InternalEventKind::JournalPersisted => "journal_persisted",
+            InternalEventKind::RetentionRan => "retention_ran",
+            InternalEventKind::MemoryEvictionRan => "memory_eviction_ran",
+            InternalEventKind::Hydrated => "hydrated",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct InternalEvent {
+    pub kind: InternalEventKind,
+    pub at: DateTime<Utc>,
+    // Human-readable context, e.g. the disconnect reason
+    pub detail: Option<String>,
+    // Items affected, e.g. components evicted
+    pub count: Option<u64>,
+}
+
+// Health-state transitions for `internalEvents` subscribers, also counted in
+// daemon_internal_events_total so alerts can watch for flapping.
+pub struct Lifecycle {
+    tx: broadcast::Sender<InternalEvent>,
+    metrics: Arc<Metrics>,
+}
+
+impl Lifecycle {
+    pub fn new(capacity: usize, metrics: Arc<Metrics>) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self { tx, metrics }
+    }
+
+    pub fn emit(&self, kind: InternalEventKind, detail: Option<String>, count: Option<u64>) {
+        *self.metrics.internal_events.entry(kind.label()).or_insert(0) += 1;
+        match kind {
+            InternalEventKind::UpstreamConnected => self.metrics.upstream_connected.store(1, Ordering::Relaxed),
+            InternalEventKind::UpstreamDisconnected => self.metrics.upstream_connected.store(0, Ordering::Relaxed),
+            _ => {}
+        }
+        let _ = self.tx.send(InternalEvent {
+            kind,
+            at: Utc::now(),
+            detail,
+            count,
+        });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<InternalEvent> {
+        self.tx.subscribe()
+    }
+}
@@ -0,0 +1,145 @@
+use serde_json::{json, Map, Value};
+use tracing::warn;
+
+use crate::blobs::BlobStore;
+use crate::config::env_parse;
+
+// ========================
+// SIZE LIMITS
+// ========================
+
+// Room left in a truncated payload for its `_truncated` marker
+const MARKER_RESERVE: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OversizedPolicy {
+    Reject,
+    // Top-level fields are kept while they fit; `_truncated` lists the rest
+    Truncate,
+    // The payload moves to the blob store and `_blob` points at it
+    Spill,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OversizedAction {
+    Truncated,
+    Spilled,
+}
+
+impl OversizedAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            OversizedAction::Truncated => "truncated",
+            OversizedAction::Spilled => "spilled",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TooLarge {
+    pub bytes: usize,
+    pub limit: usize,
+}
+
+impl std::fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Component data is {} bytes, over the {} byte limit", self.bytes, self.limit)
+    }
+}
+
+// COMPONENT_MAX_BYTES caps a component's serialized `data` and
+// BATCH_MAX_BYTES the total data of one import batch (0 = unlimited).
+// OVERSIZED_POLICY decides what happens to a component over the cap:
+// reject (default), truncate or spill.
+#[derive(Clone, Copy, Debug)]
+pub struct SizeLimits {
+    component_max: usize,
+    pub batch_max: usize,
+    policy: OversizedPolicy,
+}
+
+impl SizeLimits {
+    pub fn from_env() -> Self {
+        let policy = match std::env::var("OVERSIZED_POLICY").as_deref().map(str::trim) {
+            Ok("reject") | Err(_) => OversizedPolicy::Reject,
+            Ok("truncate") => OversizedPolicy::Truncate,
+            Ok("spill") => OversizedPolicy::Spill,
+            Ok(other) => {
+                warn!("⚠️ Daemon: Ignoring invalid OVERSIZED_POLICY='{}'", other);
+                OversizedPolicy::Reject
+            }
+        };
+        Self {
+            component_max: env_parse("COMPONENT_MAX_BYTES", 0),
+            batch_max: env_parse("BATCH_MAX_BYTES", 0),
+            policy,
+        }
+    }
+
+    // Serialized size, when any limit needs it
+    pub fn measure(&self, data: &Value) -> usize {
+        if self.component_max == 0 && self.batch_max == 0 {
+            return 0;
+        }
+        serde_json::to_vec(data).map_or(0, |bytes| bytes.len())
+    }
+
+    // Leaves `data` alone when it fits, otherwise applies the policy
+    pub fn enforce(&self, data: &mut Value, blobs: &BlobStore) -> Result<Option<OversizedAction>, TooLarge> {
+        if self.component_max == 0 {
+            return Ok(None);
+        }
+        let serialized = serde_json::to_vec(data).unwrap_or_default();
+        let bytes = serialized.len();
+        if bytes <= self.component_max {
+            return Ok(None);
+        }
+        match self.policy {
+            OversizedPolicy::Reject => Err(TooLarge {
+                bytes,
+                limit: self.component_max,
+            }),
+            OversizedPolicy::Truncate => {
+                *data = truncate(std::mem::take(data), bytes, self.component_max);
+                Ok(Some(OversizedAction::Truncated))
+            }
+            OversizedPolicy::Spill => {
+                let hash = blobs.put("application/json", serialized.into());
+                *data = json!({
+                    "_blob": {
+                        "hash": hash,
+                        "bytes": bytes,
+                        "contentType": "application/json",
+                        "href": format!("/blobs/{hash}"),
+                    }
+                });
+                Ok(Some(OversizedAction::Spilled))
+            }
+        }
+    }
+}
+
+fn truncate(data: Value, original_bytes: usize, limit: usize) -> Value {
+    let Value::Object(fields) = data else {
+        return json!({ "_truncated": { "originalBytes": original_bytes } });
+    };
+    let budget = limit - MARKER_RESERVE.min(limit / 2);
+    let mut kept = Map::new();
+    let mut omitted = Vec::new();
+    let mut used = 2;
+    for (key, value) in fields {
+        // "key":value plus a separating comma
+        let size = key.len() + 4 + serde_json::to_vec(&value).map_or(0, |bytes| bytes.len());
+        if used + size <= budget {
+            used += size;
+            kept.insert(key, value);
+        } else {
+            omitted.push(key);
+        }
+    }
+    kept.insert(
+        "_truncated".to_string(),
+        json!({ "originalBytes": original_bytes, "omitted": omitted }),
+    );
+    Value::Object(kept)
+}
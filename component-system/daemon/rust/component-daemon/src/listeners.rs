@@ -0,0 +1,186 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use tracing::warn;
+use warp::http::StatusCode;
+use warp::reply::{Reply, Response};
+use warp::{Filter, Rejection};
+
+use crate::auth::{self, AuthError, AuthProvider, Principal};
+
+// ========================
+// LISTENERS
+// ========================
+
+// Paths served by the admin listener: ops endpoints, Prometheus scrapes and
+// the stream hot standby followers replicate from
+const ADMIN_PATHS: &[&str] = &["admin", "metrics", "replication"];
+
+// PUBLIC_BIND (default 0.0.0.0:<port>) is where renderers connect.
+// ADMIN_BIND gives the admin endpoints a listener of their own, e.g.
+// 127.0.0.1:3002; without it they share the public one. Each side picks its
+// auth provider independently through the PUBLIC_AUTH_* and ADMIN_AUTH_*
+// variables (see auth.rs).
+#[derive(Clone)]
+pub struct Listeners {
+    pub public: SocketAddr,
+    pub admin: Option<SocketAddr>,
+    pub public_auth: BearerAuth,
+    pub admin_auth: BearerAuth,
+}
+
+impl Listeners {
+    // Fails on bind or auth settings that can't be honored, rather than
+    // starting open
+    pub fn from_env(port: u16) -> Result<Self> {
+        let listeners = Self {
+            public: bind_from_env("PUBLIC_BIND")?.unwrap_or_else(|| SocketAddr::from(([0, 0, 0, 0], port))),
+            admin: bind_from_env("ADMIN_BIND")?,
+            public_auth: BearerAuth::from_env("PUBLIC")?,
+            admin_auth: BearerAuth::from_env("ADMIN")?,
+        };
+        if listeners.admin.is_none() && listeners.admin_auth.provider.is_none() {
+            warn!("⚠️ Daemon: Admin endpoints share the public listener without auth; set ADMIN_BIND or ADMIN_AUTH_PROVIDER");
+        }
+        Ok(listeners)
+    }
+}
+
+#[derive(Clone)]
+pub struct BearerAuth {
+    // Checks `Authorization: Bearer <token>`; None lets every request through
+    provider: Option<Arc<dyn AuthProvider>>,
+    // The principal must hold this scope, or "*"
+    scope: Option<Arc<str>>,
+}
+
+impl BearerAuth {
+    pub fn new(provider: Option<Arc<dyn AuthProvider>>, scope: Option<String>) -> Self {
+        Self {
+            provider,
+            scope: scope.map(Into::into),
+        }
+    }
+
+    // `<PREFIX>_AUTH_SCOPE` is the scope every request needs
+    fn from_env(prefix: &str) -> Result<Self> {
+        let provider = auth::provider_from_env(prefix)
+            .with_context(|| format!("Invalid {prefix} listener auth settings"))?;
+        let scope = std::env::var(format!("{prefix}_AUTH_SCOPE"))
+            .ok()
+            .map(|scope| scope.trim().to_string())
+            .filter(|scope| !scope.is_empty());
+        Ok(Self::new(provider, scope))
+    }
+
+    pub fn filter(&self) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+        let auth = self.clone();
+        warp::header::optional::<String>("authorization")
+            .and_then(move |header: Option<String>| {
+                let auth = auth.clone();
+                async move { auth.check(header.as_deref()).await.map_err(warp::reject::custom) }
+            })
+            .untuple_one()
+    }
+
+    // Who the request's token belongs to; None when the listener is open.
+    // Meant to follow `filter`, so tokens that fail here were already refused.
+    pub fn principal(&self) -> impl Filter<Extract = (Option<Principal>,), Error = Rejection> + Clone {
+        let auth = self.clone();
+        warp::header::optional::<String>("authorization").then(move |header: Option<String>| {
+            let auth = auth.clone();
+            async move {
+                let token = header.as_deref()?.strip_prefix("Bearer ")?.trim().to_string();
+                auth.provider.as_ref()?.validate(&token).await.ok()
+            }
+        })
+    }
+
+    async fn check(&self, header: Option<&str>) -> Result<(), AuthRejection> {
+        let Some(provider) = &self.provider else {
+            return Ok(());
+        };
+        let token = header
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .ok_or(AuthRejection::Unauthorized)?;
+        let principal = provider.validate(token).await.map_err(|e| {
+            warn!("🔐 Daemon: {} auth failed: {}", provider.name(), e);
+            match e {
+                AuthError::Rejected(_) => AuthRejection::Unauthorized,
+                AuthError::Unavailable(_) => AuthRejection::Unavailable,
+            }
+        })?;
+        if let Some(scope) = &self.scope {
+            if !principal.scopes.contains(&**scope) && !principal.scopes.contains("*") {
+                warn!("🔐 Daemon: {} lacks scope {}", principal.subject, scope);
+                return Err(AuthRejection::Forbidden);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Matches only admin paths, so admin routes can share a listener without
+// answering for anything else
+pub fn admin_scope() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    path_scope(true)
+}
+
+// Everything but admin paths, so public auth doesn't also judge admin requests
+pub fn public_scope() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    path_scope(false)
+}
+
+fn path_scope(admin: bool) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::path::peek()
+        .and_then(move |peek: warp::path::Peek| async move {
+            let first = peek.segments().next().unwrap_or_default();
+            if ADMIN_PATHS.contains(&first) == admin {
+                Ok(())
+            } else {
+                Err(warp::reject::not_found())
+            }
+        })
+        .untuple_one()
+}
+
+#[derive(Debug)]
+enum AuthRejection {
+    Unauthorized,
+    Forbidden,
+    Unavailable,
+}
+
+impl warp::reject::Reject for AuthRejection {}
+
+// Turns auth failures into 401, 403 or 503; other rejections pass through
+pub async fn recover(rejection: Rejection) -> Result<Response, Rejection> {
+    let Some(auth) = rejection.find::<AuthRejection>() else {
+        return Err(rejection);
+    };
+    let (status, message) = match auth {
+        AuthRejection::Unauthorized => (StatusCode::UNAUTHORIZED, "Missing or invalid bearer token"),
+        AuthRejection::Forbidden => (StatusCode::FORBIDDEN, "Token lacks the required scope"),
+        AuthRejection::Unavailable => (StatusCode::SERVICE_UNAVAILABLE, "Token could not be checked, try again"),
+    };
+    let reply = warp::reply::with_status(warp::reply::json(&serde_json::json!({ "error": message })), status);
+    Ok(match auth {
+        AuthRejection::Unauthorized => warp::reply::with_header(reply, "www-authenticate", "Bearer").into_response(),
+        _ => reply.into_response(),
+    })
+}
+
+// An unparsable address is an error rather than unset: a bad ADMIN_BIND
+// would otherwise put the admin routes on the public listener
+fn bind_from_env(name: &str) -> Result<Option<SocketAddr>> {
+    let Ok(raw) = std::env::var(name) else {
+        return Ok(None);
+    };
+    let addr = raw
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid {name}='{raw}', expected host:port"))?;
+    Ok(Some(addr))
+}
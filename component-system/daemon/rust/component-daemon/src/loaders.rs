@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use async_graphql::dataloader::{DataLoader, HashMapCache, Loader};
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextParseQuery, NextPrepareRequest,
+};
+use async_graphql::parser::types::{ExecutableDocument, OperationType};
+use async_graphql::{Context, Error, Request, ServerResult, Variables};
+
+use crate::errors::{graphql_error, ErrorCode};
+use crate::{Component, ComponentDaemon};
+
+// ========================
+// BATCHED LOOKUPS
+// ========================
+
+// Keys other than a plain component id
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ChildrenOf(pub String);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HistoryOf(pub String);
+
+// Lookups that `parent`, `children`, `related` and `history` would otherwise
+// make once per component in a listing. Keys requested while one level of
+// the response resolves are answered together.
+pub struct StoreLoader {
+    daemon: ComponentDaemon,
+}
+
+#[async_trait::async_trait]
+impl Loader<String> for StoreLoader {
+    type Value = Arc<Component>;
+    type Error = Infallible;
+
+    // Ids that aren't stored are simply missing from the map
+    async fn load(&self, ids: &[String]) -> Result<HashMap<String, Arc<Component>>, Infallible> {
+        Ok(ids
+            .iter()
+            .filter_map(|id| Some((id.clone(), self.daemon.stored(id)?)))
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl Loader<ChildrenOf> for StoreLoader {
+    type Value = Vec<Arc<Component>>;
+    type Error = Infallible;
+
+    async fn load(&self, parents: &[ChildrenOf]) -> Result<HashMap<ChildrenOf, Vec<Arc<Component>>>, Infallible> {
+        Ok(parents
+            .iter()
+            .map(|parent| {
+                let children = self
+                    .daemon
+                    .graph()
+                    .children_of(&parent.0)
+                    .iter()
+                    .filter_map(|id| self.daemon.stored(id))
+                    .collect();
+                (parent.clone(), children)
+            })
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl Loader<HistoryOf> for StoreLoader {
+    type Value = Vec<Component>;
+    type Error = Infallible;
+
+    // One pass over the history buffer for the whole batch
+    async fn load(&self, ids: &[HistoryOf]) -> Result<HashMap<HistoryOf, Vec<Component>>, Infallible> {
+        let ids: Vec<&str> = ids.iter().map(|id| id.0.as_str()).collect();
+        Ok(self
+            .daemon
+            .history()
+            .versions_of(&ids)
+            .into_iter()
+            .map(|(id, versions)| (HistoryOf(id), versions))
+            .collect())
+    }
+}
+
+pub type StoreDataLoader = DataLoader<StoreLoader, HashMapCache>;
+
+pub fn from_context<'a>(ctx: &Context<'a>) -> Result<&'a Arc<StoreDataLoader>, Error> {
+    ctx.data::<Arc<StoreDataLoader>>()
+        .map_err(|_| graphql_error(ErrorCode::Internal, "Store loader not found in context", None))
+}
+
+// Gives every request its own loader, so cached lookups never outlive it.
+// Subscriptions resolve each event long after the request started, so their
+// loader only batches and doesn't cache.
+pub struct Loaders {
+    daemon: ComponentDaemon,
+}
+
+impl Loaders {
+    pub fn new(daemon: ComponentDaemon) -> Self {
+        Self { daemon }
+    }
+}
+
+impl ExtensionFactory for Loaders {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(LoadersExtension {
+            loader: Arc::new(DataLoader::with_cache(
+                StoreLoader {
+                    daemon: self.daemon.clone(),
+                },
+                tokio::spawn,
+                HashMapCache::default(),
+            )),
+        })
+    }
+}
+
+struct LoadersExtension {
+    loader: Arc<StoreDataLoader>,
+}
+
+#[async_trait::async_trait]
+impl Extension for LoadersExtension {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        next.run(ctx, request.data(self.loader.clone())).await
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        if document
+            .operations
+            .iter()
+            .any(|(_, op)| op.node.ty == OperationType::Subscription)
+        {
+            self.loader.enable_all_cache(false);
+        }
+        Ok(document)
+    }
+}
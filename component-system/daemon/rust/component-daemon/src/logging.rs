@@ -0,0 +1,270 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use async_graphql::SimpleObject;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use tracing::info;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+use utoipa::ToSchema;
+
+use crate::config::env_parse;
+
+// ========================
+// LOGGING
+// ========================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RotationInterval {
+    Never,
+    Hourly,
+    Daily,
+}
+
+impl RotationInterval {
+    // Files are rotated whenever this key changes between writes
+    fn period(&self, at: DateTime<Utc>) -> Option<String> {
+        match self {
+            RotationInterval::Never => None,
+            RotationInterval::Hourly => Some(at.format("%Y%m%d%H").to_string()),
+            RotationInterval::Daily => Some(at.format("%Y%m%d").to_string()),
+        }
+    }
+}
+
+// Suffix of rotated files, e.g. `daemon.log.20240131T235959.123`
+const ROTATED_SUFFIX: &str = "%Y%m%dT%H%M%S%.3f";
+
+// Log file that rolls over by size and/or time. Rotated files are renamed to
+// `<path>.<timestamp>` and only the newest `retain` of them are kept.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_bytes: u64,
+    interval: RotationInterval,
+    period: Option<String>,
+    retain: usize,
+}
+
+impl RotatingFile {
+    fn open(
+        path: PathBuf,
+        max_bytes: u64,
+        interval: RotationInterval,
+        retain: usize,
+    ) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open log file {}", path.display()))?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            written,
+            max_bytes,
+            interval,
+            period: interval.period(Utc::now()),
+            retain,
+        })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let rotated = PathBuf::from(format!(
+            "{}.{}",
+            self.path.display(),
+            Utc::now().format(ROTATED_SUFFIX)
+        ));
+        std::fs::rename(&self.path, &rotated)?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.written = 0;
+        self.prune();
+        Ok(())
+    }
+
+    // Timestamps sort lexically, so the oldest rotated files come first.
+    // Only names this writer produces are touched, so `daemon.log.bak` or
+    // another process's `daemon.log.lock` next to it survive.
+    fn prune(&self) {
+        let Some(dir) = self.path.parent().map(|d| {
+            if d.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                d
+            }
+        }) else {
+            return;
+        };
+        let Some(name) = self.path.file_name().and_then(|n| n.to_str()) else {
+            return;
+        };
+        let prefix = format!("{name}.");
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let mut rotated: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| n.strip_prefix(&prefix))
+                    .is_some_and(|suffix| {
+                        suffix.len() == 19 && NaiveDateTime::parse_from_str(suffix, ROTATED_SUFFIX).is_ok()
+                    })
+            })
+            .collect();
+        rotated.sort();
+        let excess = rotated.len().saturating_sub(self.retain);
+        for old in rotated.into_iter().take(excess) {
+            let _ = std::fs::remove_file(old);
+        }
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let period = self.interval.period(Utc::now());
+        let size_exceeded = self.max_bytes > 0
+            && self.written > 0
+            && self.written + buf.len() as u64 > self.max_bytes;
+        if size_exceeded || period != self.period {
+            self.rotate()?;
+            self.period = period;
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+struct LevelControl {
+    handle: reload::Handle<EnvFilter, Registry>,
+    // Directive string currently applied; EnvFilter doesn't round-trip exactly
+    current: Mutex<String>,
+}
+
+static LEVEL: OnceLock<LevelControl> = OnceLock::new();
+
+#[derive(Clone, Debug, Serialize, SimpleObject, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLevelChange {
+    pub previous: String,
+    pub current: String,
+    pub revert_after_secs: Option<u64>,
+}
+
+pub fn current_level() -> Option<String> {
+    LEVEL
+        .get()
+        .map(|control| control.current.lock().unwrap().clone())
+}
+
+// Swaps the active filter (e.g. "debug" or "component_daemon=debug,warp=info").
+// With a TTL the previous filter is restored afterwards, unless someone
+// changed it again in the meantime.
+pub fn set_level(filter: &str, ttl: Option<Duration>) -> Result<LogLevelChange> {
+    let control = LEVEL
+        .get()
+        .ok_or_else(|| anyhow!("Log level is not adjustable in this process"))?;
+    let parsed =
+        EnvFilter::try_new(filter).with_context(|| format!("Invalid log filter '{filter}'"))?;
+
+    let previous = {
+        let mut current = control.current.lock().unwrap();
+        control.handle.reload(parsed)?;
+        std::mem::replace(&mut *current, filter.to_string())
+    };
+    info!(
+        "🔧 Daemon: Log level changed from '{}' to '{}'",
+        previous, filter
+    );
+
+    if let Some(ttl) = ttl {
+        let (applied, restore) = (filter.to_string(), previous.clone());
+        tokio::spawn(async move {
+            tokio::time::sleep(ttl).await;
+            if current_level().as_deref() == Some(applied.as_str()) {
+                if let Err(e) = set_level(&restore, None) {
+                    tracing::error!("❌ Daemon: Failed to restore log level: {:#}", e);
+                }
+            }
+        });
+    }
+
+    Ok(LogLevelChange {
+        previous,
+        current: filter.to_string(),
+        revert_after_secs: ttl.map(|ttl| ttl.as_secs()),
+    })
+}
+
+// RUST_LOG sets the initial filter (default "info"); it can be changed at
+// runtime through `set_level`.
+// LOG_FILE writes logs to a file as well as stdout (set LOG_STDOUT=false to
+// drop stdout). Rotation: LOG_ROTATE_MAX_MB (0 = no size limit),
+// LOG_ROTATE_INTERVAL (never | hourly | daily), LOG_RETAIN_FILES.
+pub fn init() -> Result<()> {
+    let file_layer = match std::env::var("LOG_FILE") {
+        Ok(path) => {
+            let interval = match std::env::var("LOG_ROTATE_INTERVAL").as_deref() {
+                Ok("hourly") => RotationInterval::Hourly,
+                Ok("daily") | Err(_) => RotationInterval::Daily,
+                Ok("never") => RotationInterval::Never,
+                Ok(other) => return Err(anyhow!("Unknown LOG_ROTATE_INTERVAL '{other}'")),
+            };
+            let max_bytes = env_parse::<u64>("LOG_ROTATE_MAX_MB", 100) * 1024 * 1024;
+            let file = RotatingFile::open(
+                PathBuf::from(path),
+                max_bytes,
+                interval,
+                env_parse("LOG_RETAIN_FILES", 7),
+            )?;
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(Mutex::new(file)),
+            )
+        }
+        Err(_) => None,
+    };
+    let stdout_layer = (file_layer.is_none() || env_parse("LOG_STDOUT", true))
+        .then(tracing_subscriber::fmt::layer);
+
+    #[cfg(windows)]
+    let event_log_layer = crate::service::event_log_layer();
+    #[cfg(not(windows))]
+    let event_log_layer: Option<tracing_subscriber::layer::Identity> = None;
+
+    let initial = std::env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string());
+    let filter =
+        EnvFilter::try_new(&initial).with_context(|| format!("Invalid RUST_LOG '{initial}'"))?;
+    let (filter_layer, handle) = reload::Layer::new(filter);
+
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(stdout_layer)
+        .with(file_layer)
+        .with(event_log_layer)
+        .try_init()?;
+    let _ = LEVEL.set(LevelControl {
+        handle,
+        current: Mutex::new(initial),
+    });
+    Ok(())
+}
@@ -1,36 +1,178 @@
 use std::convert::Infallible;
 use std::time::Duration;
 use std::sync::Arc;
-use std::collections::HashMap;
 
 use anyhow::{Context, Result};
 use async_graphql::*;
 use async_stream::stream;
 use chrono::{DateTime, Utc};
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use futures_util::{SinkExt, StreamExt, stream::SplitSink};
 use serde::{Deserialize, Serialize};
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, Notify};
 use tokio::time::sleep;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use tracing::{error, info, warn};
+use utoipa::ToSchema;
 use warp::Filter;
-use uuid::Uuid;
+
+mod ack;
+mod admin;
+mod admin_graphql;
+mod at_rest;
+mod auth;
+mod attachments;
+mod audit;
+mod bench;
+mod blobs;
+#[cfg(feature = "chaos")]
+mod chaos;
+mod channels;
+mod compression;
+mod config;
+mod conflict;
+mod debounce;
+mod delta;
+mod errors;
+mod flags;
+mod forms;
+mod graph;
+mod history;
+mod hydration;
+mod import;
+mod interactions;
+mod incremental;
+mod labels;
+mod latency;
+mod lifecycle;
+mod limits;
+mod listeners;
+mod loaders;
+mod logging;
+mod memory;
+mod metrics;
+mod openapi;
+mod ordering;
+mod pagination;
+mod payload;
+mod preview;
+mod priority;
+mod push;
+mod quotas;
+mod redaction;
+mod registry_errors;
+mod replication;
+mod request_log;
+mod rest;
+mod retention;
+mod schedule;
+#[cfg(windows)]
+mod service;
+mod shadow;
+mod signature;
+mod soak;
+mod sinks;
+mod subscribers;
+mod ui;
+mod upstream;
+mod validation;
+mod versioning;
+mod ws;
+
+use ack::{AckConfig, Acknowledgement};
+use at_rest::AtRestCipher;
+use attachments::Attachments;
+use channels::{ComponentChange, IdChannels, UpdateChannels, UpdateReceiver};
+use audit::{AuditLog, MutationAudit, RequestOrigin};
+use config::env_parse;
+use conflict::{ConflictConfig, Resolution};
+use debounce::{Debounced, Debouncer};
+use delta::{ComponentDelta, DeltaTracker};
+use errors::{graphql_error, missing_daemon, ErrorCode};
+use flags::{FeatureFlags, Flag};
+use forms::{FormSubmissions, SubmitFormResult};
+use graph::{ComponentGraph, ComponentTreeNode};
+use labels::{LabelRules, LabelSelector, Labels};
+use latency::{LatencyStats, LatencyTracker, Stamps};
+use lifecycle::{InternalEventKind, Lifecycle};
+use limits::SizeLimits;
+use listeners::Listeners;
+use loaders::{ChildrenOf, HistoryOf, Loaders};
+use blobs::BlobStore;
+use history::{History, HistoryEvent, ResumeUnavailable};
+use hydration::{Hydration, HydrationMode};
+use ordering::OrderingKey;
+use pagination::{ComponentPage, PageSnapshots, PinnedView};
+use interactions::{Interaction, Interactions};
+use memory::{approx_size, ByteGauge, MemoryBudget};
+use metrics::{FailureKind, IngestFailures, Metrics};
+use payload::TypedComponent;
+use priority::{DeliveryQueue, PriorityConfig};
+use request_log::RequestLog;
+use subscribers::{SubscriberId, SubscriberRegistry};
+use redaction::Redactor;
+use quotas::{QuotaEnforcement, Quotas};
+use registry_errors::{Reaction, RegistryError, RegistryErrors};
+use replication::Replication;
+use retention::RetentionPolicy;
+use schedule::Scheduler;
+use shadow::{Outcome, ShadowRules};
+use upstream::Upstream;
+use signature::{FailureAction, Quarantine, SignatureVerifier};
+use validation::{ValidationMode, ValidationReport, Validator};
+use versioning::{ApiVersion, ApiVersionInfo, ApiVersioning};
 
 // ========================
 // TYPES
 // ========================
 
-#[derive(Clone, Debug, Serialize, Deserialize, SimpleObject)]
+#[derive(Clone, Debug, Serialize, Deserialize, SimpleObject, ToSchema)]
+#[graphql(complex)]
 #[serde(rename_all = "camelCase")]
 pub struct Component {
     pub id: String,
     pub r#type: ComponentType,
     pub data: serde_json::Value,
     pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    // Starts at the creating seq and is bumped by the daemon on every
+    // accepted write; upstream values are ignored
+    #[serde(default)]
+    pub version: u64,
+    // Position in the daemon's broadcast order, for resuming subscriptions
+    #[serde(default)]
+    pub seq: u64,
+    // The registry's own per-id sequence, when it sends one; see ORDERING_KEY
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream_seq: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related_ids: Vec<String>,
+    // Deployment metadata for selectors; merged with LABEL_RULES on ingest
+    #[serde(default, skip_serializing_if = "Labels::is_empty")]
+    #[schema(value_type = Object)]
+    pub labels: Labels,
+    // Held back from renderers until this time; also read from `data.deliverAt`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deliver_at: Option<DateTime<Utc>>,
+    // Set by acknowledgeNotification; a new payload from upstream clears it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acknowledged: Option<Box<Acknowledgement>>,
+    #[serde(skip)]
+    #[graphql(skip)]
+    pub stamps: Stamps,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Enum, Copy, PartialEq, Eq)]
+impl Component {
+    pub fn etag(&self) -> String {
+        format!("\"{}-v{}\"", self.id, self.version)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Enum, Copy, PartialEq, Eq, Hash, ToSchema)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ComponentType {
     Card,
@@ -38,35 +180,347 @@ pub enum ComponentType {
     Form,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, Enum, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RemovalReason {
+    Purged,
+    Tombstone,
+    Deleted,
+    Expired,
+    Evicted,
+    Acknowledged,
+    // Sent to renderers on an API version that predates the actual reason
+    Other,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentRemoval {
+    pub id: String,
+    pub reason: RemovalReason,
+    pub removed_at: DateTime<Utc>,
+}
+
+// Where a component entering `ingest` came from, for SIGNATURE_POLICY
+#[derive(Clone, Copy)]
+pub enum Source<'a> {
+    // The raw registry object, verified against its signature
+    Registry(&'a serde_json::Value),
+    // updateComponent, importComponents and --seed; never signed
+    Local,
+    // Released from quarantine by an operator, or benchmarked
+    Trusted,
+}
+
+#[derive(Debug)]
+pub enum WriteError {
+    NotFound,
+    VersionConflict { expected: u64, actual: u64 },
+    Invalid(String),
+    TooLarge(limits::TooLarge),
+    // Turned away by SIGNATURE_POLICY
+    Unsigned(String),
+    // This daemon follows a primary, which owns every write
+    ReadOnly,
+}
+
+impl std::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteError::NotFound => write!(f, "Component not found"),
+            WriteError::VersionConflict { expected, actual } => write!(
+                f,
+                "Version conflict: expected {expected}, current version is {actual}"
+            ),
+            WriteError::Invalid(summary) => write!(f, "Invalid component data: {summary}"),
+            WriteError::TooLarge(too_large) => write!(f, "{too_large}"),
+            WriteError::Unsigned(reason) => write!(f, "Rejected by signature policy: {reason}"),
+            WriteError::ReadOnly => write!(f, "This daemon is a read-only replication follower"),
+        }
+    }
+}
+
+impl WriteError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            WriteError::NotFound => ErrorCode::NotFound,
+            WriteError::VersionConflict { .. } => ErrorCode::VersionConflict,
+            WriteError::Invalid(_) | WriteError::Unsigned(_) => ErrorCode::Invalid,
+            WriteError::TooLarge(_) => ErrorCode::PayloadTooLarge,
+            WriteError::ReadOnly => ErrorCode::ReadOnly,
+        }
+    }
+}
+
+#[ComplexObject]
+impl Component {
+    async fn typed_data(&self) -> TypedComponent {
+        TypedComponent::from_component(self)
+    }
+
+    // Null when there is no parent or it isn't stored (yet)
+    async fn parent(&self, ctx: &async_graphql::Context<'_>) -> Result<Option<Arc<Component>>, Error> {
+        let Some(parent_id) = self.parent_id.clone() else {
+            return Ok(None);
+        };
+        if let Some(view) = ctx.data_opt::<PinnedView>() {
+            return Ok(view.get(&parent_id));
+        }
+        let loader = loaders::from_context(ctx)?;
+        let Ok(parent) = loader.load_one(parent_id).await;
+        Ok(parent)
+    }
+
+    async fn children(&self, ctx: &async_graphql::Context<'_>) -> Result<Vec<Arc<Component>>, Error> {
+        if let Some(view) = ctx.data_opt::<PinnedView>() {
+            let daemon = ctx.data::<ComponentDaemon>().map_err(|_| missing_daemon())?;
+            let children = daemon.graph().children_of(&self.id).iter().filter_map(|id| view.get(id)).collect();
+            return Ok(incremental::window(ctx, children));
+        }
+        let loader = loaders::from_context(ctx)?;
+        let Ok(children) = loader.load_one(ChildrenOf(self.id.clone())).await;
+        Ok(incremental::window(ctx, children.unwrap_or_default()))
+    }
+
+    // Related components that are currently stored, in declared order
+    async fn related(&self, ctx: &async_graphql::Context<'_>) -> Result<Vec<Arc<Component>>, Error> {
+        if let Some(view) = ctx.data_opt::<PinnedView>() {
+            return Ok(self.related_ids.iter().filter_map(|id| view.get(id)).collect());
+        }
+        let loader = loaders::from_context(ctx)?;
+        let Ok(mut found) = loader.load_many(self.related_ids.iter().cloned()).await;
+        Ok(self.related_ids.iter().filter_map(|id| found.remove(id)).collect())
+    }
+
+    // Earlier versions still in the history buffer, newest first
+    async fn history(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        #[graphql(default = 10)] limit: usize,
+    ) -> Result<Vec<Component>, Error> {
+        let loader = loaders::from_context(ctx)?;
+        let Ok(versions) = loader.load_one(HistoryOf(self.id.clone())).await;
+        Ok(versions
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|version| version.seq < self.seq)
+            .take(limit)
+            .collect())
+    }
+}
 
 
 // ========================
 // DAEMON
 // ========================
 
+// Wait between registry connection attempts
+pub(crate) const REGISTRY_RECONNECT_BACKOFF: Duration = Duration::from_secs(2);
+
+const REGISTRY_SUBSCRIPTION_ID: &str = "registry-sub";
+
+// Start message for the registry subscription, in subscriptions-transport-ws format
+fn subscription_start(query: &str) -> String {
+    serde_json::json!({
+        "id": REGISTRY_SUBSCRIPTION_ID,
+        "type": "start",
+        "payload": {
+            "query": query
+        }
+    })
+    .to_string()
+}
+
+type RegistrySink = SplitSink<
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+    Message,
+>;
+
+// Upper bound for `waitForMs`, so a lookup can't hold a request open forever
+pub const MAX_COMPONENT_WAIT: Duration = Duration::from_secs(30);
+
 #[derive(Clone)]
 pub struct ComponentDaemon {
-    components: Arc<DashMap<String, Component>>,
-    all_components: Arc<tokio::sync::Mutex<Vec<Component>>>,
-    broadcast_tx: broadcast::Sender<Component>,
+    // Values are shared so read snapshots copy pointers, not components
+    components: Arc<DashMap<String, Arc<Component>>>,
+    history: Arc<History>,
+    graph: Arc<ComponentGraph>,
+    updates: Arc<UpdateChannels>,
+    watchers: IdChannels,
+    removal_tx: broadcast::Sender<ComponentRemoval>,
+    // Upserts and removals on one ring, in the order they happened
+    changes: broadcast::Sender<ComponentChange>,
+    // Per-subscriber rendererUpdate backlog, in distinct ids
+    delivery_capacity: usize,
+    ack_tx: broadcast::Sender<Acknowledgement>,
+    acks: Arc<AckConfig>,
+    upstream: Arc<Upstream>,
+    registry_errors: Arc<RegistryErrors>,
+    forms: Arc<FormSubmissions>,
+    limits: SizeLimits,
+    blobs: Arc<BlobStore>,
+    attachments: Arc<Attachments>,
+    flags: Arc<FeatureFlags>,
+    latency: Arc<LatencyTracker>,
+    hydration: Arc<Hydration>,
+    replication: Arc<Replication>,
+    subscribers: Arc<SubscriberRegistry>,
+    interactions: Arc<Interactions>,
+    pages: Arc<PageSnapshots>,
+    lifecycle: Arc<Lifecycle>,
+    #[cfg(feature = "chaos")]
+    chaos: Arc<chaos::Chaos>,
+    metrics: Arc<Metrics>,
+    quotas: Arc<Quotas>,
+    validator: Arc<Validator>,
+    validation_mode: ValidationMode,
+    audit: Arc<AuditLog>,
+    reconnect: Arc<Notify>,
+    debouncer: Arc<Debouncer>,
+    priorities: Arc<PriorityConfig>,
+    label_rules: Arc<LabelRules>,
+    shadow: Arc<ShadowRules>,
+    scheduler: Arc<Scheduler>,
+    conflicts: Arc<ConflictConfig>,
+    ordering: OrderingKey,
+    signatures: Arc<SignatureVerifier>,
+    quarantine: Arc<Quarantine>,
+    redactor: Arc<Redactor>,
+    // Seals persisted files when AT_REST_KEYS is set
+    at_rest: Arc<AtRestCipher>,
+    retention: Arc<RetentionPolicy>,
+    component_bytes: Arc<ByteGauge>,
+    memory: Arc<MemoryBudget>,
+    // Distinguishes state ETags across restarts, when versions start over
+    instance_id: Arc<str>,
 }
 
 impl ComponentDaemon {
-    pub fn new() -> Self {
-        let (broadcast_tx, _) = broadcast::channel(100);
-        Self {
+    pub fn new() -> Result<Self> {
+        let capacity = env_parse("BROADCAST_CAPACITY", 100);
+        let (removal_tx, _) = broadcast::channel(capacity);
+        let (changes, _) = broadcast::channel(capacity);
+        let (ack_tx, _) = broadcast::channel(capacity);
+        let sample_size = env_parse("FAILURE_SAMPLE_SIZE", 20);
+        let metrics = Arc::new(Metrics::new(sample_size));
+        // Shared by every file the daemon persists
+        let at_rest = Arc::new(AtRestCipher::from_env().context("Failed to load at-rest keys")?);
+        Ok(Self {
             components: Arc::new(DashMap::new()),
-            all_components: Arc::new(tokio::sync::Mutex::new(Vec::new())),
-            broadcast_tx,
-        }
+            history: Arc::new(History::from_env()),
+            graph: Arc::new(ComponentGraph::default()),
+            updates: Arc::new(UpdateChannels::new(capacity)),
+            watchers: IdChannels::default(),
+            removal_tx,
+            changes,
+            delivery_capacity: env_parse("DELIVERY_QUEUE_CAPACITY", capacity).max(1),
+            ack_tx,
+            acks: Arc::new(AckConfig::from_env()),
+            upstream: Arc::new(Upstream::from_env()),
+            registry_errors: Arc::new(RegistryErrors::from_env()),
+            forms: Arc::new(FormSubmissions::from_env()),
+            limits: SizeLimits::from_env(),
+            blobs: Arc::new(BlobStore::from_env(at_rest.clone())),
+            attachments: Arc::new(Attachments::from_env(metrics.clone()).context("Failed to load attachment settings")?),
+            flags: Arc::new(FeatureFlags::from_env().unwrap_or_else(|e| {
+                error!("❌ Daemon: Failed to load feature flags, using defaults: {:#}", e);
+                FeatureFlags::default()
+            })),
+            latency: Arc::new(LatencyTracker::from_env()),
+            hydration: Arc::new(Hydration::from_env()),
+            replication: Arc::new(Replication::from_env()),
+            subscribers: Arc::new(SubscriberRegistry::from_env(at_rest.clone())),
+            interactions: Arc::new(Interactions::from_env(capacity)),
+            pages: Arc::new(PageSnapshots::from_env()),
+            lifecycle: Arc::new(Lifecycle::new(capacity, metrics.clone())),
+            #[cfg(feature = "chaos")]
+            chaos: Arc::new(chaos::Chaos::from_env()),
+            metrics,
+            // Starting without limits would silently switch enforcement off
+            quotas: Arc::new(Quotas::from_env().context("Failed to load quotas")?),
+            // Running without the schemas would accept what they reject
+            validator: Arc::new(Validator::from_env().context("Failed to load component schemas")?),
+            validation_mode: ValidationMode::from_env(),
+            // The journal is the compliance record, so it never quietly
+            // stops persisting
+            audit: Arc::new(AuditLog::from_env(at_rest.clone()).context("Failed to open audit log")?),
+            reconnect: Arc::new(Notify::new()),
+            debouncer: Arc::new(Debouncer::from_env()),
+            priorities: Arc::new(PriorityConfig::from_env()),
+            label_rules: Arc::new(LabelRules::from_env()),
+            shadow: Arc::new(ShadowRules::from_env().unwrap_or_else(|e| {
+                error!("❌ Daemon: Failed to load shadow rules, not evaluating them: {:#}", e);
+                ShadowRules::disabled()
+            })),
+            scheduler: Arc::new(Scheduler::from_env()),
+            conflicts: Arc::new(ConflictConfig::from_env()),
+            ordering: OrderingKey::from_env(),
+            // A broken key or policy must not quietly turn verification off
+            signatures: Arc::new(SignatureVerifier::from_env().context("Failed to load signature settings")?),
+            quarantine: Arc::new(Quarantine::from_env()),
+            redactor: Arc::new(Redactor::from_env().unwrap_or_else(|e| {
+                // Without valid rules, raw payloads are kept out of logs entirely
+                error!("❌ Daemon: Failed to load redaction rules, payload logging disabled: {:#}", e);
+                Redactor::redact_all()
+            })),
+            retention: Arc::new(RetentionPolicy::from_env()),
+            component_bytes: Arc::new(ByteGauge::default()),
+            memory: Arc::new(MemoryBudget::from_env()),
+            instance_id: uuid::Uuid::new_v4().simple().to_string()[..8].into(),
+            at_rest,
+        })
     }
 
     pub async fn start(&self) -> Result<()> {
+        // A hot standby takes its components from the primary instead
+        if self.replication.is_following() {
+            replication::spawn_follower(self);
+        } else {
+            let daemon = self.clone();
+            tokio::spawn(async move {
+                daemon.connect_to_registry().await;
+            });
+        }
+
+        if self.retention.enabled() {
+            let daemon = self.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(daemon.retention.interval);
+                loop {
+                    ticker.tick().await;
+                    daemon.enforce_retention();
+                }
+            });
+        }
+
         let daemon = self.clone();
         tokio::spawn(async move {
-            daemon.connect_to_registry().await;
+            loop {
+                for mut component in daemon.scheduler.next_due().await {
+                    info!("⏰ Daemon: Delivering scheduled component {}", component.id);
+                    daemon.attachments.attach_stored(&mut component.data, &daemon.blobs);
+                    daemon.offer(component).await;
+                }
+            }
         });
 
+        interactions::spawn_webhooks(self);
+        self.subscribers.spawn_persistence();
+        self.blobs.spawn_recovery();
+        self.audit.spawn_recovery();
+        self.audit.spawn_writer();
+
+        #[cfg(feature = "chaos")]
+        {
+            warn!("🐒 Daemon: Built with fault injection (chaos feature)");
+            self.chaos.spawn_disconnects(self);
+        }
+
+        let push = push::PushConfig::from_env();
+        if !push.urls.is_empty() {
+            push::spawn(self, push);
+        }
+
         info!("🚀 Daemon: Started");
         Ok(())
     }
@@ -75,16 +529,21 @@ impl ComponentDaemon {
         loop {
             info!("🔌 Daemon: Connecting to registry...");
 
-            match self.try_connect_to_registry().await {
+            let detail = match self.try_connect_to_registry().await {
                 Ok(_) => {
                     warn!("🔌 Daemon: Connection to registry closed, reconnecting...");
+                    "connection closed".to_string()
                 }
                 Err(e) => {
                     error!("❌ Daemon: Registry connection error: {}", e);
+                    e.to_string()
                 }
-            }
+            };
+            self.lifecycle.emit(InternalEventKind::UpstreamDisconnected, Some(detail), None);
 
-            sleep(Duration::from_secs(2)).await;
+            let backoff = self.registry_errors.take_backoff().unwrap_or(REGISTRY_RECONNECT_BACKOFF);
+            self.lifecycle.emit(InternalEventKind::BackoffEntered, Some(format!("{backoff:?}")), None);
+            sleep(backoff).await;
         }
     }
 
@@ -94,19 +553,23 @@ impl ComponentDaemon {
         let url = format!("ws://{registry_host}:{registry_port}/graphql");
         
         info!("🔌 Daemon: Attempting to connect to {}", url);
+        self.upstream.set_ready(false);
         
         // Try the exact approach that works with your Node.js setup
         use tokio_tungstenite::tungstenite;
         
-        let request = tungstenite::http::Request::builder()
+        let mut request = tungstenite::http::Request::builder()
             .uri(&url)
             .header("Host", format!("{registry_host}:{registry_port}"))
             .header("Connection", "Upgrade")
             .header("Upgrade", "websocket")
             .header("Sec-WebSocket-Version", "13")
             .header("Sec-WebSocket-Key", tungstenite::handshake::client::generate_key())
-            .header("Sec-WebSocket-Protocol", "graphql-ws")
-            .body(())?;
+            .header("Sec-WebSocket-Protocol", "graphql-ws");
+        if let Some(authorization) = self.registry_errors.authorization() {
+            request = request.header("Authorization", authorization);
+        }
+        let request = request.body(())?;
             
         info!("🔌 Daemon: Built WebSocket request with graphql-ws protocol");
         
@@ -116,21 +579,18 @@ impl ComponentDaemon {
                 
                 let (mut write, mut read) = ws_stream.split();
 
-                // Send connection_init exactly like Node.js version
-                let init_message = serde_json::json!({
-                    "type": "connection_init"
-                });
+                // Send connection_init exactly like Node.js version, plus credentials
+                let init_message = self.registry_errors.connection_init();
                 let init_json = serde_json::to_string(&init_message)?;
-                info!("📤 Daemon: Sending connection_init: {}", init_json);
+                // The payload carries the registry token, so it is never logged
+                info!("📤 Daemon: Sending connection_init (credentials: {})", self.registry_errors.authorization().is_some());
                 write.send(Message::Text(init_json)).await?;
 
-                while let Some(message) = read.next().await {
+                while let Some(message) = self.next_or_reconnect(&mut read, &mut write).await {
                     match message {
                         Ok(Message::Text(text)) => {
-                            info!("📨 Daemon: Raw message from registry: {}", text);
-                            if let Err(e) = self.handle_registry_message(&mut write, &text).await {
-                                error!("Error handling registry message: {}", e);
-                            }
+                            info!("📨 Daemon: Raw message from registry: {}", self.redactor.redact_message(&text));
+                            self.receive_registry_text(&mut write, &url, &text).await;
                         }
                         Ok(Message::Close(frame)) => {
                             if let Some(f) = frame {
@@ -160,26 +620,25 @@ impl ComponentDaemon {
                 
                 // Fallback: try without subprotocol
                 info!("🔌 Daemon: Trying without subprotocol...");
-                match connect_async(url).await {
+                match connect_async(url.as_str()).await {
                     Ok((ws_stream, response)) => {
                         info!("✅ Daemon: Connected without subprotocol, status: {}", response.status());
                         
                         let (mut write, mut read) = ws_stream.split();
                         
-                        let init_message = serde_json::json!({
-                            "type": "connection_init"
-                        });
+                        let init_message = self.registry_errors.connection_init();
                         let init_json = serde_json::to_string(&init_message)?;
-                        info!("📤 Daemon: Sending connection_init (no subprotocol): {}", init_json);
+                        info!(
+                            "📤 Daemon: Sending connection_init (no subprotocol, credentials: {})",
+                            self.registry_errors.authorization().is_some()
+                        );
                         write.send(Message::Text(init_json)).await?;
 
-                        while let Some(message) = read.next().await {
+                        while let Some(message) = self.next_or_reconnect(&mut read, &mut write).await {
                             match message {
                                 Ok(Message::Text(text)) => {
-                                    info!("📨 Daemon: Raw message: {}", text);
-                                    if let Err(e) = self.handle_registry_message(&mut write, &text).await {
-                                        error!("Error handling registry message: {}", e);
-                                    }
+                                    info!("📨 Daemon: Raw message: {}", self.redactor.redact_message(&text));
+                                    self.receive_registry_text(&mut write, &url, &text).await;
                                 }
                                 Ok(Message::Close(frame)) => {
                                     if let Some(f) = frame {
@@ -206,17 +665,59 @@ impl ComponentDaemon {
         Ok(())
     }
 
+    // Also drains queued upstream operations once the registry has acked
+    async fn next_or_reconnect<S>(&self, read: &mut S, write: &mut RegistrySink) -> Option<S::Item>
+    where
+        S: futures::Stream + Unpin,
+    {
+        let mut outbox = self.upstream.outbox().await;
+        loop {
+            if self.registry_errors.take_reconnect() {
+                info!("🔄 Daemon: Dropping registry connection after a registry error");
+                return None;
+            }
+            tokio::select! {
+                message = read.next() => return message,
+                Some(operation) = outbox.recv(), if self.upstream.is_ready() => {
+                    if let Err(e) = write.send(Message::Text(operation)).await {
+                        error!("❌ Daemon: Failed to send operation to registry: {}", e);
+                        return None;
+                    }
+                }
+                _ = self.reconnect.notified() => {
+                    info!("🔄 Daemon: Reconnect requested, dropping registry connection");
+                    return None;
+                }
+            }
+        }
+    }
+
+    async fn receive_registry_text(&self, write: &mut RegistrySink, upstream: &str, text: &str) {
+        #[cfg(feature = "chaos")]
+        let messages = self.chaos.perturb(text).await;
+        #[cfg(not(feature = "chaos"))]
+        let messages = [text];
+        for text in messages {
+            if let Err(e) = self.handle_registry_message(write, upstream, text.as_ref()).await {
+                error!("Error handling registry message: {}", e);
+            }
+        }
+    }
+
     async fn handle_registry_message(
         &self,
-        write: &mut SplitSink<
-            tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
-            Message,
-        >,
+        write: &mut RegistrySink,
+        upstream: &str,
         text: &str,
     ) -> Result<()> {
         // Parse as generic JSON first to see the message type
-        let message: serde_json::Value = serde_json::from_str(text)
-            .context("Failed to parse message from registry")?;
+        let message: serde_json::Value = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(e) => {
+                self.metrics.ingest_failures.record_raw(upstream, &e, text);
+                return Err(e).context("Failed to parse message from registry");
+            }
+        };
 
         let msg_type = message.get("type").and_then(|v| v.as_str()).unwrap_or("unknown");
         info!("📨 Daemon: Received message type: {}", msg_type);
@@ -224,41 +725,52 @@ impl ComponentDaemon {
         match msg_type {
             "connection_ack" => {
                 info!("📡 Daemon: Registry connection acknowledged, starting subscription...");
-                // Send start subscription using subscriptions-transport-ws format
-                let subscription = serde_json::json!({
-                    "id": "registry-sub",
-                    "type": "start",
-                    "payload": {
-                        "query": "subscription { componentUpdate { id type data createdAt } }"
-                    }
-                });
-                let sub_json = serde_json::to_string(&subscription)?;
+                self.upstream.set_ready(true);
+                self.lifecycle.emit(InternalEventKind::UpstreamConnected, Some(upstream.to_string()), None);
+                // Registries that publish deletions add `deleted` to the selection,
+                // and those with layouts add `parentId relatedIds`
+                let sub_json = subscription_start(&self.registry_errors.query());
                 info!("📡 Daemon: Sending subscription: {}", sub_json);
                 write.send(Message::Text(sub_json)).await?;
+                // Subscribed first, so nothing changes unseen while the snapshot loads
+                self.start_hydration(write, upstream).await?;
+            }
+            "data" | "error" | "complete" if message["id"] == hydration::OPERATION_ID => {
+                let payload = match msg_type {
+                    "data" => message["payload"].clone(),
+                    "error" => serde_json::json!({ "errors": message["payload"] }),
+                    _ => return Ok(()),
+                };
+                self.hydrate(upstream, hydration::components_in(&payload)).await;
+            }
+            "data" | "error" | "complete" if message["id"].as_str().is_some_and(|id| id.starts_with(upstream::OPERATION_ID_PREFIX)) => {
+                // "error" carries the error list itself as its payload
+                let payload = match msg_type {
+                    "error" => serde_json::json!({ "errors": message["payload"] }),
+                    _ => message["payload"].clone(),
+                };
+                if let Some(errors) = payload.get("errors") {
+                    warn!("⚠️ Daemon: Registry rejected forwarded operation: {}", errors);
+                }
+                if let (Some(id), false) = (message["id"].as_str(), payload.is_null()) {
+                    self.upstream.complete(id, payload);
+                }
             }
             "data" => {
                 if let Some(payload) = message.get("payload") {
                     if let Some(errors) = payload.get("errors") {
-                        error!("❌ Daemon: GraphQL subscription errors: {}", 
-                              serde_json::to_string_pretty(errors)?);
+                        self.react_to_registry_error(write, errors).await?;
                     } else if let Some(data) = payload.get("data") {
                         if let Some(component_update) = data.get("componentUpdate") {
-                            match serde_json::from_value::<Component>(component_update.clone()) {
-                                Ok(component) => {
-                                    info!("📦 Daemon: Received component from registry: {}", component.id);
-                                    self.handle_component_from_registry(component).await?;
-                                },
-                                Err(e) => {
-                                    error!("❌ Daemon: Failed to deserialize component: {}\nValue: {}", e, component_update);
-                                }
-                            }
+                            self.registry_errors.succeeded();
+                            self.ingest_registry_value(upstream, component_update).await?;
                         }
                     }
                 }
             }
-            "error" => {
+            "error" | "connection_error" => {
                 if let Some(payload) = message.get("payload") {
-                    error!("❌ Daemon: GraphQL error from registry: {}", payload);
+                    self.react_to_registry_error(write, payload).await?;
                 }
             }
             "complete" => {
@@ -269,166 +781,1877 @@ impl ComponentDaemon {
                 info!("💓 Daemon: Keep-alive from registry");
             }
             _ => {
-                info!("ℹ️ Daemon: Unknown message type '{}': {}", msg_type, text);
+                info!("ℹ️ Daemon: Unknown message type '{}': {}", msg_type, self.redactor.redact_message(text));
             }
         }
 
         Ok(())
     }
 
-    async fn handle_component_from_registry(&self, component: Component) -> Result<()> {
-        info!("📦 Daemon: Forwarding component {} to renderer", component.id);
-        self.components.insert(component.id.clone(), component.clone());
-        // Store every received component for history/counting
-        let count = {
-            let mut all = self.all_components.lock().await;
-            all.push(component.clone());
-            all.len()
-        };
-        info!("📦 Daemon: Total received components so far: {}", count);
-        // Broadcast to all GraphQL subscriptions
-        let _ = self.broadcast_tx.send(component.clone());
+    async fn react_to_registry_error(&self, write: &mut RegistrySink, errors: &serde_json::Value) -> Result<()> {
+        if let Reaction::Resubscribe(query) = self.registry_errors.handle(errors) {
+            let stop = serde_json::json!({ "id": REGISTRY_SUBSCRIPTION_ID, "type": "stop" });
+            write.send(Message::Text(stop.to_string())).await?;
+            write.send(Message::Text(subscription_start(&query))).await?;
+        }
         Ok(())
     }
 
+    // Tombstones and deserialization for one component object from the
+    // registry, then the shared ingest path, which checks its signature
+    async fn ingest_registry_value(&self, upstream: &str, component_update: &serde_json::Value) -> Result<()> {
+        if component_update.get("deleted").and_then(|d| d.as_bool()) == Some(true) {
+            if let Some(id) = component_update.get("id").and_then(|id| id.as_str()) {
+                info!("🪦 Daemon: Received tombstone from registry: {}", id);
+                self.remove_component(id, RemovalReason::Tombstone);
+            }
+            return Ok(());
+        }
+        match serde_json::from_value::<Component>(component_update.clone()) {
+            Ok(mut component) => {
+                info!("📦 Daemon: Received component from registry: {}", component.id);
+                component.stamps.received = Some(std::time::Instant::now());
+                self.handle_component_from_registry(upstream, component, Source::Registry(component_update))
+                    .await?;
+            },
+            Err(e) => {
+                self.metrics.ingest_failures.record(upstream, &e, component_update);
+                error!("❌ Daemon: Failed to deserialize component: {}\nValue: {}", e, self.redactor.redact_component(component_update));
+            }
+        }
+        Ok(())
+    }
 
+    async fn start_hydration(&self, write: &mut RegistrySink, upstream: &str) -> Result<()> {
+        match self.hydration.mode() {
+            HydrationMode::Off => return Ok(()),
+            HydrationMode::Ws => {
+                info!("💧 Daemon: Requesting current components from registry");
+                write.send(Message::Text(self.hydration.start_message())).await?;
+            }
+            HydrationMode::Http => {
+                info!("💧 Daemon: Fetching current components from registry over HTTP");
+                let daemon = self.clone();
+                let upstream = upstream.to_string();
+                tokio::spawn(async move {
+                    let result = daemon.hydration.fetch_http(&upstream).await;
+                    daemon.hydrate(&upstream, result).await;
+                });
+            }
+        }
+        // Don't hold readiness forever on a registry that never answers
+        let daemon = self.clone();
+        tokio::spawn(async move {
+            sleep(daemon.hydration.timeout).await;
+            if daemon.hydration.mark_ready() {
+                warn!("⏳ Daemon: No hydration response within {:?}, reporting ready without it", daemon.hydration.timeout);
+            }
+        });
+        Ok(())
+    }
 
-    pub fn get_components(&self) -> Vec<Component> {
-        self.components.iter().map(|entry| entry.value().clone()).collect()
+    async fn hydrate(&self, upstream: &str, result: Result<Vec<serde_json::Value>>) {
+        let components = match result {
+            Ok(components) => components,
+            Err(e) => {
+                error!("❌ Daemon: Hydration from registry failed: {:#}", e);
+                if self.hydration.mark_ready() {
+                    warn!("⏳ Daemon: Reporting ready without hydration");
+                }
+                return;
+            }
+        };
+        // Re-hydrating after a reconnect skips what the daemon already has, so
+        // renderers aren't sent unchanged components again
+        let mut loaded = 0;
+        for value in &components {
+            let unchanged = value["id"]
+                .as_str()
+                .and_then(|id| self.get_component(id))
+                .is_some_and(|stored| stored.data == value["data"]);
+            if unchanged {
+                continue;
+            }
+            if let Err(e) = self.ingest_registry_value(upstream, value).await {
+                error!("Error hydrating component: {}", e);
+            }
+            loaded += 1;
+        }
+        info!("💧 Daemon: Hydrated {} of {} registry components", loaded, components.len());
+        self.lifecycle.emit(InternalEventKind::Hydrated, Some(upstream.to_string()), Some(loaded));
+        self.hydration.mark_ready();
     }
 
-    pub async fn get_all_components_count(&self) -> usize {
-        let all = self.all_components.lock().await;
-        all.len()
+    pub fn is_ready(&self) -> bool {
+        self.hydration.is_ready()
+            && self.replication.is_warm()
+            && !self.blobs.blocks_readiness()
+            && !self.audit.blocks_readiness()
+            && !self.subscribers.blocks_readiness()
     }
 
-    pub fn subscribe_to_updates(&self) -> broadcast::Receiver<Component> {
-        self.broadcast_tx.subscribe()
+    // Serving, but a storage backend is failing; see BLOB_DEGRADATION,
+    // AUDIT_DEGRADATION and SUBSCRIBER_REGISTRY_DEGRADATION
+    pub fn is_degraded(&self) -> bool {
+        self.blobs.is_degraded() || self.audit.is_degraded() || self.subscribers.is_degraded()
     }
-}
 
-// ========================
-// GRAPHQL SCHEMA
-// ========================
+    async fn handle_component_from_registry(&self, upstream: &str, component: Component, source: Source<'_>) -> Result<()> {
+        // Rejections are logged and counted inside ingest
+        let _ = self.ingest(upstream, component, source).await;
+        Ok(())
+    }
 
-pub struct Query;
+    // Signatures, validation, priority and labels, then the debounce/publish
+    // path shared by the registry, imports and seeding. Err carries the
+    // rejection reason.
+    pub(crate) async fn ingest(&self, upstream: &str, mut component: Component, source: Source<'_>) -> std::result::Result<(), String> {
+        if let Source::Local = source {
+            self.writable().map_err(|e| e.to_string())?;
+        }
+        let signed = match source {
+            Source::Registry(raw) => self.signatures.check(raw),
+            Source::Local => self.signatures.check_local(),
+            Source::Trusted => Ok(()),
+        };
+        if let Err(reason) = signed {
+            let raw = match source {
+                Source::Registry(raw) => Some(raw),
+                Source::Local | Source::Trusted => None,
+            };
+            self.metrics.ingest_failures.record_rejection(
+                upstream,
+                FailureKind::Signature,
+                reason.clone(),
+                raw.unwrap_or(&component.data),
+            );
+            // Only registry payloads can be released later; local writes are
+            // simply refused
+            match (self.signatures.action(), raw) {
+                (FailureAction::Quarantine, Some(raw)) => {
+                    warn!("🔒 Daemon: Quarantined component with bad signature: {}", reason);
+                    self.quarantine.hold(upstream, reason.clone(), raw.clone());
+                }
+                _ => warn!("🚫 Daemon: Rejected component {} by signature policy: {}", component.id, reason),
+            }
+            return Err(reason);
+        }
 
-#[Object]
-impl Query {
-    async fn components(&self, ctx: &async_graphql::Context<'_>) -> Result<Vec<Component>, Error> {
-        let daemon = ctx.data::<ComponentDaemon>()
-            .map_err(|_| Error::new("ComponentDaemon not found in context"))?;
-        Ok(daemon.get_components())
-    }
-}
+        // Candidate rules see the component as the active ones did
+        let original = self.shadow.enabled().then(|| component.clone());
+        if self.validation_mode != ValidationMode::Off {
+            let mut report = self.validate(component.r#type, &component.data);
+            if !report.valid {
+                for violation in &mut report.violations {
+                    violation.message = self.redactor.redact_text(&violation.message).into_owned();
+                }
+                validation::log_violations(&component.id, &report);
+                let summary = report
+                    .violations
+                    .iter()
+                    .map(|v| format!("{}: {}", v.rule, v.message))
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                let rules = report
+                    .violations
+                    .iter()
+                    .map(|v| format!("{} at {}", v.rule, v.path))
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                self.metrics.ingest_failures.record_rejection(upstream, FailureKind::Validation, rules, &component.data);
+                if self.validation_mode == ValidationMode::Reject {
+                    warn!("🚫 Daemon: Rejected invalid component {}", component.id);
+                    if let Some(original) = &original {
+                        self.shadow.compare(&self.validator, original, Outcome::Dropped);
+                    }
+                    return Err(summary);
+                }
+            }
+        }
 
-pub struct Subscription;
+        self.enforce_size_limit(upstream, &component.id, &mut component.data)
+            .map_err(|too_large| too_large.to_string())?;
+        let missing = self.attachments.attach_stored(&mut component.data, &self.blobs);
+        if !missing.is_empty() {
+            self.fetch_attachments(component.id.clone(), missing);
+        }
 
-#[Subscription]
-impl Subscription {
-    
-    async fn rendererUpdate(&self, ctx: &async_graphql::Context<'_>) -> Result<impl futures::Stream<Item = Component>, Error> {
-        info!("📡 Daemon: Renderer subscribed to updates");
-        
-        let daemon = ctx.data::<ComponentDaemon>()
-            .map_err(|_| Error::new("ComponentDaemon not found in context"))?;
-        
-        let mut receiver = daemon.subscribe_to_updates();
-        
-        let stream = stream! {
-            while let Ok(component) = receiver.recv().await {
-                yield component;
+        component.priority = Some(self.priorities.resolve(&component));
+        component.labels = self.label_rules.resolve(&component);
+        if let Some(original) = &original {
+            let active = Outcome::Kept {
+                priority: component.priority.unwrap_or(0),
+                labels: &component.labels,
+            };
+            self.shadow.compare(&self.validator, original, active);
+        }
+
+        if self.flags.enabled(Flag::Dedup) && self.is_duplicate(&component) {
+            info!("🔁 Daemon: Skipping unchanged component {}", component.id);
+            self.metrics.dedup_suppressed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            return Ok(());
+        }
+
+        if self.memory.exceeded(self.memory_used())
+            && component.priority.unwrap_or(0) < self.memory.priority_floor
+            && !self.components.contains_key(&component.id)
+        {
+            warn!("🧮 Daemon: Over memory budget, refusing new component {}", component.id);
+            self.metrics.memory_refused.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            return Err("Over memory budget".to_string());
+        }
+
+        if let Some(at) = schedule::deliver_at(&component).filter(|at| *at > Utc::now()) {
+            info!("⏰ Daemon: Holding component {} until {}", component.id, at.to_rfc3339());
+            component.deliver_at = Some(at);
+            // Held on purpose, so not timed
+            component.stamps = Stamps::default();
+            return self.scheduler.hold(component, at).inspect_err(|reason| {
+                warn!("⏰ Daemon: Dropped scheduled component: {}", reason);
+            });
+        }
+
+        self.offer(component).await;
+        Ok(())
+    }
+
+    // Checked after validation, which sees the payload as the registry sent it
+    fn enforce_size_limit(
+        &self,
+        upstream: &str,
+        id: &str,
+        data: &mut serde_json::Value,
+    ) -> std::result::Result<(), limits::TooLarge> {
+        match self.limits.enforce(data, &self.blobs) {
+            Ok(None) => Ok(()),
+            Ok(Some(action)) => {
+                warn!("📏 Daemon: Oversized component {} {}", id, action.label());
+                *self.metrics.oversized.entry(action.label()).or_insert(0) += 1;
+                Ok(())
             }
-        };
-        
-        Ok(stream)
+            Err(too_large) => {
+                warn!("📏 Daemon: Rejected oversized component {}: {}", id, too_large);
+                *self.metrics.oversized.entry("rejected").or_insert(0) += 1;
+                self.metrics.ingest_failures.record_rejection(
+                    upstream,
+                    FailureKind::Oversized,
+                    too_large.to_string(),
+                    &serde_json::json!({ "id": id, "bytes": too_large.bytes }),
+                );
+                Err(too_large)
+            }
+        }
     }
-}
 
+    // Same content as the stored component, with no other update in flight
+    fn is_duplicate(&self, component: &Component) -> bool {
+        if self.debouncer.is_pending(&component.id) || schedule::deliver_at(component).is_some() {
+            return false;
+        }
+        self.components.get(&component.id).is_some_and(|stored| {
+            stored.r#type == component.r#type
+                && stored.data == component.data
+                && stored.priority == component.priority
+                && stored.labels == component.labels
+                && stored.parent_id == component.parent_id
+                && stored.related_ids == component.related_ids
+        })
+    }
 
+    pub fn flags(&self) -> &FeatureFlags {
+        &self.flags
+    }
 
-// ========================
-// SERVER
-// ========================
+    pub fn latency(&self) -> &LatencyTracker {
+        &self.latency
+    }
 
-pub async fn start_daemon(port: u16) -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+    pub fn replication(&self) -> &Replication {
+        &self.replication
+    }
 
-    let daemon = ComponentDaemon::new();
-    daemon.start().await?;
+    pub fn shadow(&self) -> &ShadowRules {
+        &self.shadow
+    }
 
-    // Create GraphQL schema
-    let schema = Schema::build(Query, EmptyMutation, Subscription)
-        .data(daemon.clone())
-        .finish();
+    pub fn size_limits(&self) -> SizeLimits {
+        self.limits
+    }
 
-    // Health check endpoint
-    let daemon_for_health = daemon.clone();
-    let health = warp::path::end()
-        .and_then(move || {
-            let daemon_for_health = daemon_for_health.clone();
-            async move {
-                let components_count = daemon_for_health.get_all_components_count().await;
-                Ok::<_, Infallible>(warp::reply::json(&serde_json::json!({
-                    "message": "Component Daemon - Real Connection",
-                    "components": components_count,
-                    "status": "Connected to registry"
-                })))
+    pub fn blobs(&self) -> Arc<BlobStore> {
+        self.blobs.clone()
+    }
+
+    async fn offer(&self, component: Component) {
+        let id = component.id.clone();
+        match self.debouncer.offer(component) {
+            Debounced::Immediate(component) => self.publish(*component).await,
+            Debounced::Scheduled => self.schedule_flush(id),
+            Debounced::Coalesced => {
+                self.metrics.debounce_suppressed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
             }
-        });
+        }
+    }
 
-    // GraphQL Playground (for browser testing)
-    let graphql_playground = warp::path("playground")
-        .and(warp::get())
-        .map(|| {
-            warp::reply::html(async_graphql::http::playground_source(
-                async_graphql::http::GraphQLPlaygroundConfig::new("/graphql")
-            ))
+    fn schedule_flush(&self, id: String) {
+        let daemon = self.clone();
+        tokio::spawn(async move {
+            sleep(daemon.debouncer.window()).await;
+            if let Some((mut component, suppressed)) = daemon.debouncer.take(&id) {
+                if suppressed > 0 {
+                    info!("⏱️ Daemon: Coalesced {} intermediate updates for {}", suppressed, id);
+                }
+                daemon.attachments.attach_stored(&mut component.data, &daemon.blobs);
+                daemon.publish(component).await;
+            }
         });
+    }
 
-    // GraphQL endpoint for queries and mutations  
-    let graphql_post = warp::path("graphql")
-        .and(async_graphql_warp::graphql(schema.clone()))
-        .and_then(
-            |(schema, request): (
-                async_graphql::Schema<Query, EmptyMutation, Subscription>,
-                async_graphql::Request,
-            )| async move {
-                Ok::<_, Infallible>(async_graphql_warp::GraphQLResponse::from(schema.execute(request).await))
-            },
-        );
+    async fn publish(&self, mut component: Component) {
+        // Stored copies carry no stamps, so local writes that start from them
+        // aren't timed from this receive
+        let stamps = std::mem::take(&mut component.stamps);
+        let mut component = match self.components.entry(component.id.clone()) {
+            Entry::Occupied(mut stored) => {
+                if let Some(reason) = self.ordering.stale(stored.get(), &component) {
+                    warn!("🔀 Daemon: Dropped out-of-order update for {}: {}", stored.key(), reason);
+                    self.metrics.reordered_dropped.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    return;
+                }
+                match self.conflicts.resolve(Some(&**stored.get()), component) {
+                    Resolution::Apply(component) => {
+                        let mut component = *component;
+                        component.version = stored.get().version + 1;
+                        component.seq = self.history.next_seq();
+                        let old = stored.insert(Arc::new(component.clone()));
+                        self.component_bytes.replace(approx_size(&old), approx_size(&component));
+                        component
+                    }
+                    Resolution::Reject { reason } => {
+                        warn!("⚔️ Daemon: Kept stored {}: {}", stored.key(), reason);
+                        self.metrics.conflict_rejected.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        return;
+                    }
+                }
+            }
+            Entry::Vacant(slot) => {
+                // Every version bump takes a seq, so starting from the seq
+                // puts a recreated id past any version it had before and an
+                // old If-Match can't match the new component
+                component.seq = self.history.next_seq();
+                component.version = component.seq;
+                self.component_bytes.add(approx_size(&component));
+                slot.insert(Arc::new(component.clone()));
+                component
+            }
+        };
+
+        component.stamps = stamps;
+        self.graph.link(&component.id, component.parent_id.as_deref());
+        info!("📦 Daemon: Forwarding component {} to renderer", component.id);
+        self.broadcast(component);
+        info!("📦 Daemon: Total received components so far: {}", self.history.received_total());
+
+        if self.memory.exceeded(self.memory_used()) {
+            self.enforce_memory_budget();
+        }
+    }
 
-    let graphql_ws = async_graphql_warp::graphql_subscription(schema.clone());
+    // Fetches in the background so a slow origin doesn't hold up the registry
+    // read loop, then publishes the stored component again with the assets
+    // listed. Held or debounced components pick them up when they go out.
+    fn fetch_attachments(&self, id: String, urls: Vec<(String, url::Url)>) {
+        let daemon = self.clone();
+        tokio::spawn(async move {
+            daemon.attachments.fetch_all(urls, &daemon.blobs).await;
+            let component = {
+                let Some(mut stored) = daemon.components.get_mut(&id) else {
+                    return;
+                };
+                let mut component = (**stored).clone();
+                daemon.attachments.attach_stored(&mut component.data, &daemon.blobs);
+                if component.data == stored.data {
+                    return;
+                }
+                component.version = stored.version + 1;
+                component.seq = daemon.history.next_seq();
+                let old = std::mem::replace(&mut *stored, Arc::new(component.clone()));
+                daemon.component_bytes.replace(approx_size(&old), approx_size(&component));
+                component
+            };
+            info!("📎 Daemon: Forwarding component {} with its attachments", component.id);
+            daemon.broadcast(component);
+        });
+    }
 
+    pub fn memory_used(&self) -> u64 {
+        self.component_bytes.get() + self.history.approx_bytes()
+    }
 
+    // Drops the lowest-priority, oldest components until usage is back under
+    // the eviction target. Components at or above the priority floor stay.
+    fn enforce_memory_budget(&self) -> usize {
+        if self.replication.is_following() {
+            return 0;
+        }
+        let Some(target) = self.memory.eviction_target() else {
+            return 0;
+        };
+        let mut candidates: Vec<Arc<Component>> = self
+            .get_components()
+            .into_iter()
+            .filter(|component| component.priority.unwrap_or(0) < self.memory.priority_floor)
+            .collect();
+        candidates.sort_by_key(|component| (component.priority, component.created_at));
 
-    let routes = health
-        .or(graphql_playground)
-        .or(graphql_post.or(graphql_ws))
-        .with(
-            warp::cors()
-                .allow_any_origin()
-                .allow_headers(vec!["content-type"])
-                .allow_methods(vec!["GET", "POST"])
+        let mut evicted = 0;
+        for candidate in candidates {
+            if self.memory_used() <= target {
+                break;
+            }
+            if let Some((id, removed)) = self
+                .components
+                .remove_if(&candidate.id, |_, stored| stored.version == candidate.version)
+            {
+                self.component_bytes.sub(approx_size(&removed));
+                self.cancel_pending(&id);
+                self.emit_removal(&id, RemovalReason::Evicted);
+                evicted += 1;
+            }
+        }
+        self.metrics.memory_evicted.fetch_add(evicted as u64, std::sync::atomic::Ordering::Relaxed);
+        self.lifecycle.emit(InternalEventKind::MemoryEvictionRan, None, Some(evicted as u64));
+        warn!(
+            "🧮 Daemon: Over memory budget, evicted {} components ({} bytes in use)",
+            evicted,
+            self.memory_used()
         );
+        evicted
+    }
 
-    info!("🚀 Component Daemon running on http://0.0.0.0:{}", port);
-    info!("📡 GraphQL: http://0.0.0.0:{}/graphql", port);
-    info!("🎮 Playground: http://0.0.0.0:{}/playground", port);
+    pub fn render_metrics(&self) -> String {
+        let mut out = self.metrics.render_prometheus();
+        self.memory.render(&mut out, self.component_bytes.get(), self.history.approx_bytes());
+        self.flags.render(&mut out);
+        self.latency.render(&mut out);
+        self.registry_errors.render(&mut out);
+        self.replication.render(&mut out);
+        self.blobs.render(&mut out);
+        self.quotas.render(&mut out);
+        self.shadow.render(&mut out);
+        out
+    }
 
-    warp::serve(routes)
-        .run(([0, 0, 0, 0], port))
-        .await;
+    // Records the component for resumption, then broadcasts it on its type's
+    // channel and the "all" channel. History goes first so a resuming
+    // subscriber that misses the live event finds it there.
+    fn broadcast(&self, mut component: Component) {
+        self.latency.broadcast(&mut component.stamps);
+        self.history.record(HistoryEvent::Upsert(component.clone()));
+        if self.watchers.is_watched(&component.id) {
+            self.watchers.send(ComponentChange::Updated(component.clone()));
+        }
+        if self.changes.receiver_count() > 0 {
+            let _ = self.changes.send(ComponentChange::Updated(component.clone()));
+        }
+        self.updates.send(component);
+    }
 
-    Ok(())
-}
 
-// ========================
-// MAIN
-// ========================
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    start_daemon(3001).await
+    // Stores a component exactly as the primary sent it, version and seq
+    // included, so a renderer failing over resumes from the cursor it had.
+    // No ordering, conflict or budget checks: the primary already made them.
+    pub(crate) fn apply_replicated(&self, component: Component) {
+        match self.components.entry(component.id.clone()) {
+            Entry::Occupied(mut stored) => {
+                // Already applied, e.g. from a resent snapshot, or overtaken:
+                // the primary broadcasts after releasing the shard lock, so
+                // two updates to one id can arrive in either order
+                if component.seq <= stored.get().seq {
+                    return;
+                }
+                let old = stored.insert(Arc::new(component.clone()));
+                self.component_bytes.replace(approx_size(&old), approx_size(&component));
+            }
+            Entry::Vacant(slot) => {
+                self.component_bytes.add(approx_size(&component));
+                slot.insert(Arc::new(component.clone()));
+            }
+        }
+        self.history.advance_past(component.seq);
+        self.graph.link(&component.id, component.parent_id.as_deref());
+        self.broadcast(component);
+    }
+
+    // Makes the store match a full snapshot from the primary; ids it no
+    // longer has are purged
+    pub(crate) fn apply_replicated_snapshot(&self, latest_seq: u64, components: Vec<Component>) {
+        let kept: std::collections::HashSet<&str> = components.iter().map(|component| component.id.as_str()).collect();
+        let gone: Vec<String> = self
+            .components
+            .iter()
+            .filter(|entry| !kept.contains(entry.key().as_str()))
+            .map(|entry| entry.key().clone())
+            .collect();
+        for id in gone {
+            self.remove_component(&id, RemovalReason::Purged);
+        }
+        for component in components {
+            self.apply_replicated(component);
+        }
+        self.history.advance_past(latest_seq);
+        // The primary's snapshot stands in for hydrating from the registry
+        self.hydration.mark_ready();
+    }
+
+    // Point-in-time snapshot; only the shared pointers are copied, so it
+    // stays cheap with thousands of components.
+    pub fn get_components(&self) -> Vec<Arc<Component>> {
+        self.components.iter().map(|entry| entry.value().clone()).collect()
+    }
+
+    // Changes with every ingest, update or removal. Read it before reading
+    // components, so a response is never tagged newer than its contents.
+    pub fn state_etag(&self) -> String {
+        format!("\"state-{}-{}\"", self.instance_id, self.history.state_version())
+    }
+
+    pub fn get_all_components_count(&self) -> u64 {
+        self.history.received_total()
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn pages(&self) -> &PageSnapshots {
+        &self.pages
+    }
+
+    pub fn upstream(&self) -> &Upstream {
+        &self.upstream
+    }
+
+    pub fn form_submissions(&self) -> &FormSubmissions {
+        &self.forms
+    }
+
+    pub fn subscribers(&self) -> Arc<SubscriberRegistry> {
+        self.subscribers.clone()
+    }
+
+    pub fn interactions(&self) -> &Interactions {
+        &self.interactions
+    }
+
+    pub fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    pub fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
+    }
+
+    pub fn graph(&self) -> &ComponentGraph {
+        &self.graph
+    }
+
+    pub fn subscribe_to_updates(&self) -> broadcast::Receiver<Component> {
+        self.updates.subscribe_all()
+    }
+
+    // Only the channels for `types` (all of them when None)
+    pub fn subscribe_to_types(&self, types: Option<&[ComponentType]>) -> UpdateReceiver {
+        self.updates.subscribe(types)
+    }
+
+    pub fn ingest_failures(&self) -> &IngestFailures {
+        &self.metrics.ingest_failures
+    }
+
+    pub fn metrics(&self) -> Arc<Metrics> {
+        self.metrics.clone()
+    }
+
+    pub fn quotas(&self) -> Arc<Quotas> {
+        self.quotas.clone()
+    }
+
+    pub fn debounce_pending(&self) -> usize {
+        self.debouncer.pending_count()
+    }
+
+    pub fn audit_log(&self) -> &AuditLog {
+        &self.audit
+    }
+
+    pub fn audit_log_handle(&self) -> Arc<AuditLog> {
+        self.audit.clone()
+    }
+
+    pub fn quarantine(&self) -> &Quarantine {
+        &self.quarantine
+    }
+
+    pub fn redactor(&self) -> &Redactor {
+        &self.redactor
+    }
+
+    // Feeds a quarantined payload back through ingest, skipping the signature
+    // check that held it. The entry stays quarantined unless ingest takes it.
+    pub async fn release_quarantined(&self, id: &str) -> Result<()> {
+        let entry = self
+            .quarantine
+            .get(id)
+            .ok_or_else(|| anyhow::anyhow!("No quarantined component '{}'", id))?;
+        let component: Component = serde_json::from_value(entry.payload.clone())?;
+        self.ingest(&entry.upstream, component, Source::Trusted)
+            .await
+            .map_err(|reason| anyhow::anyhow!("Quarantined component '{}' was rejected: {}", id, reason))?;
+        self.quarantine.release(&entry);
+        info!("🔓 Daemon: Released component from quarantine: {}", id);
+        Ok(())
+    }
+
+    // Drops the current registry connection; the connect loop dials again.
+    // The permit is kept if the connection loop isn't waiting right now.
+    pub fn request_reconnect(&self) {
+        self.reconnect.notify_one();
+    }
+
+    // Ids with at least one componentChanged subscriber
+    pub fn watched_components(&self) -> usize {
+        self.watchers.watched_count()
+    }
+
+    // Updates and the removal of one component, routed by id
+    pub fn watch_component(&self, id: &str) -> channels::IdReceiver {
+        self.watchers.subscribe(id)
+    }
+
+    pub fn subscribe_to_removals(&self) -> broadcast::Receiver<ComponentRemoval> {
+        self.removal_tx.subscribe()
+    }
+
+    // Every upsert and removal in one stream, so an upsert followed by a
+    // removal of the same id can't arrive the other way round
+    pub fn subscribe_to_changes(&self) -> broadcast::Receiver<ComponentChange> {
+        self.changes.subscribe()
+    }
+
+    pub fn remove_component(&self, id: &str, reason: RemovalReason) -> bool {
+        let pending = self.cancel_pending(id);
+        let removed = match self.components.remove(id) {
+            Some((_, component)) => {
+                self.component_bytes.sub(approx_size(&component));
+                true
+            }
+            None => false,
+        };
+        if removed || pending {
+            self.emit_removal(id, reason);
+        }
+        removed
+    }
+
+    // A pending debounced or scheduled update must not resurrect a removed
+    // component; true if there was one
+    fn cancel_pending(&self, id: &str) -> bool {
+        self.debouncer.take(id).is_some() | self.scheduler.cancel(id).is_some()
+    }
+
+    fn emit_removal(&self, id: &str, reason: RemovalReason) {
+        self.graph.remove(id);
+        let removal = ComponentRemoval {
+            id: id.to_string(),
+            reason,
+            removed_at: Utc::now(),
+        };
+        self.history.record(HistoryEvent::Removed(removal.clone()));
+        self.watchers.send(ComponentChange::Removed(removal.clone()));
+        let _ = self.changes.send(ComponentChange::Removed(removal.clone()));
+        let _ = self.removal_tx.send(removal);
+    }
+
+    pub fn get_component(&self, id: &str) -> Option<Component> {
+        self.components.get(id).map(|entry| Component::clone(entry.value()))
+    }
+
+    // The shared stored value, without copying the component
+    pub fn stored(&self, id: &str) -> Option<Arc<Component>> {
+        self.components.get(id).map(|entry| entry.value().clone())
+    }
+
+    // Like get_component, but if the id isn't stored yet waits up to `wait`
+    // (capped at MAX_COMPONENT_WAIT) for it to be published.
+    pub async fn wait_for_component(&self, id: &str, wait: Duration) -> Option<Component> {
+        if let Some(component) = self.get_component(id) {
+            return Some(component);
+        }
+        let wait = wait.min(MAX_COMPONENT_WAIT);
+        if wait.is_zero() {
+            return None;
+        }
+        // Watch before the second read so a publish in between isn't missed
+        let mut receiver = self.watch_component(id);
+        if let Some(component) = self.get_component(id) {
+            return Some(component);
+        }
+        tokio::time::timeout(wait, async {
+            loop {
+                match receiver.recv().await {
+                    Ok(ComponentChange::Updated(component)) => return Some(component),
+                    Ok(ComponentChange::Removed(_)) => {}
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        if let Some(component) = self.get_component(id) {
+                            return Some(component);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+
+    // A follower takes its state and seqs from the primary only, until it is
+    // promoted
+    fn writable(&self) -> Result<(), WriteError> {
+        match self.replication.is_following() {
+            true => Err(WriteError::ReadOnly),
+            false => Ok(()),
+        }
+    }
+
+    // Local write with optimistic concurrency: `expected_version` must match
+    // the stored version when given.
+    pub fn update_component(
+        &self,
+        id: &str,
+        mut data: serde_json::Value,
+        expected_version: Option<u64>,
+    ) -> Result<Component, WriteError> {
+        self.writable()?;
+        self.signatures.check_local().map_err(WriteError::Unsigned)?;
+        let r#type = self.components.get(id).ok_or(WriteError::NotFound)?.r#type;
+
+        // Checked before the entry is locked, since size limits can write
+        // blobs to disk
+        if self.validation_mode != ValidationMode::Off {
+            let mut report = self.validate(r#type, &data);
+            if !report.valid {
+                for violation in &mut report.violations {
+                    violation.message = self.redactor.redact_text(&violation.message).into_owned();
+                }
+                validation::log_violations(id, &report);
+                if self.validation_mode == ValidationMode::Reject {
+                    let summary = report
+                        .violations
+                        .iter()
+                        .map(|v| format!("{}: {}", v.rule, v.message))
+                        .collect::<Vec<_>>()
+                        .join("; ");
+                    return Err(WriteError::Invalid(summary));
+                }
+            }
+        }
+        self.enforce_size_limit("update", id, &mut data).map_err(WriteError::TooLarge)?;
+
+        let updated = {
+            let mut entry = self.components.get_mut(id).ok_or(WriteError::NotFound)?;
+            // Replaced by a component of another type meanwhile; the checks
+            // above no longer apply
+            if entry.r#type != r#type {
+                drop(entry);
+                return self.update_component(id, data, expected_version);
+            }
+            // Copy-on-write: snapshots still holding the old version keep it
+            let stored = Arc::make_mut(entry.value_mut());
+            if let Some(expected) = expected_version {
+                if stored.version != expected {
+                    return Err(WriteError::VersionConflict {
+                        expected,
+                        actual: stored.version,
+                    });
+                }
+            }
+
+            let old_size = approx_size(stored);
+            stored.data = data;
+            stored.version += 1;
+            stored.seq = self.history.next_seq();
+            self.component_bytes.replace(old_size, approx_size(stored));
+            stored.clone()
+        };
+
+        info!("✏️ Daemon: Updated component {} to version {}", updated.id, updated.version);
+        self.broadcast(updated.clone());
+        Ok(updated)
+    }
+
+    // Records who acknowledged a notification. The ack is stored on the
+    // component (a new version) unless `remove` takes it out of active state.
+    pub fn acknowledge(&self, id: &str, by: &str, remove: Option<bool>) -> Result<Acknowledgement, WriteError> {
+        self.writable()?;
+        let remove = remove.unwrap_or(self.acks.remove_by_default);
+        let acknowledgement = Acknowledgement {
+            id: id.to_string(),
+            by: by.to_string(),
+            acknowledged_at: Utc::now(),
+            removed: remove,
+        };
+        {
+            let mut entry = self.components.get_mut(id).ok_or(WriteError::NotFound)?;
+            if entry.r#type != ComponentType::Notification {
+                return Err(WriteError::Invalid("only notifications can be acknowledged".to_string()));
+            }
+            if !remove {
+                let stored = Arc::make_mut(entry.value_mut());
+                stored.acknowledged = Some(Box::new(acknowledgement.clone()));
+                stored.version += 1;
+                stored.seq = self.history.next_seq();
+                let updated = stored.clone();
+                drop(entry);
+                self.broadcast(updated);
+            }
+        }
+        if remove {
+            self.remove_component(id, RemovalReason::Acknowledged);
+        }
+
+        info!("✅ Daemon: Notification {} acknowledged by {}", id, by);
+        let _ = self.ack_tx.send(acknowledgement.clone());
+        if self.upstream.enabled() {
+            self.upstream.send_operation(
+                &self.acks.upstream_mutation,
+                serde_json::json!({
+                    "id": id,
+                    "by": by,
+                    "acknowledgedAt": acknowledgement.acknowledged_at.to_rfc3339(),
+                }),
+            );
+        }
+        Ok(acknowledgement)
+    }
+
+    pub fn subscribe_to_acknowledgements(&self) -> broadcast::Receiver<Acknowledgement> {
+        self.ack_tx.subscribe()
+    }
+
+    pub fn delete_component(&self, id: &str, expected_version: Option<u64>) -> Result<(), WriteError> {
+        self.writable()?;
+        let removed = self.components.remove_if(id, |_, stored| {
+            expected_version.is_none_or(|expected| stored.version == expected)
+        });
+
+        match removed {
+            Some((_, component)) => {
+                self.component_bytes.sub(approx_size(&component));
+                self.cancel_pending(id);
+                self.emit_removal(id, RemovalReason::Deleted);
+                info!("🗑️ Daemon: Deleted component {}", id);
+                Ok(())
+            }
+            None => match self.components.get(id) {
+                Some(stored) => Err(WriteError::VersionConflict {
+                    expected: expected_version.unwrap_or_default(),
+                    actual: stored.version,
+                }),
+                None => Err(WriteError::NotFound),
+            },
+        }
+    }
+
+    // Evicts whatever the retention rules select, skipping components that
+    // changed since the plan was made.
+    pub fn enforce_retention(&self) -> usize {
+        // A follower mirrors the primary's evictions instead of making its own
+        if self.replication.is_following() {
+            return 0;
+        }
+        let evictions = self.retention.plan(self.get_components(), Utc::now());
+        let mut evicted = 0;
+        for eviction in evictions {
+            let removed = self
+                .components
+                .remove_if(&eviction.id, |_, stored| stored.version == eviction.version);
+            if let Some((_, component)) = removed {
+                self.component_bytes.sub(approx_size(&component));
+                self.cancel_pending(&eviction.id);
+                self.emit_removal(&eviction.id, RemovalReason::Expired);
+                *self.metrics.retention_evictions.entry(eviction.rule).or_insert(0) += 1;
+                evicted += 1;
+            }
+        }
+        if evicted > 0 {
+            info!("🗓️ Daemon: Retention evicted {} components", evicted);
+            self.lifecycle.emit(InternalEventKind::RetentionRan, None, Some(evicted as u64));
+        }
+        evicted
+    }
+
+    pub fn purge(&self) -> usize {
+        let ids: Vec<String> = self.components.iter().map(|entry| entry.key().clone()).collect();
+        let purged = ids
+            .iter()
+            .filter(|id| self.remove_component(id, RemovalReason::Purged))
+            .count();
+        warn!("🧹 Daemon: Purged {} components", purged);
+        purged
+    }
+
+    pub fn validate(&self, r#type: ComponentType, data: &serde_json::Value) -> ValidationReport {
+        self.validator.validate(r#type, data)
+    }
+}
+
+// ========================
+// GRAPHQL SCHEMA
+// ========================
+
+#[derive(Clone, Debug, SimpleObject)]
+pub struct DaemonStats {
+    // Propagation latency from registry receive to renderer delivery,
+    // overall and per subscriber, against LATENCY_SLO_MS
+    pub latency: LatencyStats,
+    // The most recent GraphQL error from the registry and how it was handled
+    pub last_registry_error: Option<RegistryError>,
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    async fn components(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        #[graphql(desc = "Label selector, e.g. \"env=prod,team in (web,ops)\"")]
+        labels: Option<String>,
+    ) -> Result<Vec<Arc<Component>>, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        // Inside an incremental response every part reads the pinned view
+        let mut snapshot = match ctx.data_opt::<PinnedView>() {
+            Some(view) => view.components(),
+            None => daemon.get_components(),
+        };
+        if let Some(selector) = selector_arg(labels.as_deref())? {
+            snapshot.retain(|component| selector.matches(&component.labels));
+        }
+        let snapshot = incremental::window(ctx, snapshot);
+        daemon.metrics.read_alloc_bytes.observe(
+            &["graphql"],
+            (snapshot.capacity() * std::mem::size_of::<Arc<Component>>()) as f64,
+        );
+        Ok(snapshot)
+    }
+
+    // Pages ordered by id over a view pinned by the first request, so items
+    // don't shift while ingest continues. Later pages pass the returned
+    // `stateVersion`; once that view expires the traversal has to restart.
+    async fn components_page(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        #[graphql(default = 100)] first: usize,
+        after: Option<String>,
+        state_version: Option<String>,
+        #[graphql(desc = "Label selector, e.g. \"env=prod,team in (web,ops)\"")]
+        labels: Option<String>,
+    ) -> Result<ComponentPage, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        let selector = selector_arg(labels.as_deref())?;
+        let state_version = state_version.or_else(|| ctx.data_opt::<PinnedView>().map(|view| view.state_version.clone()));
+        daemon
+            .pages
+            .page(daemon, state_version.as_deref(), first, after.as_deref(), selector.as_ref())
+            .ok_or_else(|| {
+                graphql_error(
+                    ErrorCode::StateVersionExpired,
+                    "State version expired, restart from the first page",
+                    None,
+                )
+            })
+    }
+
+    // Declared via the x-api-version header or `apiVersion` in connection_init
+    async fn api_version(&self, ctx: &async_graphql::Context<'_>) -> ApiVersionInfo {
+        ApiVersionInfo::new(ctx.data_opt::<ApiVersion>().copied().unwrap_or_default())
+    }
+
+    // Null when the id isn't stored. With `waitForMs`, a miss waits that long
+    // (at most 30s) for the component to arrive, e.g. right after publishing
+    // it to the registry.
+    async fn component(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        id: String,
+        wait_for_ms: Option<u64>,
+    ) -> Result<Option<Component>, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        if let Some(view) = ctx.data_opt::<PinnedView>() {
+            return Ok(view.get(&id).map(|component| Component::clone(&component)));
+        }
+        let wait = Duration::from_millis(wait_for_ms.unwrap_or(0));
+        Ok(daemon.wait_for_component(&id, wait).await)
+    }
+
+    // Components waiting for their deliverAt, soonest first
+    async fn scheduled_components(&self, ctx: &async_graphql::Context<'_>) -> Result<Vec<Component>, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        Ok(daemon.scheduler().pending())
+    }
+
+    // Point-in-time view reconstructed from the history buffer, for debugging
+    // what renderers were showing at a given moment.
+    async fn components_at(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<Vec<Component>, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        daemon.history().state_at(timestamp).map_err(|earliest| {
+            graphql_error(
+                ErrorCode::HistoryUnavailable,
+                format!("History only reaches back to {}", earliest.to_rfc3339()),
+                Some(serde_json::json!({ "earliest": earliest.to_rfc3339() })),
+            )
+        })
+    }
+
+    // The root and its descendants in depth-first order, for resolving a
+    // composite layout in one request. Null when the root isn't stored.
+    async fn component_tree(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        root_id: String,
+        #[graphql(default = 8)] max_depth: u32,
+    ) -> Result<Option<Vec<ComponentTreeNode>>, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        Ok(graph::tree(daemon, &root_id, max_depth))
+    }
+}
+
+pub struct Mutation;
+
+#[Object]
+impl Mutation {
+    // Dry-run of the ingest validation pipeline; nothing is stored or broadcast.
+    async fn validate_component(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        r#type: ComponentType,
+        data: serde_json::Value,
+    ) -> Result<ValidationReport, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        Ok(daemon.validate(r#type, &data))
+    }
+
+    async fn update_component(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        id: String,
+        data: serde_json::Value,
+        expected_version: Option<u64>,
+    ) -> Result<Component, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        daemon
+            .update_component(&id, data, expected_version)
+            .map_err(write_error)
+    }
+
+    // Loads components through the same validation and broadcast pipeline as
+    // registry updates; rejected ones are reported per id.
+    async fn import_components(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        components: Vec<import::ComponentInput>,
+    ) -> Result<import::ImportResult, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        Ok(import::import(daemon, "import", components).await)
+    }
+
+    // Drops a scheduled component before delivery; false if none was pending
+    async fn cancel_scheduled_component(&self, ctx: &async_graphql::Context<'_>, id: String) -> Result<bool, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        Ok(daemon.scheduler().cancel(&id).is_some())
+    }
+
+    // Validates `values` against the form's fields, stores the submission and,
+    // in bidirectional mode, waits for the registry to accept it. Validation
+    // and registry failures come back in the result rather than as errors.
+    async fn submit_form(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        id: String,
+        values: serde_json::Value,
+    ) -> Result<SubmitFormResult, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        forms::submit(daemon, &id, values).await.map_err(write_error)
+    }
+
+    // Reports a renderer-side action, e.g. a card button click
+    async fn interact(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        component_id: String,
+        action: String,
+        payload: Option<serde_json::Value>,
+    ) -> Result<Interaction, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        interactions::record(daemon, &component_id, &action, payload).map_err(write_error)
+    }
+
+    // `remove` defaults to ACK_REMOVES. In bidirectional mode the ack is also
+    // forwarded to the registry.
+    async fn acknowledge_notification(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        id: String,
+        by: String,
+        remove: Option<bool>,
+    ) -> Result<Acknowledgement, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        daemon.acknowledge(&id, &by, remove).map_err(write_error)
+    }
+
+    async fn delete_component(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        id: String,
+        expected_version: Option<u64>,
+    ) -> Result<bool, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        daemon
+            .delete_component(&id, expected_version)
+            .map(|_| true)
+            .map_err(write_error)
+    }
+}
+
+fn selector_arg(raw: Option<&str>) -> Result<Option<LabelSelector>, Error> {
+    raw.map(LabelSelector::parse)
+        .transpose()
+        .map_err(|e| graphql_error(ErrorCode::InvalidSelector, e.to_string(), None))
+}
+
+fn write_error(e: WriteError) -> Error {
+    let details = match &e {
+        WriteError::VersionConflict { expected, actual } => Some(serde_json::json!({
+            "expectedVersion": expected,
+            "currentVersion": actual,
+        })),
+        WriteError::TooLarge(too_large) => Some(serde_json::json!({
+            "bytes": too_large.bytes,
+            "limit": too_large.limit,
+        })),
+        _ => None,
+    };
+    graphql_error(e.code(), e.to_string(), details)
+}
+
+// Queued entries go out by priority, so a renderer has everything up to just
+// below the oldest one still waiting
+fn delivered_through(queue: &DeliveryQueue, seen: u64) -> u64 {
+    queue.lowest_seq().map_or(seen, |lowest| lowest.saturating_sub(1))
+}
+
+fn resume_unavailable(after_seq: u64, gap: &ResumeUnavailable) -> Error {
+    graphql_error(
+        ErrorCode::ResumeUnavailable,
+        format!("Cannot resume after seq {}: {}, refetch components", after_seq, gap),
+        Some(serde_json::json!({
+            "oldestSeq": gap.oldest_seq,
+            "latestSeq": gap.latest_seq,
+            "removedId": gap.removed,
+        })),
+    )
+}
+
+pub struct Subscription;
+
+#[Subscription]
+impl Subscription {
+    
+    async fn renderer_update(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        min_priority: Option<i32>,
+        after_seq: Option<u64>,
+        #[graphql(desc = "Only deliver these component types; each type has its own channel")]
+        types: Option<Vec<ComponentType>>,
+        #[graphql(desc = "Label selector, e.g. \"env=prod,team in (web,ops)\"")]
+        labels: Option<String>,
+    ) -> Result<impl futures::Stream<Item = Result<Component, Error>>, Error> {
+        info!("📡 Daemon: Renderer subscribed to updates");
+        
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        
+        // Subscribe before reading history so nothing falls between the two
+        let selector = selector_arg(labels.as_deref())?;
+        let mut receiver = daemon.subscribe_to_types(types.as_deref());
+        let wanted = move |component: &Component| {
+            min_priority.is_none_or(|min| component.priority.unwrap_or(0) >= min)
+                && types.as_ref().is_none_or(|types| types.contains(&component.r#type))
+                && selector.as_ref().is_none_or(|selector| selector.matches(&component.labels))
+        };
+
+        // Renderers that reconnect without a cursor pick up where their last
+        // connection left off, if the history still covers it
+        let subscriber = ctx.data_opt::<SubscriberId>().map(|id| id.0.clone());
+        let registry = daemon.subscribers();
+        let latency = daemon.latency.clone();
+        let metrics = daemon.metrics();
+        let delivery_capacity = daemon.delivery_capacity;
+        let tracked = match (&subscriber, after_seq) {
+            (Some(id), None) => registry.resume_cursor(id),
+            _ => None,
+        };
+        let tracked_replay = tracked.and_then(|after_seq| match daemon.history().replay_after(after_seq) {
+            Ok(replay) => Some((after_seq, replay)),
+            Err(gap) => {
+                warn!(
+                    "⏪ Daemon: Renderer {} can't resume after seq {} ({}), sending live updates only",
+                    subscriber.as_deref().unwrap_or_default(), after_seq, gap
+                );
+                None
+            }
+        });
+
+        // Everything up to `start` is either replayed below or predates the
+        // subscription
+        let mut start = daemon.history().latest_seq();
+        let replay = match after_seq {
+            None => match tracked_replay {
+                Some((after_seq, replay)) => {
+                    info!(
+                        "⏪ Daemon: Renderer {} reconnected, replaying {} after seq {}",
+                        subscriber.as_deref().unwrap_or_default(), replay.len(), after_seq
+                    );
+                    start = after_seq;
+                    replay
+                }
+                None => Vec::new(),
+            },
+            Some(after_seq) => {
+                start = after_seq;
+                daemon.history().replay_after(after_seq).map_err(|gap| resume_unavailable(after_seq, &gap))?
+            }
+        };
+        if let Some(after_seq) = after_seq {
+            info!("⏪ Daemon: Renderer resuming after seq {}, replaying {}", after_seq, replay.len());
+        }
+        let history = daemon.history.clone();
+        
+        let stream = stream! {
+            // Live events that were already replayed are skipped once
+            let mut replayed: std::collections::HashSet<u64> =
+                replay.iter().map(|component| component.seq).collect();
+            // Highest seq this subscription has received; the resume cursor
+            // only passes it once nothing older is still queued
+            let mut seen = replay.last().map_or(start, |component| component.seq);
+            for component in replay {
+                if wanted(&component) {
+                    if let Some(id) = &subscriber {
+                        registry.delivered(id, component.seq);
+                    }
+                    yield Ok(component);
+                }
+            }
+
+            let mut queue = DeliveryQueue::new(delivery_capacity, metrics);
+            // Seqs sent above the cursor, so a refill after lagging doesn't
+            // send them again
+            let mut sent = std::collections::BTreeSet::new();
+            let mut lagged = false;
+            loop {
+                if queue.is_empty() {
+                    match receiver.recv().await {
+                        Ok(component) => {
+                            seen = seen.max(component.seq);
+                            if !replayed.remove(&component.seq) && !sent.contains(&component.seq) && wanted(&component) {
+                                queue.push(component);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!("🐢 Daemon: Renderer lagged, skipped {} updates", skipped);
+                            lagged = true;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+
+                // Drain whatever piled up while the renderer was busy so the
+                // highest-priority component goes out next.
+                loop {
+                    match receiver.try_recv() {
+                        Ok(component) => {
+                            seen = seen.max(component.seq);
+                            if !replayed.remove(&component.seq) && !sent.contains(&component.seq) && wanted(&component) {
+                                queue.push(component);
+                            }
+                        }
+                        Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
+                            warn!("🐢 Daemon: Renderer lagged, skipped {} updates", skipped);
+                            lagged = true;
+                        }
+                        Err(_) => break,
+                    }
+                }
+
+                // Refill what the channel dropped from history; if it no
+                // longer covers the gap the renderer has to refetch
+                if std::mem::take(&mut lagged) {
+                    let cursor = delivered_through(&queue, seen);
+                    match history.replay_after(cursor) {
+                        Ok(missed) => {
+                            info!("⏪ Daemon: Refilling {} updates after seq {} for a lagging renderer", missed.len(), cursor);
+                            for component in missed {
+                                seen = seen.max(component.seq);
+                                if !sent.contains(&component.seq) && wanted(&component) {
+                                    queue.push(component);
+                                }
+                            }
+                        }
+                        Err(gap) => {
+                            warn!("🐢 Daemon: Renderer lagged past the history ({}), ending its subscription", gap);
+                            yield Err(resume_unavailable(cursor, &gap));
+                            break;
+                        }
+                    }
+                }
+
+                if let Some(component) = queue.pop() {
+                    sent.insert(component.seq);
+                    let cursor = delivered_through(&queue, seen);
+                    sent.retain(|seq| *seq > cursor);
+                    if let Some(id) = &subscriber {
+                        registry.delivered(id, cursor);
+                    }
+                    latency.delivered(subscriber.as_deref(), &component.stamps);
+                    yield Ok(component);
+                }
+            }
+        };
+        
+        Ok(stream)
+    }
+
+    // Like rendererUpdate, but each update only carries the top-level data
+    // fields that changed since this subscription last sent the id. Needs the
+    // deltaUpdates feature flag.
+    async fn renderer_delta(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        types: Option<Vec<ComponentType>>,
+        #[graphql(desc = "Label selector, e.g. \"env=prod,team in (web,ops)\"")]
+        labels: Option<String>,
+    ) -> Result<impl futures::Stream<Item = ComponentDelta>, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+        if !daemon.flags().enabled(Flag::DeltaUpdates) {
+            return Err(graphql_error(
+                ErrorCode::FeatureDisabled,
+                "Delta updates are disabled on this daemon",
+                Some(serde_json::json!({ "flag": Flag::DeltaUpdates.name() })),
+            ));
+        }
+
+        let selector = selector_arg(labels.as_deref())?;
+        let mut updates = daemon.subscribe_to_types(types.as_deref());
+        let mut removals = daemon.subscribe_to_removals();
+        let subscriber = ctx.data_opt::<SubscriberId>().map(|id| id.0.clone());
+        let latency = daemon.latency.clone();
+        let wanted = move |component: &Component| {
+            types.as_ref().is_none_or(|types| types.contains(&component.r#type))
+                && selector.as_ref().is_none_or(|selector| selector.matches(&component.labels))
+        };
+
+        let stream = stream! {
+            let mut tracker = DeltaTracker::default();
+            loop {
+                tokio::select! {
+                    update = updates.recv() => match update {
+                        Ok(component) if wanted(&component) => {
+                            if let Some(delta) = tracker.delta(&component) {
+                                latency.delivered(subscriber.as_deref(), &component.stamps);
+                                yield delta;
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!("🐢 Daemon: Delta subscriber lagged, skipped {} updates", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    removal = removals.recv() => match removal {
+                        Ok(removal) => tracker.forget(&removal.id),
+                        Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                }
+            }
+        };
+
+        Ok(stream)
+    }
+
+    // Changes to a single component for detail views. Starts with the current
+    // state unless `initial` is false; a removal is sent but doesn't end the
+    // stream, since the id may be published again.
+    async fn component_changed(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        id: String,
+        #[graphql(default = true)] initial: bool,
+    ) -> Result<impl futures::Stream<Item = ComponentChange>, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?
+            .clone();
+
+        // Watch before reading the current state so nothing falls in between
+        let mut receiver = daemon.watch_component(&id);
+        let current = daemon.get_component(&id);
+
+        let stream = stream! {
+            let mut last_seq = current.as_ref().map(|component| component.seq).unwrap_or(0);
+            if let Some(component) = current.filter(|_| initial) {
+                yield ComponentChange::Updated(component);
+            }
+            loop {
+                match receiver.recv().await {
+                    Ok(ComponentChange::Updated(component)) => {
+                        if component.seq > last_seq {
+                            last_seq = component.seq;
+                            yield ComponentChange::Updated(component);
+                        }
+                    }
+                    Ok(removal) => yield removal,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        // Only the latest state matters to a detail view
+                        warn!("🐢 Daemon: Watcher of {} lagged, skipped {} changes", id, skipped);
+                        if let Some(component) = daemon.get_component(&id) {
+                            last_seq = component.seq;
+                            yield ComponentChange::Updated(component);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        };
+
+        Ok(stream)
+    }
+
+    async fn component_acknowledged(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> Result<impl futures::Stream<Item = Acknowledgement>, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+
+        let mut receiver = daemon.subscribe_to_acknowledgements();
+
+        let stream = stream! {
+            loop {
+                match receiver.recv().await {
+                    Ok(acknowledgement) => yield acknowledgement,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("🐢 Daemon: Acknowledgement subscriber lagged, skipped {} events", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        };
+
+        Ok(stream)
+    }
+
+    async fn interaction_events(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        component_id: Option<String>,
+    ) -> Result<impl futures::Stream<Item = Interaction>, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+
+        let mut receiver = daemon.interactions().subscribe();
+
+        let stream = stream! {
+            loop {
+                match receiver.recv().await {
+                    Ok(interaction) => {
+                        if component_id.as_ref().is_none_or(|id| &interaction.component_id == id) {
+                            yield interaction;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("🐢 Daemon: Interaction subscriber lagged, skipped {} events", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        };
+
+        Ok(stream)
+    }
+
+    async fn component_removed(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> Result<impl futures::Stream<Item = ComponentRemoval>, Error> {
+        let daemon = ctx.data::<ComponentDaemon>()
+            .map_err(|_| missing_daemon())?;
+
+        let mut receiver = daemon.subscribe_to_removals();
+
+        let stream = stream! {
+            loop {
+                match receiver.recv().await {
+                    Ok(removal) => yield removal,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("🐢 Daemon: Removal subscriber lagged, skipped {} events", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        };
+
+        Ok(stream)
+    }
+}
+
+
+
+// ========================
+// SERVER
+// ========================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphqlIde {
+    GraphiQL,
+    // Legacy: the Playground bundle is unmaintained and only speaks the old
+    // subscriptions-transport-ws protocol.
+    Playground,
+    Disabled,
+}
+
+impl GraphqlIde {
+    pub fn from_env() -> Self {
+        match std::env::var("GRAPHQL_IDE").as_deref() {
+            Ok("playground") => GraphqlIde::Playground,
+            Ok("none") | Ok("off") => GraphqlIde::Disabled,
+            _ => GraphqlIde::GraphiQL,
+        }
+    }
+
+    pub fn path(&self) -> &'static str {
+        match self {
+            GraphqlIde::Playground => "playground",
+            _ => "graphiql",
+        }
+    }
+
+    pub fn page(&self) -> Option<String> {
+        match self {
+            GraphqlIde::GraphiQL => Some(
+                async_graphql::http::GraphiQLSource::build()
+                    .endpoint("/graphql")
+                    .subscription_endpoint("/graphql")
+                    .title("Component Daemon")
+                    .finish(),
+            ),
+            GraphqlIde::Playground => Some(async_graphql::http::playground_source(
+                async_graphql::http::GraphQLPlaygroundConfig::new("/graphql")
+                    .subscription_endpoint("/graphql"),
+            )),
+            GraphqlIde::Disabled => None,
+        }
+    }
+}
+
+// `x-api-version` request header; absent means the current version
+pub(crate) fn api_version() -> impl Filter<Extract = (ApiVersion,), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("x-api-version")
+        .map(|raw: Option<String>| raw.as_deref().and_then(ApiVersion::parse).unwrap_or_default())
+}
+
+// The actor is whoever `auth` authenticated; `x-actor` rides along only as
+// an unverified claim
+pub(crate) fn request_origin(auth: &listeners::BearerAuth) -> impl Filter<Extract = (RequestOrigin,), Error = warp::Rejection> + Clone {
+    auth.principal()
+        .and(warp::header::optional::<String>("x-actor"))
+        .and(warp::addr::remote())
+        .map(|principal: Option<auth::Principal>, claimed, source| {
+            RequestOrigin::new(principal.map(|principal| principal.subject), claimed, source)
+        })
+}
+
+pub async fn start_daemon(port: u16, seed: Option<std::path::PathBuf>) -> Result<()> {
+    serve_until(port, seed, std::future::pending()).await
+}
+
+// Runs the daemon until `shutdown` resolves, then lets in-flight requests finish
+pub async fn serve_until(port: u16, seed: Option<std::path::PathBuf>, shutdown: impl std::future::Future<Output = ()> + Send + 'static) -> Result<()> {
+    // Initialize tracing
+    if let Err(e) = logging::init() {
+        tracing_subscriber::fmt::init();
+        error!("❌ Daemon: Failed to set up file logging, using stdout only: {:#}", e);
+    }
+
+    let daemon = ComponentDaemon::new()?;
+    // Seeded before connecting, so the registry's versions win
+    if let Some(seed) = &seed {
+        import::seed(&daemon, seed).await?;
+    }
+    daemon.start().await?;
+
+    // Create GraphQL schema
+    let schema = Schema::build(Query, Mutation, Subscription)
+        .data(daemon.clone())
+        .extension(RequestLog::new(daemon.metrics()))
+        .extension(MutationAudit::new(daemon.audit_log_handle(), daemon.redactor.clone()))
+        .extension(ApiVersioning)
+        .extension(Loaders::new(daemon.clone()))
+        .extension(QuotaEnforcement::new(daemon.quotas()))
+        .finish();
+
+    // Health check endpoint
+    let daemon_for_health = daemon.clone();
+    let health = warp::path::end()
+        .and_then(move || {
+            let daemon_for_health = daemon_for_health.clone();
+            async move {
+                let components_count = daemon_for_health.get_all_components_count();
+                Ok::<_, Infallible>(warp::reply::json(&serde_json::json!({
+                    "message": "Component Daemon - Real Connection",
+                    "components": components_count,
+                    "ingestFailures": daemon_for_health.ingest_failures().total(),
+                    "debouncePending": daemon_for_health.debounce_pending(),
+                    "memoryBytes": daemon_for_health.memory_used(),
+                    "subscribers": daemon_for_health.metrics().subscribers_active.load(std::sync::atomic::Ordering::Relaxed),
+                    "watchedComponents": daemon_for_health.watched_components(),
+                    "scheduledPending": daemon_for_health.scheduler().pending_count(),
+                    "ready": daemon_for_health.is_ready(),
+                    "degraded": daemon_for_health.is_degraded(),
+                    "blobStore": daemon_for_health.blobs().status(),
+                    "auditLog": daemon_for_health.audit_log().status(),
+                    "subscriberRegistry": daemon_for_health.subscribers().status(),
+                    "status": "Connected to registry"
+                })))
+            }
+        });
+
+    // Readiness probe: 503 until the registry's current state has been loaded.
+    // A degraded daemon stays ready unless the failing backend's policy is
+    // `unready`.
+    let daemon_for_ready = daemon.clone();
+    let ready = warp::path("ready")
+        .and(warp::path::end())
+        .and(warp::get())
+        .map(move || {
+            let ready = daemon_for_ready.is_ready();
+            let status = if ready {
+                warp::http::StatusCode::OK
+            } else {
+                warp::http::StatusCode::SERVICE_UNAVAILABLE
+            };
+            let degraded = daemon_for_ready.is_degraded();
+            warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({ "ready": ready, "degraded": degraded })),
+                status,
+            )
+        });
+    let health = health.or(ready);
+
+    // Prometheus scrape endpoint
+    let daemon_for_metrics = daemon.clone();
+    let metrics = warp::path("metrics")
+        .and(warp::path::end())
+        .and(warp::get())
+        .map(move || {
+            warp::reply::with_header(
+                daemon_for_metrics.render_metrics(),
+                "content-type",
+                "text/plain; version=0.0.4",
+            )
+        });
+
+    // GraphQL IDE (for browser testing), behind the playground flag
+    let ide = GraphqlIde::from_env();
+    let ide_page = ide.page();
+    let daemon_for_ide = daemon.clone();
+    let graphql_ide = warp::path(ide.path())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and_then(move || {
+            let ide_page = ide_page.clone().filter(|_| daemon_for_ide.flags().enabled(Flag::Playground));
+            async move {
+                match ide_page {
+                    Some(page) => Ok(warp::reply::html(page)),
+                    None => Err(warp::reject::not_found()),
+                }
+            }
+        });
+
+    // GraphQL endpoint for queries and mutations. GET queries carry the state
+    // ETag and are answered with 304 while nothing has changed.
+    // Created up front: the GraphQL routes charge usage to the public
+    // listener's principals
+    let listeners = Listeners::from_env(port)?;
+
+    let daemon_for_graphql = daemon.clone();
+    let graphql_post = warp::path("graphql")
+        .and(warp::method())
+        .and(warp::header::optional::<String>("if-none-match"))
+        .and(async_graphql_warp::graphql(schema.clone()))
+        .and(request_origin(&listeners.public_auth))
+        .and(api_version())
+        .and(listeners.public_auth.principal())
+        .and_then(
+            move |method: warp::http::Method, if_none_match: Option<String>, (schema, request): (
+                async_graphql::Schema<Query, Mutation, Subscription>,
+                async_graphql::Request,
+            ), origin: RequestOrigin, version: ApiVersion, principal: Option<auth::Principal>| {
+                let daemon = daemon_for_graphql.clone();
+                async move {
+                    let etag = (method == warp::http::Method::GET).then(|| daemon.state_etag());
+                    if let Some(etag) = &etag {
+                        if rest::etag_matches(if_none_match.as_deref(), etag) {
+                            return Ok::<_, Infallible>(rest::not_modified(etag));
+                        }
+                    }
+                    let request = request
+                        .data(origin)
+                        .data(version)
+                        .data(quotas::ApiKey::from_principal(principal.as_ref()));
+                    let response = schema.execute(request).await;
+                    let cacheable = response.is_ok();
+                    let reply = warp::Reply::into_response(async_graphql_warp::GraphQLResponse::from(response));
+                    Ok(match etag.filter(|_| cacheable) {
+                        Some(etag) => warp::Reply::into_response(warp::reply::with_header(reply, "etag", etag)),
+                        None => reply,
+                    })
+                }
+            },
+        );
+
+    let graphql_ws = ws::graphql_subscription(
+        schema.clone(),
+        daemon.metrics(),
+        ws::KeepAliveConfig::from_env(),
+        ws::SubprotocolConfig::from_env(),
+        daemon.subscribers(),
+        listeners.public_auth.clone(),
+    );
+
+    let compression = compression::CompressionConfig::from_env();
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_headers(vec!["content-type", "x-actor", "x-api-version", "authorization"])
+        .allow_headers(vec!["if-match", "if-none-match"])
+        .expose_headers(vec!["etag", "content-encoding"])
+        .allow_methods(vec!["GET", "POST", "PUT", "DELETE"]);
+
+    // Ops endpoints, kept off the renderer network when ADMIN_BIND is set
+    let admin_routes = listeners::admin_scope()
+        .and(listeners.admin_auth.filter())
+        .and(
+            metrics
+                .or(admin::routes(daemon.clone(), &listeners.admin_auth))
+                .or(admin_graphql::routes(admin_graphql::schema(&daemon), &listeners.admin_auth))
+                .or(replication::routes(daemon.clone())),
+        );
+
+    // Health stays unauthenticated so probes work on either listener
+    let public_routes = listeners::public_scope().and(listeners.public_auth.filter()).and(
+        rest::routes(daemon.clone(), &listeners.public_auth)
+            .or(blobs::routes(daemon.blobs()))
+            .or(openapi::routes())
+            .or(ui::routes())
+            .or(preview::routes(daemon.clone()))
+            .or(graphql_ide)
+            .or(incremental::routes(schema.clone(), daemon.clone(), listeners.public_auth.clone()))
+            .or(graphql_post.or(graphql_ws)),
+    );
+
+    let public = listeners.public;
+    info!("🚀 Component Daemon running on http://{}", public);
+    info!("📡 GraphQL: http://{}/graphql", public);
+    info!("📘 OpenAPI: http://{}/openapi.json", public);
+    info!("👀 Live view: http://{}/ui", public);
+    if ide != GraphqlIde::Disabled {
+        info!("🎮 {:?}: http://{}/{}", ide, public, ide.path());
+    }
+
+    let shutdown = futures::FutureExt::shared(Box::pin(shutdown));
+    match listeners.admin {
+        Some(admin) => {
+            info!("🛠️ Admin API: http://{}/admin", admin);
+            let admin_routes = compression::wrap(compression.clone(), health.clone().or(admin_routes))
+                .recover(listeners::recover);
+            let public_routes = compression::wrap(compression, health.or(public_routes))
+                .recover(listeners::recover)
+                .with(cors);
+            let (_, admin_server) = warp::serve(admin_routes)
+                .try_bind_with_graceful_shutdown(admin, shutdown.clone())
+                .with_context(|| format!("Failed to bind admin listener {}", admin))?;
+            let (_, public_server) = warp::serve(public_routes)
+                .try_bind_with_graceful_shutdown(public, shutdown)
+                .with_context(|| format!("Failed to bind {}", public))?;
+            tokio::join!(admin_server, public_server);
+        }
+        None => {
+            info!("🛠️ Admin API: http://{}/admin", public);
+            let routes = compression::wrap(compression, health.or(admin_routes).or(public_routes))
+                .recover(listeners::recover)
+                .with(cors);
+            let (_, server) = warp::serve(routes)
+                .try_bind_with_graceful_shutdown(public, shutdown)
+                .with_context(|| format!("Failed to bind {}", public))?;
+            server.await;
+        }
+    }
+
+    Ok(())
+}
+
+// ========================
+// MAIN
+// ========================
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("bench-ingest") => bench::run(&args[1..]).await,
+        Some("soak") => soak::run(&args[1..]).await,
+        #[cfg(windows)]
+        Some("install-service") => service::install(&args[1..]),
+        #[cfg(windows)]
+        Some("uninstall-service") => service::uninstall(),
+        #[cfg(windows)]
+        Some("run-service") => tokio::task::block_in_place(service::run),
+        #[cfg(not(windows))]
+        Some(command @ ("install-service" | "uninstall-service" | "run-service")) => {
+            Err(anyhow::anyhow!("{} is only available on Windows", command))
+        }
+        _ => start_daemon(3001, import::seed_arg(&args)?).await,
+    }
 }
\ No newline at end of file
@@ -0,0 +1,113 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tracing::info;
+
+use crate::config::env_parse;
+use crate::Component;
+
+// ========================
+// MEMORY ACCOUNTING
+// ========================
+
+// Rough heap + inline footprint of a component; good enough to compare
+// against a budget, not an exact allocator measurement.
+pub fn approx_size(component: &Component) -> u64 {
+    (std::mem::size_of::<Component>() + component.id.len() + json_size(&component.data)) as u64
+}
+
+fn json_size(value: &serde_json::Value) -> usize {
+    use serde_json::Value;
+
+    const NODE: usize = std::mem::size_of::<Value>();
+    match value {
+        Value::Null | Value::Bool(_) | Value::Number(_) => NODE,
+        Value::String(s) => NODE + s.len(),
+        Value::Array(items) => NODE + items.iter().map(json_size).sum::<usize>(),
+        Value::Object(map) => {
+            NODE + map
+                .iter()
+                .map(|(k, v)| std::mem::size_of::<String>() + k.len() + json_size(v))
+                .sum::<usize>()
+        }
+    }
+}
+
+// Byte counter kept in step with a collection's inserts and removals
+#[derive(Default)]
+pub struct ByteGauge(AtomicU64);
+
+impl ByteGauge {
+    pub fn add(&self, bytes: u64) {
+        self.0.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn sub(&self, bytes: u64) {
+        // Saturate rather than wrap if estimates ever drift
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+                Some(current.saturating_sub(bytes))
+            });
+    }
+
+    pub fn replace(&self, old: u64, new: u64) {
+        self.add(new);
+        self.sub(old);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+pub struct MemoryBudget {
+    pub limit_bytes: Option<u64>,
+    // Components at or above this priority are still accepted over budget
+    pub priority_floor: i32,
+}
+
+impl MemoryBudget {
+    // MEMORY_BUDGET_MB (unset = unlimited) caps components plus history.
+    // MEMORY_BUDGET_PRIORITY_FLOOR decides which new components are still
+    // admitted while over budget.
+    pub fn from_env() -> Self {
+        let limit_bytes = std::env::var("MEMORY_BUDGET_MB")
+            .ok()
+            .and_then(|raw| raw.trim().parse::<u64>().ok())
+            .map(|mb| mb * 1024 * 1024);
+        let budget = Self {
+            limit_bytes,
+            priority_floor: env_parse("MEMORY_BUDGET_PRIORITY_FLOOR", 1),
+        };
+        if let Some(limit) = budget.limit_bytes {
+            info!(
+                "🧮 Daemon: Memory budget {} bytes, priority >= {} admitted when full",
+                limit, budget.priority_floor
+            );
+        }
+        budget
+    }
+
+    pub fn exceeded(&self, used: u64) -> bool {
+        self.limit_bytes.is_some_and(|limit| used > limit)
+    }
+
+    pub fn render(&self, out: &mut String, components: u64, history: u64) {
+        out.push_str("# HELP daemon_memory_bytes Approximate memory held by stored components and history.\n");
+        out.push_str("# TYPE daemon_memory_bytes gauge\n");
+        let _ = writeln!(out, "daemon_memory_bytes{{area=\"components\"}} {}", components);
+        let _ = writeln!(out, "daemon_memory_bytes{{area=\"history\"}} {}", history);
+        if let Some(limit) = self.limit_bytes {
+            out.push_str("# HELP daemon_memory_budget_bytes Configured memory budget.\n");
+            out.push_str("# TYPE daemon_memory_budget_bytes gauge\n");
+            let _ = writeln!(out, "daemon_memory_budget_bytes {}", limit);
+        }
+    }
+
+    // Evictions stop at 90% of the budget so one new component doesn't
+    // trigger another round right away.
+    pub fn eviction_target(&self) -> Option<u64> {
+        self.limit_bytes.map(|limit| limit / 10 * 9)
+    }
+}
@@ -0,0 +1,611 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+
+use crate::config::env_parse;
+use crate::sinks::SinkStats;
+
+// ========================
+// REGISTRY
+// ========================
+
+pub struct Metrics {
+    pub ingest_failures: IngestFailures,
+    pub operations: OperationMetrics,
+    pub debounce_suppressed: AtomicU64,
+    // Registry updates identical to the stored component, with dedup on
+    pub dedup_suppressed: AtomicU64,
+    pub conflict_rejected: AtomicU64,
+    // Updates older than the stored state by the ordering key
+    pub reordered_dropped: AtomicU64,
+    // rendererUpdate backlog: updates folded into a newer queued version of
+    // the same id, and updates dropped with the queue full
+    pub delivery_coalesced: AtomicU64,
+    pub delivery_dropped: AtomicU64,
+    pub subscribers_active: AtomicU64,
+    pub subscribers_reaped: AtomicU64,
+    // Keyed by the retention rule that caused the eviction
+    pub retention_evictions: DashMap<String, u64>,
+    pub memory_evicted: AtomicU64,
+    pub memory_refused: AtomicU64,
+    // Renderers currently connected in push mode, and messages pushed to them
+    pub push_connected: AtomicU64,
+    pub push_sent: AtomicU64,
+    // Lifecycle transitions by kind, and whether the registry link is up
+    pub internal_events: DashMap<&'static str, u64>,
+    pub upstream_connected: AtomicU64,
+    // Components over COMPONENT_MAX_BYTES by what was done with them
+    pub oversized: DashMap<&'static str, u64>,
+    // Referenced assets by outcome: fetched, cached or failed
+    pub attachments: DashMap<&'static str, u64>,
+    // Outbound sink workers by sink name
+    pub sinks: DashMap<String, Arc<SinkStats>>,
+    // Bytes allocated to answer a full component listing, per API
+    pub read_alloc_bytes: HistogramVec,
+}
+
+impl Metrics {
+    pub fn new(failure_sample_size: usize) -> Self {
+        Self {
+            ingest_failures: IngestFailures::new(failure_sample_size),
+            operations: OperationMetrics::from_env(),
+            debounce_suppressed: AtomicU64::new(0),
+            dedup_suppressed: AtomicU64::new(0),
+            conflict_rejected: AtomicU64::new(0),
+            reordered_dropped: AtomicU64::new(0),
+            delivery_coalesced: AtomicU64::new(0),
+            delivery_dropped: AtomicU64::new(0),
+            subscribers_active: AtomicU64::new(0),
+            subscribers_reaped: AtomicU64::new(0),
+            retention_evictions: DashMap::new(),
+            memory_evicted: AtomicU64::new(0),
+            memory_refused: AtomicU64::new(0),
+            push_connected: AtomicU64::new(0),
+            push_sent: AtomicU64::new(0),
+            internal_events: DashMap::new(),
+            upstream_connected: AtomicU64::new(0),
+            oversized: DashMap::new(),
+            attachments: DashMap::new(),
+            sinks: DashMap::new(),
+            read_alloc_bytes: HistogramVec::new(READ_BUCKETS, &["api"]),
+        }
+    }
+
+    // Prometheus text exposition format
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP daemon_ingest_failures_total Upstream messages that failed to deserialize or validate.\n");
+        out.push_str("# TYPE daemon_ingest_failures_total counter\n");
+        for failure in self.ingest_failures.counts() {
+            let kind = serde_json::to_value(failure.kind).unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "daemon_ingest_failures_total{{upstream=\"{}\",kind=\"{}\"}} {}",
+                escape_label(&failure.upstream),
+                kind.as_str().unwrap_or("OTHER"),
+                failure.count
+            );
+        }
+
+        out.push_str("# HELP daemon_debounce_suppressed_total Intermediate updates coalesced away by the per-id debounce window.\n");
+        out.push_str("# TYPE daemon_debounce_suppressed_total counter\n");
+        let _ = writeln!(
+            out,
+            "daemon_debounce_suppressed_total {}",
+            self.debounce_suppressed.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP daemon_dedup_suppressed_total Updates dropped because they matched the stored component.\n");
+        out.push_str("# TYPE daemon_dedup_suppressed_total counter\n");
+        let _ = writeln!(
+            out,
+            "daemon_dedup_suppressed_total {}",
+            self.dedup_suppressed.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP daemon_conflict_rejected_total Incoming updates rejected by the conflict resolution policy.\n");
+        out.push_str("# TYPE daemon_conflict_rejected_total counter\n");
+        let _ = writeln!(
+            out,
+            "daemon_conflict_rejected_total {}",
+            self.conflict_rejected.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP daemon_reordered_dropped_total Out-of-order updates dropped because the stored state is newer.\n");
+        out.push_str("# TYPE daemon_reordered_dropped_total counter\n");
+        let _ = writeln!(
+            out,
+            "daemon_reordered_dropped_total {}",
+            self.reordered_dropped.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP daemon_delivery_coalesced_total Queued renderer updates replaced by a newer version of the same component.\n");
+        out.push_str("# TYPE daemon_delivery_coalesced_total counter\n");
+        let _ = writeln!(
+            out,
+            "daemon_delivery_coalesced_total {}",
+            self.delivery_coalesced.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP daemon_delivery_dropped_total Renderer updates dropped because the subscriber's delivery queue was full.\n");
+        out.push_str("# TYPE daemon_delivery_dropped_total counter\n");
+        let _ = writeln!(
+            out,
+            "daemon_delivery_dropped_total {}",
+            self.delivery_dropped.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP daemon_subscribers_active Open downstream WebSocket connections.\n");
+        out.push_str("# TYPE daemon_subscribers_active gauge\n");
+        let _ = writeln!(
+            out,
+            "daemon_subscribers_active {}",
+            self.subscribers_active.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP daemon_subscribers_reaped_total Downstream connections closed for missing keep-alive responses.\n");
+        out.push_str("# TYPE daemon_subscribers_reaped_total counter\n");
+        let _ = writeln!(
+            out,
+            "daemon_subscribers_reaped_total {}",
+            self.subscribers_reaped.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP daemon_retention_evictions_total Components evicted by retention rules.\n");
+        out.push_str("# TYPE daemon_retention_evictions_total counter\n");
+        let mut evictions: Vec<(String, u64)> = self
+            .retention_evictions
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect();
+        evictions.sort();
+        for (rule, count) in evictions {
+            let _ = writeln!(
+                out,
+                "daemon_retention_evictions_total{{rule=\"{}\"}} {}",
+                escape_label(&rule),
+                count
+            );
+        }
+
+        out.push_str("# HELP daemon_memory_budget_evictions_total Components evicted to get back under the memory budget.\n");
+        out.push_str("# TYPE daemon_memory_budget_evictions_total counter\n");
+        let _ = writeln!(
+            out,
+            "daemon_memory_budget_evictions_total {}",
+            self.memory_evicted.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP daemon_memory_budget_refused_total New components refused while over the memory budget.\n");
+        out.push_str("# TYPE daemon_memory_budget_refused_total counter\n");
+        let _ = writeln!(
+            out,
+            "daemon_memory_budget_refused_total {}",
+            self.memory_refused.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP daemon_push_connected Renderers the daemon is pushing to.\n");
+        out.push_str("# TYPE daemon_push_connected gauge\n");
+        let _ = writeln!(
+            out,
+            "daemon_push_connected {}",
+            self.push_connected.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP daemon_push_messages_total Messages pushed to renderers in push mode.\n");
+        out.push_str("# TYPE daemon_push_messages_total counter\n");
+        let _ = writeln!(
+            out,
+            "daemon_push_messages_total {}",
+            self.push_sent.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP daemon_upstream_connected Whether the registry connection is acknowledged.\n");
+        out.push_str("# TYPE daemon_upstream_connected gauge\n");
+        let _ = writeln!(
+            out,
+            "daemon_upstream_connected {}",
+            self.upstream_connected.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP daemon_internal_events_total Health-state transitions by kind.\n");
+        out.push_str("# TYPE daemon_internal_events_total counter\n");
+        let mut internal_events: Vec<(&str, u64)> = self
+            .internal_events
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect();
+        internal_events.sort();
+        for (kind, count) in internal_events {
+            let _ = writeln!(out, "daemon_internal_events_total{{kind=\"{}\"}} {}", kind, count);
+        }
+
+        out.push_str("# HELP daemon_oversized_components_total Components over the size limit by action taken.\n");
+        out.push_str("# TYPE daemon_oversized_components_total counter\n");
+        let mut oversized: Vec<(&str, u64)> = self.oversized.iter().map(|entry| (*entry.key(), *entry.value())).collect();
+        oversized.sort();
+        for (action, count) in oversized {
+            let _ = writeln!(out, "daemon_oversized_components_total{{action=\"{}\"}} {}", action, count);
+        }
+
+        out.push_str("# HELP daemon_attachments_total Referenced assets by outcome.\n");
+        out.push_str("# TYPE daemon_attachments_total counter\n");
+        let mut attachments: Vec<(&str, u64)> = self.attachments.iter().map(|entry| (*entry.key(), *entry.value())).collect();
+        attachments.sort();
+        for (outcome, count) in attachments {
+            let _ = writeln!(out, "daemon_attachments_total{{outcome=\"{}\"}} {}", outcome, count);
+        }
+
+        let mut sinks: Vec<(String, Arc<SinkStats>)> = self
+            .sinks
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        sinks.sort_by(|a, b| a.0.cmp(&b.0));
+        out.push_str("# HELP daemon_sink_events_total Outbound sink events by outcome.\n");
+        out.push_str("# TYPE daemon_sink_events_total counter\n");
+        for (sink, stats) in &sinks {
+            for (outcome, count) in [
+                ("delivered", &stats.delivered),
+                ("retried", &stats.retried),
+                ("dead_lettered", &stats.dead_lettered),
+            ] {
+                let _ = writeln!(
+                    out,
+                    "daemon_sink_events_total{{sink=\"{}\",outcome=\"{}\"}} {}",
+                    escape_label(sink),
+                    outcome,
+                    count.load(Ordering::Relaxed)
+                );
+            }
+        }
+        out.push_str("# HELP daemon_sink_queue_depth Events waiting in a sink's delivery queue.\n");
+        out.push_str("# TYPE daemon_sink_queue_depth gauge\n");
+        for (sink, stats) in &sinks {
+            let _ = writeln!(
+                out,
+                "daemon_sink_queue_depth{{sink=\"{}\"}} {}",
+                escape_label(sink),
+                stats.queued.load(Ordering::Relaxed)
+            );
+        }
+
+        self.read_alloc_bytes.render(
+            &mut out,
+            "daemon_component_read_alloc_bytes",
+            "Approximate bytes allocated per full component listing.",
+        );
+
+        self.operations.durations.render(
+            &mut out,
+            "daemon_graphql_operation_duration_seconds",
+            "GraphQL operation execution time.",
+        );
+        self.operations.variables_bytes.render(
+            &mut out,
+            "daemon_graphql_operation_variables_bytes",
+            "Serialized size of GraphQL operation variables.",
+        );
+
+        out
+    }
+}
+
+pub fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+// ========================
+// HISTOGRAMS
+// ========================
+
+pub const DURATION_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+pub const SIZE_BUCKETS: &[f64] = &[64.0, 256.0, 1024.0, 4096.0, 16384.0, 65536.0, 262144.0];
+pub const READ_BUCKETS: &[f64] = &[1024.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0, 16777216.0];
+
+pub struct Histogram {
+    bounds: &'static [f64],
+    buckets: Vec<AtomicU64>,
+    count: AtomicU64,
+    // Sum is kept in micro-units so it fits an atomic integer
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: bounds.iter().map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, value: f64) {
+        for (bound, bucket) in self.bounds.iter().zip(&self.buckets) {
+            if value <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add((value * 1_000_000.0) as u64, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        for (bound, bucket) in self.bounds.iter().zip(&self.buckets) {
+            let _ = writeln!(
+                out,
+                "{name}_bucket{{{labels}{sep}le=\"{bound}\"}} {}",
+                bucket.load(Ordering::Relaxed)
+            );
+        }
+        let _ = writeln!(out, "{name}_bucket{{{labels}{sep}le=\"+Inf\"}} {}", self.count());
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum());
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.count());
+    }
+}
+
+// Histograms keyed by a label set, created on first observation.
+pub struct HistogramVec {
+    bounds: &'static [f64],
+    label_names: &'static [&'static str],
+    series: DashMap<Vec<String>, Histogram>,
+}
+
+impl HistogramVec {
+    pub fn new(bounds: &'static [f64], label_names: &'static [&'static str]) -> Self {
+        Self {
+            bounds,
+            label_names,
+            series: DashMap::new(),
+        }
+    }
+
+    pub fn observe(&self, labels: &[&str], value: f64) {
+        let key: Vec<String> = labels.iter().map(|l| l.to_string()).collect();
+        self.series
+            .entry(key)
+            .or_insert_with(|| Histogram::new(self.bounds))
+            .observe(value);
+    }
+
+    pub fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        for entry in self.series.iter() {
+            let labels = self
+                .label_names
+                .iter()
+                .zip(entry.key())
+                .map(|(k, v)| format!("{k}=\"{}\"", escape_label(v)))
+                .collect::<Vec<_>>()
+                .join(",");
+            entry.value().render(out, name, &labels);
+        }
+    }
+}
+
+// ========================
+// GRAPHQL OPERATIONS
+// ========================
+
+// Series are labelled by the client-supplied operationName, so only the
+// first OPERATION_LABEL_LIMIT (default 100) distinct names get their own;
+// later names, and anything that isn't a GraphQL name, count as "other".
+pub struct OperationMetrics {
+    pub durations: HistogramVec,
+    pub variables_bytes: HistogramVec,
+    names: Mutex<HashSet<String>>,
+    name_limit: usize,
+}
+
+impl OperationMetrics {
+    pub fn from_env() -> Self {
+        Self {
+            durations: HistogramVec::new(DURATION_BUCKETS, &["operation", "status"]),
+            variables_bytes: HistogramVec::new(SIZE_BUCKETS, &["operation"]),
+            names: Mutex::new(HashSet::new()),
+            name_limit: env_parse("OPERATION_LABEL_LIMIT", 100),
+        }
+    }
+
+    pub fn record(&self, operation: &str, status: &str, duration: Duration, variables_bytes: usize) {
+        let operation = self.label(operation);
+        self.durations
+            .observe(&[operation, status], duration.as_secs_f64());
+        self.variables_bytes
+            .observe(&[operation], variables_bytes as f64);
+    }
+
+    fn label<'a>(&self, operation: &'a str) -> &'a str {
+        let valid = operation.len() <= 128
+            && operation.chars().next().is_some_and(|c| c == '_' || c.is_ascii_alphabetic())
+            && operation.chars().all(|c| c == '_' || c.is_ascii_alphanumeric());
+        if !valid {
+            return "other";
+        }
+        let mut names = self.names.lock().unwrap();
+        if names.contains(operation) {
+            return operation;
+        }
+        if names.len() < self.name_limit {
+            names.insert(operation.to_string());
+            return operation;
+        }
+        "other"
+    }
+}
+
+// ========================
+// INGEST FAILURES
+// ========================
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FailureKind {
+    InvalidJson,
+    MissingField,
+    UnknownVariant,
+    InvalidType,
+    Validation,
+    Signature,
+    Oversized,
+    Other,
+}
+
+impl FailureKind {
+    pub fn classify(err: &serde_json::Error) -> Self {
+        use serde_json::error::Category;
+
+        match err.classify() {
+            Category::Syntax | Category::Eof | Category::Io => FailureKind::InvalidJson,
+            Category::Data => {
+                let msg = err.to_string();
+                if msg.starts_with("missing field") {
+                    FailureKind::MissingField
+                } else if msg.starts_with("unknown variant") {
+                    FailureKind::UnknownVariant
+                } else if msg.starts_with("invalid type") || msg.starts_with("invalid value") {
+                    FailureKind::InvalidType
+                } else {
+                    FailureKind::Other
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailureCount {
+    pub upstream: String,
+    pub kind: FailureKind,
+    pub count: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailureSample {
+    pub upstream: String,
+    pub kind: FailureKind,
+    pub error: String,
+    pub payload: serde_json::Value,
+    pub received_at: DateTime<Utc>,
+}
+
+pub struct IngestFailures {
+    counts: DashMap<(String, FailureKind), u64>,
+    samples: Mutex<VecDeque<FailureSample>>,
+    sample_capacity: usize,
+}
+
+impl IngestFailures {
+    pub fn new(sample_capacity: usize) -> Self {
+        Self {
+            counts: DashMap::new(),
+            samples: Mutex::new(VecDeque::with_capacity(sample_capacity)),
+            sample_capacity,
+        }
+    }
+
+    pub fn record(&self, upstream: &str, err: &serde_json::Error, payload: &serde_json::Value) {
+        self.push(upstream, FailureKind::classify(err), describe(err), redact(payload));
+    }
+
+    // `error` must not quote payload values, e.g. validation rules and paths
+    // rather than the validator's messages
+    pub fn record_rejection(&self, upstream: &str, kind: FailureKind, error: String, payload: &serde_json::Value) {
+        self.push(upstream, kind, error, redact(payload));
+    }
+
+    // Text that never parsed as JSON has no shape worth keeping, only its size.
+    pub fn record_raw(&self, upstream: &str, err: &serde_json::Error, raw: &str) {
+        let placeholder = format!("<unparseable message, {} bytes>", raw.len());
+        self.push(upstream, FailureKind::classify(err), describe(err), serde_json::Value::String(placeholder));
+    }
+
+    fn push(&self, upstream: &str, kind: FailureKind, error: String, payload: serde_json::Value) {
+        *self.counts.entry((upstream.to_string(), kind)).or_insert(0) += 1;
+
+        if self.sample_capacity == 0 {
+            return;
+        }
+
+        let sample = FailureSample {
+            upstream: upstream.to_string(),
+            kind,
+            error,
+            payload,
+            received_at: Utc::now(),
+        };
+
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == self.sample_capacity {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    pub fn counts(&self) -> Vec<FailureCount> {
+        let mut counts: Vec<FailureCount> = self
+            .counts
+            .iter()
+            .map(|entry| FailureCount {
+                upstream: entry.key().0.clone(),
+                kind: entry.key().1,
+                count: *entry.value(),
+            })
+            .collect();
+        counts.sort_by_key(|c| std::cmp::Reverse(c.count));
+        counts
+    }
+
+    pub fn samples(&self) -> Vec<FailureSample> {
+        self.samples.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().map(|entry| *entry.value()).sum()
+    }
+}
+
+// serde's messages quote the offending value ("invalid type: string
+// \"...\""), so samples only keep the kind and where it happened
+fn describe(err: &serde_json::Error) -> String {
+    format!("{:?} at line {} column {}", FailureKind::classify(err), err.line(), err.column())
+}
+
+// Keep the shape of the payload (keys, nesting, value types) so schema drift is
+// visible, but never retain the actual user content.
+fn redact(value: &serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), redact(v)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact).collect()),
+        Value::String(s) => Value::String(format!("<redacted string, {} chars>", s.chars().count())),
+        Value::Number(_) => Value::String("<redacted number>".to_string()),
+        Value::Bool(_) | Value::Null => value.clone(),
+    }
+}
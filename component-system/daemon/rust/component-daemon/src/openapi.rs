@@ -0,0 +1,90 @@
+use utoipa::OpenApi;
+use warp::Filter;
+
+use crate::{admin, rest};
+
+// ========================
+// OPENAPI
+// ========================
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Component Daemon", description = "REST and admin API of the component daemon"),
+    paths(
+        rest::list_components,
+        rest::get_component,
+        rest::update_component,
+        rest::delete_component,
+        admin::ingest_failures,
+        admin::purge,
+        admin::reconnect,
+        admin::replication_status,
+        admin::promote,
+        admin::usage,
+        admin::shadow,
+        admin::audit_query,
+        admin::audit_verify,
+        admin::rewrap,
+        admin::log_level_get,
+        admin::log_level_put,
+        admin::quarantine_list,
+        admin::quarantine_release,
+        admin::quarantine_drop,
+        admin::flags_list,
+        admin::flag_put,
+        admin::flag_delete,
+    ),
+    tags(
+        (name = "components", description = "Stored components"),
+        (name = "admin", description = "Operational endpoints, audited where they change state"),
+    )
+)]
+pub struct ApiDoc;
+
+// Swagger UI page loading its bundle from a CDN, like the GraphiQL page.
+// SWAGGER_UI=off disables it; /openapi.json is always served.
+fn swagger_page() -> Option<String> {
+    if matches!(
+        std::env::var("SWAGGER_UI").as_deref(),
+        Ok("off") | Ok("none")
+    ) {
+        return None;
+    }
+    Some(
+        r##"<!DOCTYPE html>
+<html>
+<head>
+  <title>Component Daemon API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>"##
+            .to_string(),
+    )
+}
+
+pub fn routes() -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let spec = serde_json::to_value(ApiDoc::openapi()).unwrap_or_default();
+    let spec = warp::path!("openapi.json")
+        .and(warp::get())
+        .map(move || warp::reply::json(&spec));
+
+    let page = swagger_page();
+    let docs = warp::path!("docs").and(warp::get()).and_then(move || {
+        let page = page.clone();
+        async move {
+            match page {
+                Some(page) => Ok(warp::reply::html(page)),
+                None => Err(warp::reject::not_found()),
+            }
+        }
+    });
+
+    spec.or(docs)
+}
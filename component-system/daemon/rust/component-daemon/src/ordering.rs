@@ -0,0 +1,108 @@
+use crate::Component;
+
+// ========================
+// PER-ID ORDERING
+// ========================
+
+// Which key decides whether an incoming update is older than the stored one.
+// Ties are applied, so a registry that never changes createdAt still updates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderingKey {
+    Off,
+    CreatedAt,
+    UpstreamSeq,
+    // upstreamSeq when both sides carry one, createdAt otherwise
+    Auto,
+}
+
+impl OrderingKey {
+    // ORDERING_KEY: off, created-at, upstream-seq or auto (default). Registries
+    // that number their updates add `upstreamSeq` to the subscription selection.
+    pub fn from_env() -> Self {
+        match std::env::var("ORDERING_KEY").as_deref().map(str::trim) {
+            Ok("off") => OrderingKey::Off,
+            Ok("created-at") => OrderingKey::CreatedAt,
+            Ok("upstream-seq") => OrderingKey::UpstreamSeq,
+            _ => OrderingKey::Auto,
+        }
+    }
+
+    // Why `incoming` is stale relative to `stored`, if it is
+    pub fn stale(&self, stored: &Component, incoming: &Component) -> Option<String> {
+        let by_seq = match (stored.upstream_seq, incoming.upstream_seq) {
+            (Some(stored), Some(incoming)) => Some((stored, incoming)),
+            _ => None,
+        };
+        match (self, by_seq) {
+            (OrderingKey::Off, _) | (OrderingKey::UpstreamSeq, None) => None,
+            (OrderingKey::UpstreamSeq | OrderingKey::Auto, Some((stored, incoming))) => (incoming < stored)
+                .then(|| format!("upstreamSeq {incoming} is behind stored {stored}")),
+            (OrderingKey::CreatedAt | OrderingKey::Auto, _) => (incoming.created_at < stored.created_at)
+                .then(|| {
+                    format!(
+                        "createdAt {} is behind stored {}",
+                        incoming.created_at.to_rfc3339(),
+                        stored.created_at.to_rfc3339()
+                    )
+                }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn component(created_at: &str, upstream_seq: Option<u64>) -> Component {
+        serde_json::from_value(json!({
+            "id": "c1",
+            "type": "CARD",
+            "data": {},
+            "createdAt": created_at,
+            "upstreamSeq": upstream_seq,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn upstream_seq_decides_when_both_sides_carry_one() {
+        let stored = component("2024-01-01T00:00:00Z", Some(5));
+        // Newer createdAt, older seq: the seq wins
+        let behind = component("2024-06-01T00:00:00Z", Some(4));
+        let ahead = component("2023-01-01T00:00:00Z", Some(6));
+
+        for key in [OrderingKey::UpstreamSeq, OrderingKey::Auto] {
+            assert_eq!(key.stale(&stored, &behind).as_deref(), Some("upstreamSeq 4 is behind stored 5"));
+            assert_eq!(key.stale(&stored, &ahead), None);
+        }
+        assert_eq!(OrderingKey::UpstreamSeq.stale(&stored, &component("2023-01-01T00:00:00Z", None)), None);
+    }
+
+    #[test]
+    fn created_at_decides_without_upstream_seq() {
+        let stored = component("2024-01-01T00:00:00Z", Some(5));
+        let older = component("2023-12-31T23:59:59Z", None);
+        let newer = component("2024-01-01T00:00:01Z", None);
+
+        for key in [OrderingKey::CreatedAt, OrderingKey::Auto] {
+            assert!(key.stale(&stored, &older).is_some());
+            assert_eq!(key.stale(&stored, &newer), None);
+        }
+        // createdAt ignores any seq
+        assert!(OrderingKey::CreatedAt
+            .stale(&stored, &component("2023-01-01T00:00:00Z", Some(9)))
+            .is_some());
+    }
+
+    #[test]
+    fn ties_are_applied_and_off_never_rejects() {
+        let stored = component("2024-01-01T00:00:00Z", Some(5));
+        let same = component("2024-01-01T00:00:00Z", Some(5));
+        for key in [OrderingKey::CreatedAt, OrderingKey::UpstreamSeq, OrderingKey::Auto] {
+            assert_eq!(key.stale(&stored, &same), None);
+        }
+        assert_eq!(OrderingKey::Off.stale(&stored, &component("2020-01-01T00:00:00Z", Some(1))), None);
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_graphql::SimpleObject;
+
+use crate::config::env_parse;
+use crate::labels::LabelSelector;
+use crate::{Component, ComponentDaemon};
+
+// ========================
+// SNAPSHOT PAGINATION
+// ========================
+
+#[derive(Clone, Debug, SimpleObject)]
+pub struct ComponentPage {
+    pub items: Vec<Arc<Component>>,
+    // Pass back with `after` to keep reading the same view
+    pub state_version: String,
+    // Id of the last item; null on an empty page
+    pub end_cursor: Option<String>,
+    pub has_next_page: bool,
+    // Matching components in the pinned view, across all pages
+    pub total_count: usize,
+}
+
+// One pinned view handed to every execution behind a single response, e.g.
+// the parts of an incremental (@defer/@stream) reply, so later parts read the
+// state the first one was built from
+#[derive(Clone)]
+pub struct PinnedView {
+    pub state_version: String,
+    // Sorted by id
+    components: Arc<Vec<Arc<Component>>>,
+}
+
+impl PinnedView {
+    pub fn components(&self) -> Vec<Arc<Component>> {
+        self.components.to_vec()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<Component>> {
+        let index = self.components.binary_search_by(|component| component.id.as_str().cmp(id)).ok()?;
+        Some(self.components[index].clone())
+    }
+}
+
+struct Pinned {
+    // Sorted by id, which is also the cursor order
+    components: Arc<Vec<Arc<Component>>>,
+    last_used: Instant,
+}
+
+// Views pinned by the first page of a traversal. A view is a sorted list of
+// the stored Arcs, so pinning costs one pointer per component and later
+// writes replace map entries without touching it. Views idle for
+// PAGE_SNAPSHOT_TTL_SECS are dropped, as is the least recently used one once
+// PAGE_SNAPSHOTS_MAX are held.
+pub struct PageSnapshots {
+    pinned: Mutex<HashMap<String, Pinned>>,
+    ttl: Duration,
+    max_pinned: usize,
+    max_page_size: usize,
+}
+
+impl PageSnapshots {
+    pub fn from_env() -> Self {
+        Self {
+            pinned: Mutex::new(HashMap::new()),
+            ttl: Duration::from_secs(env_parse("PAGE_SNAPSHOT_TTL_SECS", 60)),
+            max_pinned: env_parse("PAGE_SNAPSHOTS_MAX", 16).max(1),
+            max_page_size: env_parse("PAGE_SIZE_MAX", 1000),
+        }
+    }
+
+    // The current state's view; traversals starting at the same state share it
+    fn pin(&self, daemon: &ComponentDaemon) -> (String, Arc<Vec<Arc<Component>>>) {
+        // Same ordering as the ETag: version first, then the contents
+        let token = daemon.state_etag().trim_matches('"').to_string();
+        let now = Instant::now();
+        let mut pinned = self.pinned.lock().unwrap();
+        pinned.retain(|_, view| now.duration_since(view.last_used) < self.ttl);
+        if let Some(view) = pinned.get_mut(&token) {
+            view.last_used = now;
+            return (token, view.components.clone());
+        }
+
+        let mut components = daemon.get_components();
+        components.sort_unstable_by(|a, b| a.id.cmp(&b.id));
+        let components = Arc::new(components);
+        if pinned.len() >= self.max_pinned {
+            let oldest = pinned
+                .iter()
+                .min_by_key(|(_, view)| view.last_used)
+                .map(|(token, _)| token.clone());
+            if let Some(oldest) = oldest {
+                pinned.remove(&oldest);
+            }
+        }
+        pinned.insert(
+            token.clone(),
+            Pinned {
+                components: components.clone(),
+                last_used: now,
+            },
+        );
+        (token, components)
+    }
+
+    // Also resumable as a `stateVersion` by componentsPage
+    pub fn view(&self, daemon: &ComponentDaemon) -> PinnedView {
+        let (state_version, components) = self.pin(daemon);
+        PinnedView { state_version, components }
+    }
+
+    fn resume(&self, token: &str) -> Option<Arc<Vec<Arc<Component>>>> {
+        let now = Instant::now();
+        let mut pinned = self.pinned.lock().unwrap();
+        let view = pinned.get_mut(token)?;
+        if now.duration_since(view.last_used) >= self.ttl {
+            pinned.remove(token);
+            return None;
+        }
+        view.last_used = now;
+        Some(view.components.clone())
+    }
+
+    // Without `state_version` the current state is pinned; with it the page
+    // comes from that view, or None once the view has been dropped.
+    pub fn page(
+        &self,
+        daemon: &ComponentDaemon,
+        state_version: Option<&str>,
+        first: usize,
+        after: Option<&str>,
+        selector: Option<&LabelSelector>,
+    ) -> Option<ComponentPage> {
+        let (token, view) = match state_version {
+            Some(token) => (token.to_string(), self.resume(token)?),
+            None => self.pin(daemon),
+        };
+        let first = first.min(self.max_page_size);
+        let start = after.map_or(0, |after| view.partition_point(|c| c.id.as_str() <= after));
+        let matching = |component: &&Arc<Component>| {
+            selector.is_none_or(|selector| selector.matches(&component.labels))
+        };
+
+        let mut rest = view[start..].iter().filter(matching);
+        let items: Vec<Arc<Component>> = rest.by_ref().take(first).cloned().collect();
+        let has_next_page = rest.next().is_some();
+        let total_count = match selector {
+            Some(_) => view.iter().filter(matching).count(),
+            None => view.len(),
+        };
+        Some(ComponentPage {
+            end_cursor: items.last().map(|component| component.id.clone()),
+            items,
+            state_version: token,
+            has_next_page,
+            total_count,
+        })
+    }
+}
@@ -0,0 +1,103 @@
+use async_graphql::{Enum, SimpleObject, Union};
+use serde::{Deserialize, Serialize};
+
+use crate::{Component, ComponentType};
+
+// ========================
+// TYPED PAYLOADS
+// ========================
+
+#[derive(Clone, Debug, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct CardButton {
+    pub text: String,
+    pub action: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct CardData {
+    pub title: Option<String>,
+    pub content: Option<String>,
+    #[serde(default)]
+    pub buttons: Vec<CardButton>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Enum, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum NotificationLevel {
+    Success,
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationData {
+    pub r#type: NotificationLevel,
+    pub title: Option<String>,
+    pub message: String,
+    pub dismissible: Option<bool>,
+    pub auto_remove: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct FormField {
+    pub name: String,
+    pub label: Option<String>,
+    #[serde(default = "default_field_type")]
+    pub r#type: String,
+    pub placeholder: Option<String>,
+    // Checked by submitForm
+    #[serde(default)]
+    pub required: bool,
+}
+
+fn default_field_type() -> String {
+    "text".to_string()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct FormData {
+    pub title: Option<String>,
+    pub fields: Vec<FormField>,
+    pub submit_text: Option<String>,
+}
+
+// Fallback for payloads that don't match the typed schema for their component
+// type, so renderers still receive the data instead of an error.
+#[derive(Clone, Debug, Serialize, Deserialize, SimpleObject)]
+pub struct RawData {
+    pub data: serde_json::Value,
+}
+
+#[derive(Clone, Debug, Union)]
+pub enum TypedComponent {
+    Card(CardData),
+    Notification(NotificationData),
+    Form(FormData),
+    Raw(RawData),
+}
+
+impl TypedComponent {
+    pub fn parse(r#type: ComponentType, data: &serde_json::Value) -> Result<Self, serde_json::Error> {
+        Ok(match r#type {
+            ComponentType::Card => TypedComponent::Card(serde_json::from_value(data.clone())?),
+            ComponentType::Notification => {
+                TypedComponent::Notification(serde_json::from_value(data.clone())?)
+            }
+            ComponentType::Form => TypedComponent::Form(serde_json::from_value(data.clone())?),
+        })
+    }
+
+    pub fn from_component(component: &Component) -> Self {
+        Self::parse(component.r#type, &component.data).unwrap_or_else(|_| {
+            TypedComponent::Raw(RawData {
+                data: component.data.clone(),
+            })
+        })
+    }
+}
@@ -0,0 +1,175 @@
+use std::convert::Infallible;
+use std::fmt::Write;
+
+use warp::http::{header, StatusCode};
+use warp::reply::{Reply, Response};
+use warp::Filter;
+
+use crate::payload::{CardData, FormData, NotificationData, RawData, TypedComponent};
+use crate::rest::{etag_matches, not_modified};
+use crate::{Component, ComponentDaemon};
+
+// ========================
+// HTML PREVIEW
+// ========================
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:16px;color:#222}\
+.component{max-width:480px;border:1px solid #ddd;border-left:4px solid #9ca3af;border-radius:6px;padding:12px}\
+.SUCCESS{border-left-color:#059669}.ERROR{border-left-color:#b91c1c}\
+.WARNING{border-left-color:#d97706}.INFO{border-left-color:#2563eb}\
+h1{font-size:16px;margin:0 0 6px}label{display:block;font-size:13px;margin-top:6px}\
+input{display:block;width:100%;box-sizing:border-box}button{margin:8px 6px 0 0}\
+pre{font-size:12px;white-space:pre-wrap}.meta{font-size:11px;color:#888;margin-top:10px}";
+
+// GET /preview/:id renders one component as a standalone HTML page, e.g. for
+// an iframe in an internal dashboard. All data is escaped before interpolation.
+pub fn routes(
+    daemon: ComponentDaemon,
+) -> impl Filter<Extract = (Response,), Error = warp::Rejection> + Clone {
+    warp::path!("preview" / String)
+        .and(warp::get())
+        .and(warp::header::optional::<String>("if-none-match"))
+        .and(warp::any().map(move || daemon.clone()))
+        .and_then(preview)
+}
+
+async fn preview(
+    id: String,
+    if_none_match: Option<String>,
+    daemon: ComponentDaemon,
+) -> Result<Response, Infallible> {
+    let Some(component) = daemon.get_component(&id) else {
+        let page = page(
+            "Not found",
+            &format!("<p>No component <code>{}</code>.</p>", escape(&id)),
+        );
+        return Ok(
+            warp::reply::with_status(warp::reply::html(page), StatusCode::NOT_FOUND)
+                .into_response(),
+        );
+    };
+
+    let etag = component.etag();
+    if etag_matches(if_none_match.as_deref(), &etag) {
+        return Ok(not_modified(&etag));
+    }
+    let html = page(&component.id, &render(&component));
+    Ok(warp::reply::with_header(warp::reply::html(html), header::ETAG, etag).into_response())
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{}</title>\
+<style>{STYLE}</style></head><body>{body}</body></html>",
+        escape(title)
+    )
+}
+
+fn render(component: &Component) -> String {
+    let typed = match TypedComponent::from_component(component) {
+        // Every card field is optional, so unrelated data still parses as one
+        TypedComponent::Card(card)
+            if card.title.is_none() && card.content.is_none() && card.buttons.is_empty() =>
+        {
+            TypedComponent::Raw(RawData {
+                data: component.data.clone(),
+            })
+        }
+        typed => typed,
+    };
+    let (class, inner) = match typed {
+        TypedComponent::Card(card) => (String::new(), card_html(&card)),
+        TypedComponent::Notification(notification) => {
+            let level = serde_json::to_value(notification.r#type).unwrap_or_default();
+            (
+                level.as_str().unwrap_or_default().to_string(),
+                notification_html(&notification),
+            )
+        }
+        TypedComponent::Form(form) => (String::new(), form_html(&form)),
+        TypedComponent::Raw(raw) => (
+            String::new(),
+            format!(
+                "<pre>{}</pre>",
+                escape(&serde_json::to_string_pretty(&raw.data).unwrap_or_default())
+            ),
+        ),
+    };
+    let r#type = serde_json::to_value(component.r#type).unwrap_or_default();
+    format!(
+        "<div class=\"component {}\">{}<div class=\"meta\">{} · {} · v{} · seq {}</div></div>",
+        escape(&class),
+        inner,
+        escape(r#type.as_str().unwrap_or_default()),
+        escape(&component.id),
+        component.version,
+        component.seq
+    )
+}
+
+fn card_html(card: &CardData) -> String {
+    let mut html = String::new();
+    if let Some(title) = &card.title {
+        let _ = write!(html, "<h1>{}</h1>", escape(title));
+    }
+    if let Some(content) = &card.content {
+        let _ = write!(html, "<p>{}</p>", escape(content));
+    }
+    for button in &card.buttons {
+        let _ = write!(
+            html,
+            "<button title=\"{}\" disabled>{}</button>",
+            escape(button.action.as_deref().unwrap_or_default()),
+            escape(&button.text)
+        );
+    }
+    html
+}
+
+fn notification_html(notification: &NotificationData) -> String {
+    let mut html = String::new();
+    if let Some(title) = &notification.title {
+        let _ = write!(html, "<h1>{}</h1>", escape(title));
+    }
+    let _ = write!(html, "<p>{}</p>", escape(&notification.message));
+    html
+}
+
+fn form_html(form: &FormData) -> String {
+    let mut html = String::from("<form onsubmit=\"return false\">");
+    if let Some(title) = &form.title {
+        let _ = write!(html, "<h1>{}</h1>", escape(title));
+    }
+    for field in &form.fields {
+        let _ = write!(
+            html,
+            "<label>{}<input name=\"{}\" type=\"{}\" placeholder=\"{}\" disabled></label>",
+            escape(field.label.as_deref().unwrap_or(&field.name)),
+            escape(&field.name),
+            escape(&field.r#type),
+            escape(field.placeholder.as_deref().unwrap_or_default())
+        );
+    }
+    let _ = write!(
+        html,
+        "<button disabled>{}</button></form>",
+        escape(form.submit_text.as_deref().unwrap_or("Submit"))
+    );
+    html
+}
+
+// Safe for both element content and quoted attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,140 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::config::per_type_overrides;
+use crate::metrics::Metrics;
+use crate::{Component, ComponentType};
+
+// ========================
+// PRIORITY
+// ========================
+
+pub struct PriorityConfig {
+    defaults: HashMap<ComponentType, i32>,
+}
+
+impl PriorityConfig {
+    // PRIORITY_DEFAULTS overrides the per-type fallbacks, e.g.
+    // "NOTIFICATION=10,FORM=5,CARD=0".
+    pub fn from_env() -> Self {
+        Self::from_var("PRIORITY_DEFAULTS")
+    }
+
+    pub fn from_var(name: &str) -> Self {
+        let mut defaults = HashMap::from([
+            (ComponentType::Notification, 10),
+            (ComponentType::Form, 5),
+            (ComponentType::Card, 0),
+        ]);
+        defaults.extend(per_type_overrides(name, |v| v.parse().ok()));
+
+        Self { defaults }
+    }
+
+    // An explicit priority on the component wins, then `data.priority`, then
+    // the per-type default.
+    pub fn resolve(&self, component: &Component) -> i32 {
+        component
+            .priority
+            .or_else(|| {
+                component
+                    .data
+                    .get("priority")
+                    .and_then(|p| p.as_i64())
+                    .map(|p| p.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+            })
+            .unwrap_or_else(|| self.defaults.get(&component.r#type).copied().unwrap_or(0))
+    }
+}
+
+// ========================
+// DELIVERY QUEUE
+// ========================
+
+// Highest priority first; FIFO among equal priorities
+type Rank = (Reverse<i32>, u64);
+
+// Per-subscriber reorder buffer. When a renderer falls behind, everything that
+// piled up in the broadcast channel is drained here and handed out by priority.
+// Holds one entry per id, the newest version, and at most `capacity` ids;
+// past that the lowest-ranked entry is dropped and counted.
+pub struct DeliveryQueue {
+    queued: BTreeMap<Rank, Component>,
+    by_id: HashMap<String, Rank>,
+    // Seqs of the queued entries, so the oldest one still waiting is cheap to find
+    seqs: BTreeSet<u64>,
+    capacity: usize,
+    next_seq: u64,
+    metrics: Arc<Metrics>,
+}
+
+impl DeliveryQueue {
+    pub fn new(capacity: usize, metrics: Arc<Metrics>) -> Self {
+        Self {
+            queued: BTreeMap::new(),
+            by_id: HashMap::new(),
+            seqs: BTreeSet::new(),
+            capacity: capacity.max(1),
+            next_seq: 0,
+            metrics,
+        }
+    }
+
+    pub fn push(&mut self, component: Component) {
+        let priority = Reverse(component.priority.unwrap_or(0));
+        // A newer version takes over the queued one's place in line, so an id
+        // that keeps changing isn't pushed to the back every time
+        if let Some(rank) = self.by_id.get(&component.id).copied() {
+            if self.queued[&rank].seq > component.seq {
+                return;
+            }
+            if let Some(replaced) = self.queued.remove(&rank) {
+                self.seqs.remove(&replaced.seq);
+            }
+            let rank = (priority, rank.1);
+            self.by_id.insert(component.id.clone(), rank);
+            self.seqs.insert(component.seq);
+            self.queued.insert(rank, component);
+            self.metrics.delivery_coalesced.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        let rank = (priority, self.next_seq);
+        self.next_seq += 1;
+        if self.queued.len() >= self.capacity {
+            self.metrics.delivery_dropped.fetch_add(1, Ordering::Relaxed);
+            match self.queued.last_key_value() {
+                Some((last, _)) if *last > rank => {
+                    let last = *last;
+                    if let Some(dropped) = self.queued.remove(&last) {
+                        self.by_id.remove(&dropped.id);
+                        self.seqs.remove(&dropped.seq);
+                    }
+                }
+                _ => return,
+            }
+        }
+        self.by_id.insert(component.id.clone(), rank);
+        self.seqs.insert(component.seq);
+        self.queued.insert(rank, component);
+    }
+
+    pub fn pop(&mut self) -> Option<Component> {
+        let (_, component) = self.queued.pop_first()?;
+        self.by_id.remove(&component.id);
+        self.seqs.remove(&component.seq);
+        Some(component)
+    }
+
+    // The oldest update still waiting. Entries go out by priority, so
+    // everything below this has been handed out or superseded.
+    pub fn lowest_seq(&self) -> Option<u64> {
+        self.seqs.first().copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+}
@@ -0,0 +1,224 @@
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{sleep, timeout};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{error, info, warn};
+
+use crate::config::env_parse;
+use crate::ws::KeepAliveConfig;
+use crate::{Component, ComponentDaemon};
+
+// ========================
+// PUSH MODE
+// ========================
+
+// For renderers that can't accept inbound connections: the daemon dials out
+// and plays the server side of graphql-ws over that socket. After the ack it
+// sends one `rendererSnapshot` that replaces the renderer's state, then
+// `rendererUpdate` / `componentRemoved` data messages as they happen. The
+// message ids are the field names, since there is no client-started operation.
+#[derive(Clone, Debug)]
+pub struct PushConfig {
+    pub urls: Vec<String>,
+    pub reconnect_delay: Duration,
+    pub ack_timeout: Duration,
+}
+
+impl PushConfig {
+    // PUSH_RENDERER_URLS is a comma-separated list of ws:// or wss:// renderer
+    // endpoints; unset disables push mode.
+    pub fn from_env() -> Self {
+        Self {
+            urls: std::env::var("PUSH_RENDERER_URLS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(str::to_string)
+                .collect(),
+            reconnect_delay: Duration::from_secs(env_parse("PUSH_RECONNECT_SECS", 2)),
+            ack_timeout: Duration::from_secs(env_parse("PUSH_ACK_TIMEOUT_SECS", 10)),
+        }
+    }
+}
+
+pub fn spawn(daemon: &ComponentDaemon, config: PushConfig) {
+    let keepalive = KeepAliveConfig::from_env();
+    for url in config.urls.clone() {
+        let daemon = daemon.clone();
+        let config = config.clone();
+        info!("📤 Daemon: Push mode enabled for renderer {}", url);
+        tokio::spawn(async move {
+            loop {
+                match push_to(&daemon, &url, &config, keepalive).await {
+                    Ok(()) => warn!(
+                        "🔌 Daemon: Renderer {} closed the push connection, reconnecting...",
+                        url
+                    ),
+                    Err(e) => error!("❌ Daemon: Push to renderer {} failed: {:#}", url, e),
+                }
+                sleep(config.reconnect_delay).await;
+            }
+        });
+    }
+}
+
+async fn push_to(
+    daemon: &ComponentDaemon,
+    url: &str,
+    config: &PushConfig,
+    keepalive: KeepAliveConfig,
+) -> Result<()> {
+    let mut request = url.into_client_request()?;
+    request.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        HeaderValue::from_static("graphql-ws"),
+    );
+    let (socket, _) = connect_async(request).await?;
+    let (mut write, mut read) = socket.split();
+
+    write
+        .send(Message::Text(
+            json!({ "type": "connection_init" }).to_string(),
+        ))
+        .await?;
+    let acked = timeout(config.ack_timeout, async {
+        while let Some(message) = read.next().await {
+            if let Message::Text(text) = message? {
+                let message: serde_json::Value = serde_json::from_str(&text)?;
+                match message["type"].as_str() {
+                    Some("connection_ack") => return Ok(()),
+                    Some("connection_error") => {
+                        return Err(anyhow!(
+                            "Renderer refused the connection: {}",
+                            message["payload"]
+                        ))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Err(anyhow!("Renderer closed before acknowledging"))
+    })
+    .await;
+    acked.map_err(|_| anyhow!("No connection_ack within {:?}", config.ack_timeout))??;
+    info!("✅ Daemon: Pushing to renderer {}", url);
+
+    // Subscribe before taking the snapshot so nothing falls in between
+    let mut updates = daemon.subscribe_to_updates();
+    let mut removals = daemon.subscribe_to_removals();
+    let mut snapshot_seq = send_snapshot(daemon, &mut write).await?;
+
+    let _connected = ConnectedGuard::new(daemon);
+    let mut last_seen = Instant::now();
+    let mut ticker = tokio::time::interval(keepalive.interval.unwrap_or(Duration::from_secs(3600)));
+    ticker.tick().await;
+
+    loop {
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(component) if component.seq > snapshot_seq => {
+                    send_data(daemon, &mut write, "rendererUpdate", json!(component)).await?;
+                    daemon.latency().delivered(Some(&format!("push:{url}")), &component.stamps);
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("⚠️ Daemon: Push to {} lagged by {} updates, resending snapshot", url, skipped);
+                    snapshot_seq = send_snapshot(daemon, &mut write).await?;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            removal = removals.recv() => match removal {
+                Ok(removal) => send_data(daemon, &mut write, "componentRemoved", json!(removal)).await?,
+                Err(RecvError::Lagged(_)) => {
+                    snapshot_seq = send_snapshot(daemon, &mut write).await?;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            message = read.next() => {
+                last_seen = Instant::now();
+                match message {
+                    Some(Ok(Message::Text(text))) if text.contains("connection_terminate") => return Ok(()),
+                    Some(Ok(Message::Close(_))) | None => return Ok(()),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e.into()),
+                }
+            }
+            _ = ticker.tick(), if keepalive.interval.is_some() => {
+                if last_seen.elapsed() > keepalive.idle_timeout {
+                    return Err(anyhow!("Renderer silent for {:?}", last_seen.elapsed()));
+                }
+                write.send(Message::Ping(Vec::new())).await?;
+                write.send(Message::Text(json!({ "type": "ka" }).to_string())).await?;
+            }
+        }
+    }
+}
+
+// Sends every stored component in broadcast order; returns the highest seq
+// included so live updates already covered can be skipped.
+async fn send_snapshot<S>(daemon: &ComponentDaemon, write: &mut S) -> Result<u64>
+where
+    S: futures_util::Sink<Message> + Unpin,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    let mut components = daemon.get_components();
+    components.sort_by_key(|component| component.seq);
+    let seq = components
+        .last()
+        .map(|component| component.seq)
+        .unwrap_or(0);
+    let components: Vec<&Component> = components.iter().map(|component| &**component).collect();
+    send_data(daemon, write, "rendererSnapshot", json!(components)).await?;
+    Ok(seq)
+}
+
+async fn send_data<S>(
+    daemon: &ComponentDaemon,
+    write: &mut S,
+    field: &str,
+    value: serde_json::Value,
+) -> Result<()>
+where
+    S: futures_util::Sink<Message> + Unpin,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    let message = json!({
+        "type": "data",
+        "id": field,
+        "payload": { "data": { field: value } },
+    });
+    write.send(Message::Text(message.to_string())).await?;
+    daemon.metrics().push_sent.fetch_add(1, Ordering::Relaxed);
+    Ok(())
+}
+
+// Keeps the connected-renderers gauge right however the push loop ends
+struct ConnectedGuard(ComponentDaemon);
+
+impl ConnectedGuard {
+    fn new(daemon: &ComponentDaemon) -> Self {
+        daemon
+            .metrics()
+            .push_connected
+            .fetch_add(1, Ordering::Relaxed);
+        Self(daemon.clone())
+    }
+}
+
+impl Drop for ConnectedGuard {
+    fn drop(&mut self) {
+        self.0
+            .metrics()
+            .push_connected
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,448 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextPrepareRequest, NextRequest, NextSubscribe,
+};
+use async_graphql::{Pos, Request, Response, ServerError, ServerResult};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::auth::Principal;
+use crate::errors::{graphql_error, ErrorCode};
+use crate::metrics::escape_label;
+
+// ========================
+// USAGE QUOTAS
+// ========================
+
+const HOUR_SECS: i64 = 3600;
+const DAY_SECS: i64 = 86_400;
+
+// Who usage is charged to: the subject of the public listener's token, or
+// "anonymous" when that listener is open
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ApiKey(pub Arc<str>);
+
+impl ApiKey {
+    pub fn from_principal(principal: Option<&Principal>) -> Self {
+        Self(principal.map_or("anonymous", |principal| principal.subject.as_str()).into())
+    }
+}
+
+// Unset means unlimited
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct Limits {
+    pub queries: Option<u64>,
+    // Subscription events delivered over websockets
+    pub messages: Option<u64>,
+    // Serialized responses and events
+    pub bytes: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct KeyLimits {
+    #[serde(default)]
+    pub hourly: Limits,
+    #[serde(default)]
+    pub daily: Limits,
+}
+
+#[derive(Default, Deserialize)]
+struct QuotaFile {
+    // For keys not listed under `keys`
+    #[serde(default)]
+    default: KeyLimits,
+    #[serde(default)]
+    keys: HashMap<String, KeyLimits>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, ToSchema)]
+pub struct Counts {
+    pub queries: u64,
+    pub messages: u64,
+    pub bytes: u64,
+}
+
+impl Counts {
+    fn add(&mut self, queries: u64, messages: u64, bytes: u64) {
+        self.queries += queries;
+        self.messages += messages;
+        self.bytes += bytes;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowUsage {
+    pub start: DateTime<Utc>,
+    pub resets_at: DateTime<Utc>,
+    pub used: Counts,
+    pub limits: Limits,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct UsageReport {
+    pub key: String,
+    pub hourly: WindowUsage,
+    pub daily: WindowUsage,
+    // Since the daemon started
+    pub total: Counts,
+    // Queries refused and subscriptions cut off for being over quota
+    pub rejected: u64,
+}
+
+// Fixed windows aligned to the UTC hour and day
+struct Window {
+    start: i64,
+    used: Counts,
+}
+
+impl Window {
+    fn roll(&mut self, now: i64, len: i64) {
+        let start = now - now.rem_euclid(len);
+        if start != self.start {
+            self.start = start;
+            self.used = Counts::default();
+        }
+    }
+
+    fn usage(&self, len: i64, limits: Limits) -> WindowUsage {
+        WindowUsage {
+            start: DateTime::from_timestamp(self.start, 0).unwrap_or_default(),
+            resets_at: DateTime::from_timestamp(self.start + len, 0).unwrap_or_default(),
+            used: self.used,
+            limits,
+        }
+    }
+}
+
+struct KeyUsage {
+    hourly: Window,
+    daily: Window,
+    total: Counts,
+    rejected: u64,
+}
+
+impl KeyUsage {
+    // The first limit already used up; queries only count against new requests
+    fn exhausted(&self, limits: &KeyLimits, key: &ApiKey, admitting: bool) -> Option<QuotaExceeded> {
+        [
+            ("hourly", &self.hourly, HOUR_SECS, &limits.hourly),
+            ("daily", &self.daily, DAY_SECS, &limits.daily),
+        ]
+        .into_iter()
+        .find_map(|(window, usage, len, limits)| {
+            let (kind, limit) = [
+                ("queries", limits.queries, usage.used.queries, admitting),
+                ("messages", limits.messages, usage.used.messages, true),
+                ("bytes", limits.bytes, usage.used.bytes, true),
+            ]
+            .into_iter()
+            .find_map(|(kind, limit, used, checked)| limit.filter(|limit| checked && used >= *limit).map(|limit| (kind, limit)))?;
+            Some(QuotaExceeded {
+                key: key.0.to_string(),
+                window,
+                kind,
+                limit,
+                resets_at: DateTime::from_timestamp(usage.start + len, 0).unwrap_or_default(),
+            })
+        })
+    }
+
+    fn add(&mut self, queries: u64, messages: u64, bytes: u64) {
+        self.hourly.used.add(queries, messages, bytes);
+        self.daily.used.add(queries, messages, bytes);
+        self.total.add(queries, messages, bytes);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct QuotaExceeded {
+    pub key: String,
+    pub window: &'static str,
+    pub kind: &'static str,
+    pub limit: u64,
+    pub resets_at: DateTime<Utc>,
+}
+
+impl QuotaExceeded {
+    fn into_server_error(self) -> ServerError {
+        let mut error = graphql_error(
+            ErrorCode::QuotaExceeded,
+            format!("{} {} quota of {} used up for {}", self.window, self.kind, self.limit, self.key),
+            Some(serde_json::json!({
+                "key": self.key,
+                "window": self.window,
+                "kind": self.kind,
+                "limit": self.limit,
+                "resetsAt": self.resets_at,
+            })),
+        )
+        .into_server_error(Pos::default());
+        // Refused before parsing, so there is no position to point at
+        error.locations.clear();
+        error
+    }
+}
+
+// Per-key usage of the GraphQL API, on the POST endpoint and over websockets,
+// against hourly and daily limits from QUOTAS_FILE:
+//   {"default": {"hourly": {"queries": 1000}},
+//    "keys": {"team-a": {"daily": {"messages": 50000, "bytes": 100000000}}}}
+// A key listed under `keys` gets only its own limits, not the default's.
+// Usage is tracked for every key, with or without limits. A key over its
+// query quota gets QUOTA_EXCEEDED for new operations until the window resets;
+// one over its message or byte quota also has its subscriptions ended.
+pub struct Quotas {
+    defaults: KeyLimits,
+    keys: HashMap<String, KeyLimits>,
+    usage: DashMap<ApiKey, KeyUsage>,
+}
+
+impl Quotas {
+    pub fn from_env() -> Result<Self> {
+        let Ok(path) = std::env::var("QUOTAS_FILE") else {
+            return Ok(Self::unlimited());
+        };
+        let raw = std::fs::read_to_string(&path).with_context(|| format!("Failed to read quotas {path}"))?;
+        let file: QuotaFile = serde_json::from_str(&raw).with_context(|| format!("Invalid quotas {path}"))?;
+        info!("🎟️ Daemon: Loaded quotas for {} keys from {}", file.keys.len(), path);
+        Ok(Self {
+            defaults: file.default,
+            keys: file.keys,
+            usage: DashMap::new(),
+        })
+    }
+
+    fn unlimited() -> Self {
+        Self {
+            defaults: KeyLimits::default(),
+            keys: HashMap::new(),
+            usage: DashMap::new(),
+        }
+    }
+
+    fn limits(&self, key: &ApiKey) -> &KeyLimits {
+        self.keys.get(&*key.0).unwrap_or(&self.defaults)
+    }
+
+    fn with_usage<T>(&self, key: &ApiKey, f: impl FnOnce(&mut KeyUsage) -> T) -> T {
+        let now = Utc::now().timestamp();
+        let mut usage = self.usage.entry(key.clone()).or_insert_with(|| KeyUsage {
+            hourly: Window { start: 0, used: Counts::default() },
+            daily: Window { start: 0, used: Counts::default() },
+            total: Counts::default(),
+            rejected: 0,
+        });
+        usage.hourly.roll(now, HOUR_SECS);
+        usage.daily.roll(now, DAY_SECS);
+        f(&mut usage)
+    }
+
+    // Charges one query, unless a quota is already used up
+    pub fn admit(&self, key: &ApiKey) -> Result<(), QuotaExceeded> {
+        let limits = self.limits(key);
+        self.with_usage(key, |usage| match usage.exhausted(limits, key, true) {
+            Some(exceeded) => {
+                usage.rejected += 1;
+                warn!(
+                    "🎟️ Daemon: Refused {}: {} {} quota of {} used up",
+                    key.0, exceeded.window, exceeded.kind, exceeded.limit
+                );
+                Err(exceeded)
+            }
+            None => {
+                usage.add(1, 0, 0);
+                Ok(())
+            }
+        })
+    }
+
+    // Charges one subscription event, unless messages or bytes are used up
+    pub fn deliver(&self, key: &ApiKey, bytes: u64) -> Result<(), QuotaExceeded> {
+        let limits = self.limits(key);
+        self.with_usage(key, |usage| match usage.exhausted(limits, key, false) {
+            Some(exceeded) => {
+                usage.rejected += 1;
+                warn!(
+                    "🎟️ Daemon: Ending subscription of {}: {} {} quota of {} used up",
+                    key.0, exceeded.window, exceeded.kind, exceeded.limit
+                );
+                Err(exceeded)
+            }
+            None => {
+                usage.add(0, 1, bytes);
+                Ok(())
+            }
+        })
+    }
+
+    // Bytes of an admitted response; it was already paid for, so never refused
+    pub fn record_bytes(&self, key: &ApiKey, bytes: u64) {
+        self.with_usage(key, |usage| usage.add(0, 0, bytes));
+    }
+
+    pub fn report(&self, key: Option<&str>) -> Vec<UsageReport> {
+        let keys: Vec<ApiKey> = self
+            .usage
+            .iter()
+            .map(|entry| entry.key().clone())
+            .filter(|candidate| key.is_none_or(|key| &*candidate.0 == key))
+            .collect();
+        let mut reports: Vec<UsageReport> = keys
+            .into_iter()
+            .map(|key| {
+                let limits = *self.limits(&key);
+                self.with_usage(&key, |usage| UsageReport {
+                    key: key.0.to_string(),
+                    hourly: usage.hourly.usage(HOUR_SECS, limits.hourly),
+                    daily: usage.daily.usage(DAY_SECS, limits.daily),
+                    total: usage.total,
+                    rejected: usage.rejected,
+                })
+            })
+            .collect();
+        reports.sort_by(|a, b| a.key.cmp(&b.key));
+        reports
+    }
+
+    pub fn render(&self, out: &mut String) {
+        let reports = self.report(None);
+        out.push_str("# HELP daemon_quota_used Usage per key in the current window.\n");
+        out.push_str("# TYPE daemon_quota_used gauge\n");
+        for report in &reports {
+            let key = escape_label(&report.key);
+            for (window, usage) in [("hourly", &report.hourly), ("daily", &report.daily)] {
+                for (kind, used) in [
+                    ("queries", usage.used.queries),
+                    ("messages", usage.used.messages),
+                    ("bytes", usage.used.bytes),
+                ] {
+                    let _ = writeln!(
+                        out,
+                        "daemon_quota_used{{key=\"{key}\",window=\"{window}\",kind=\"{kind}\"}} {used}"
+                    );
+                }
+            }
+        }
+        out.push_str("# HELP daemon_quota_rejections_total Operations refused or ended for being over quota.\n");
+        out.push_str("# TYPE daemon_quota_rejections_total counter\n");
+        for report in &reports {
+            let _ = writeln!(
+                out,
+                "daemon_quota_rejections_total{{key=\"{}\"}} {}",
+                escape_label(&report.key),
+                report.rejected
+            );
+        }
+    }
+}
+
+// Serialized size without building the JSON
+fn response_size(response: &Response) -> u64 {
+    struct Counter(u64);
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len() as u64;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut counter = Counter(0);
+    let _ = serde_json::to_writer(&mut counter, response);
+    counter.0
+}
+
+pub struct QuotaEnforcement {
+    quotas: Arc<Quotas>,
+}
+
+impl QuotaEnforcement {
+    pub fn new(quotas: Arc<Quotas>) -> Self {
+        Self { quotas }
+    }
+}
+
+impl ExtensionFactory for QuotaEnforcement {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(QuotaExtension {
+            quotas: self.quotas.clone(),
+            admitted: Arc::new(Mutex::new(None)),
+        })
+    }
+}
+
+struct QuotaExtension {
+    quotas: Arc<Quotas>,
+    // Set once the operation is admitted; only admitted ones are charged bytes
+    admitted: Arc<Mutex<Option<ApiKey>>>,
+}
+
+#[async_trait::async_trait]
+impl Extension for QuotaExtension {
+    // POST requests carry the key in request data, websocket operations in
+    // their connection's data
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        let key = request
+            .data
+            .get(&TypeId::of::<ApiKey>())
+            .and_then(|data| data.downcast_ref::<ApiKey>())
+            .or_else(|| ctx.data_opt::<ApiKey>())
+            .cloned()
+            .unwrap_or_else(|| ApiKey::from_principal(None));
+        self.quotas.admit(&key).map_err(QuotaExceeded::into_server_error)?;
+        *self.admitted.lock().unwrap() = Some(key);
+        next.run(ctx, request).await
+    }
+
+    // Only POST requests come through here; websocket ones are streams
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let response = next.run(ctx).await;
+        let admitted = self.admitted.lock().unwrap().clone();
+        if let Some(key) = admitted {
+            self.quotas.record_bytes(&key, response_size(&response));
+        }
+        response
+    }
+
+    fn subscribe<'s>(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        stream: BoxStream<'s, Response>,
+        next: NextSubscribe<'_>,
+    ) -> BoxStream<'s, Response> {
+        let quotas = self.quotas.clone();
+        let admitted = self.admitted.clone();
+        let mut stream = next.run(ctx, stream);
+        async_stream::stream! {
+            while let Some(response) = stream.next().await {
+                let key = admitted.lock().unwrap().clone();
+                if let Some(key) = key {
+                    if let Err(exceeded) = quotas.deliver(&key, response_size(&response)) {
+                        yield Response::from_errors(vec![exceeded.into_server_error()]);
+                        break;
+                    }
+                }
+                yield response;
+            }
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,251 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use tracing::info;
+
+use crate::ComponentType;
+
+// ========================
+// PII REDACTION
+// ========================
+
+const REDACTED: &str = "[REDACTED]";
+
+// Rules file shape:
+// {
+//   "paths": { "*": ["/data/email"], "FORM": ["/data/fields/*/value"] },
+//   "patterns": { "*": ["[\\w.+-]+@[\\w-]+\\.[\\w.]+"] }
+// }
+// Paths are JSON pointers into the component object, where a `*` segment
+// matches every key or index. Patterns are applied to every string value.
+#[derive(Debug, Default, Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    paths: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    patterns: HashMap<String, Vec<String>>,
+}
+
+#[derive(Default)]
+struct Rules {
+    paths: Vec<Vec<String>>,
+    patterns: Vec<Regex>,
+}
+
+impl Rules {
+    fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.patterns.is_empty()
+    }
+}
+
+// Applied to payloads before they are logged, exported or dead-lettered.
+// Stored components and the renderer-facing APIs are left untouched.
+#[derive(Default)]
+pub struct Redactor {
+    // Rules under "*" apply to every component type
+    all: Rules,
+    per_type: HashMap<ComponentType, Rules>,
+}
+
+impl Redactor {
+    // REDACTION_RULES_PATH points at a JSON rules file; without it nothing is
+    // redacted.
+    pub fn from_env() -> Result<Self> {
+        let Ok(path) = std::env::var("REDACTION_RULES_PATH") else {
+            return Ok(Self::default());
+        };
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read redaction rules {path}"))?;
+        let file: RulesFile = serde_json::from_str(&raw)
+            .with_context(|| format!("Invalid redaction rules {path}"))?;
+
+        let mut redactor = Self::default();
+        for (scope, pointers) in file.paths {
+            let rules = redactor.rules_mut(&scope)?;
+            for pointer in pointers {
+                if !pointer.starts_with('/') {
+                    return Err(anyhow!("Redaction path '{pointer}' must be a JSON pointer"));
+                }
+                rules.paths.push(
+                    pointer[1..]
+                        .split('/')
+                        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+                        .collect(),
+                );
+            }
+        }
+        for (scope, patterns) in file.patterns {
+            let rules = redactor.rules_mut(&scope)?;
+            for pattern in patterns {
+                rules.patterns.push(
+                    Regex::new(&pattern)
+                        .with_context(|| format!("Invalid redaction pattern '{pattern}'"))?,
+                );
+            }
+        }
+
+        info!(
+            "🙈 Daemon: Loaded redaction rules from {} ({} type-specific scope(s))",
+            path,
+            redactor.per_type.len()
+        );
+        Ok(redactor)
+    }
+
+    // Fail-closed fallback for unreadable rules: every string is redacted.
+    pub fn redact_all() -> Self {
+        Self {
+            all: Rules {
+                paths: Vec::new(),
+                patterns: vec![Regex::new("(?s).+").expect("static pattern")],
+            },
+            per_type: HashMap::new(),
+        }
+    }
+
+    fn rules_mut(&mut self, scope: &str) -> Result<&mut Rules> {
+        if scope == "*" {
+            return Ok(&mut self.all);
+        }
+        let r#type = serde_json::from_value::<ComponentType>(serde_json::json!(scope))
+            .map_err(|_| anyhow!("Unknown component type '{scope}' in redaction rules"))?;
+        Ok(self.per_type.entry(r#type).or_default())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.all.is_empty() || self.per_type.values().any(|rules| !rules.is_empty())
+    }
+
+    // Redacts a raw component object, picking type rules from its `type` field.
+    pub fn redact_component(&self, component: &serde_json::Value) -> serde_json::Value {
+        let mut redacted = component.clone();
+        let type_rules = component
+            .get("type")
+            .and_then(|t| serde_json::from_value::<ComponentType>(t.clone()).ok())
+            .and_then(|t| self.per_type.get(&t));
+
+        for rules in std::iter::once(&self.all).chain(type_rules) {
+            for path in &rules.paths {
+                redact_path(&mut redacted, path);
+            }
+            for pattern in &rules.patterns {
+                redact_strings(&mut redacted, pattern);
+            }
+        }
+        redacted
+    }
+
+    // Redacts submitted form values. They are matched as `/values/...` of a
+    // FORM object, e.g. a path rule "/values/email" under "FORM" or "*".
+    pub fn redact_submission(&self, values: &serde_json::Value) -> serde_json::Value {
+        let wrapped = serde_json::json!({ "type": ComponentType::Form, "values": values });
+        let mut redacted = self.redact_component(&wrapped);
+        redacted["values"].take()
+    }
+
+    // Redacts an outbound event such as an interaction: its `payload` like
+    // submitted values, then the global patterns over the rest.
+    pub fn redact_event(&self, event: &serde_json::Value) -> serde_json::Value {
+        let mut redacted = event.clone();
+        if let Some(payload) = redacted.get_mut("payload") {
+            *payload = self.redact_submission(payload);
+        }
+        for pattern in &self.all.patterns {
+            redact_strings(&mut redacted, pattern);
+        }
+        redacted
+    }
+
+    // Redacts GraphQL mutation arguments for the audit log. `components`
+    // items and the top level go through the component rules, `values` and
+    // `payload` through the submission rules.
+    pub fn redact_arguments(&self, arguments: &serde_json::Value) -> serde_json::Value {
+        let mut redacted = self.redact_component(arguments);
+        if let Some(serde_json::Value::Array(components)) = redacted.get_mut("components") {
+            for component in components.iter_mut() {
+                *component = self.redact_component(component);
+            }
+        }
+        for key in ["values", "payload"] {
+            if let Some(values) = redacted.get_mut(key) {
+                *values = self.redact_submission(values);
+            }
+        }
+        redacted
+    }
+
+    // Redacts free text such as validation messages with the global patterns.
+    pub fn redact_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for pattern in &self.all.patterns {
+            if let Cow::Owned(replaced) = pattern.replace_all(&text, REDACTED) {
+                text = Cow::Owned(replaced);
+            }
+        }
+        text
+    }
+
+    // Redacts a raw registry frame, applying component rules to the
+    // `componentUpdate` it carries.
+    pub fn redact_message<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if !self.is_enabled() {
+            return Cow::Borrowed(text);
+        }
+        let Ok(mut message) = serde_json::from_str::<serde_json::Value>(text) else {
+            return self.redact_text(text);
+        };
+        if let Some(update) = message.pointer_mut("/payload/data/componentUpdate") {
+            *update = self.redact_component(update);
+        }
+        for pattern in &self.all.patterns {
+            redact_strings(&mut message, pattern);
+        }
+        Cow::Owned(message.to_string())
+    }
+}
+
+fn redact_path(value: &mut serde_json::Value, path: &[String]) {
+    use serde_json::Value;
+
+    let Some((segment, rest)) = path.split_first() else {
+        *value = Value::String(REDACTED.to_string());
+        return;
+    };
+    match value {
+        Value::Object(map) if segment == "*" => {
+            map.values_mut().for_each(|child| redact_path(child, rest));
+        }
+        Value::Object(map) => {
+            if let Some(child) = map.get_mut(segment) {
+                redact_path(child, rest);
+            }
+        }
+        Value::Array(items) if segment == "*" => {
+            items.iter_mut().for_each(|child| redact_path(child, rest));
+        }
+        Value::Array(items) => {
+            if let Some(child) = segment.parse::<usize>().ok().and_then(|i| items.get_mut(i)) {
+                redact_path(child, rest);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn redact_strings(value: &mut serde_json::Value, pattern: &Regex) {
+    use serde_json::Value;
+
+    match value {
+        Value::String(s) => {
+            if let Cow::Owned(replaced) = pattern.replace_all(s, REDACTED) {
+                *s = replaced;
+            }
+        }
+        Value::Object(map) => map.values_mut().for_each(|child| redact_strings(child, pattern)),
+        Value::Array(items) => items.iter_mut().for_each(|child| redact_strings(child, pattern)),
+        _ => {}
+    }
+}
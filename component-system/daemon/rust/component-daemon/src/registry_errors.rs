@@ -0,0 +1,313 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_graphql::{Enum, SimpleObject};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::{error, info, warn};
+
+use crate::config::env_parse;
+
+// ========================
+// REGISTRY ERRORS
+// ========================
+
+pub const DEFAULT_SUBSCRIPTION_QUERY: &str = "subscription { componentUpdate { id type data createdAt } }";
+
+// Fields the daemon can't do without; an error about one of these can't be
+// fixed by dropping it from the subscription
+const REQUIRED_FIELDS: [&str; 4] = ["componentUpdate", "id", "type", "data"];
+
+// Auth failures in a row, without a component in between, before the daemon
+// stops reconnecting right away and backs off
+const MAX_REAUTH_ATTEMPTS: u32 = 3;
+
+#[derive(Clone, Copy, Debug, Serialize, Enum, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RegistryErrorKind {
+    AuthExpired,
+    SubscriptionInvalid,
+    ServerRestarting,
+    Other,
+}
+
+impl RegistryErrorKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RegistryErrorKind::AuthExpired => "auth_expired",
+            RegistryErrorKind::SubscriptionInvalid => "subscription_invalid",
+            RegistryErrorKind::ServerRestarting => "server_restarting",
+            RegistryErrorKind::Other => "other",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistryError {
+    pub kind: RegistryErrorKind,
+    pub message: String,
+    // `extensions.code` of the first error, when the registry sent one
+    pub code: Option<String>,
+    // What the daemon did about it
+    pub reaction: String,
+    pub at: DateTime<Utc>,
+}
+
+// What the registry connection should do next
+#[derive(Debug, PartialEq, Eq)]
+pub enum Reaction {
+    // Reload credentials and reconnect
+    Reauthenticate,
+    // Stop and restart the subscription with this query
+    Resubscribe(String),
+    // Drop the connection and wait this long before reconnecting
+    BackOff(Duration),
+    Ignore,
+}
+
+// Turns `error`, `connection_error` and payload `errors` from the registry
+// into a reaction instead of only a log line. REGISTRY_AUTH_TOKEN, or
+// REGISTRY_AUTH_TOKEN_FILE (re-read on every re-authentication so rotated
+// tokens are picked up), is sent in connection_init. A server that says it is
+// restarting is given REGISTRY_RESTART_BACKOFF_SECS (default 15) unless it
+// sends `extensions.retryAfter`; errors that can't be fixed here back off
+// for REGISTRY_ERROR_BACKOFF_SECS (default 30).
+pub struct RegistryErrors {
+    token_file: Option<String>,
+    token: Mutex<Option<String>>,
+    query: Mutex<String>,
+    restart_backoff: Duration,
+    error_backoff: Duration,
+    auth_failures: AtomicU32,
+    // Set by a reaction, taken by the connection loop
+    reconnect: AtomicBool,
+    backoff: Mutex<Option<Duration>>,
+    last: Mutex<Option<RegistryError>>,
+    counts: DashMap<RegistryErrorKind, u64>,
+}
+
+impl RegistryErrors {
+    pub fn from_env() -> Self {
+        let token_file = std::env::var("REGISTRY_AUTH_TOKEN_FILE").ok();
+        let errors = Self {
+            token_file,
+            token: Mutex::new(None),
+            query: Mutex::new(
+                std::env::var("REGISTRY_SUBSCRIPTION_QUERY")
+                    .unwrap_or_else(|_| DEFAULT_SUBSCRIPTION_QUERY.to_string()),
+            ),
+            restart_backoff: Duration::from_secs(env_parse("REGISTRY_RESTART_BACKOFF_SECS", 15)),
+            error_backoff: Duration::from_secs(env_parse("REGISTRY_ERROR_BACKOFF_SECS", 30)),
+            auth_failures: AtomicU32::new(0),
+            reconnect: AtomicBool::new(false),
+            backoff: Mutex::new(None),
+            last: Mutex::new(None),
+            counts: DashMap::new(),
+        };
+        errors.reload_token();
+        errors
+    }
+
+    // The file wins over the variable, so a rotated token replaces a stale one
+    fn reload_token(&self) {
+        let token = match &self.token_file {
+            Some(path) => match std::fs::read_to_string(path) {
+                Ok(token) => Some(token.trim().to_string()),
+                Err(e) => {
+                    error!("❌ Daemon: Failed to read registry token {}: {}", path, e);
+                    None
+                }
+            },
+            None => std::env::var("REGISTRY_AUTH_TOKEN").ok(),
+        };
+        *self.token.lock().unwrap() = token.filter(|token| !token.is_empty());
+    }
+
+    pub fn authorization(&self) -> Option<String> {
+        self.token.lock().unwrap().as_ref().map(|token| format!("Bearer {token}"))
+    }
+
+    pub fn connection_init(&self) -> Value {
+        match self.authorization() {
+            Some(authorization) => serde_json::json!({
+                "type": "connection_init",
+                "payload": { "authorization": authorization },
+            }),
+            None => serde_json::json!({ "type": "connection_init" }),
+        }
+    }
+
+    pub fn query(&self) -> String {
+        self.query.lock().unwrap().clone()
+    }
+
+    // A component arrived, so credentials and subscription are fine
+    pub fn succeeded(&self) {
+        self.auth_failures.store(0, Ordering::Relaxed);
+    }
+
+    pub fn take_reconnect(&self) -> bool {
+        self.reconnect.swap(false, Ordering::Relaxed)
+    }
+
+    // Longer than the usual reconnect wait, when a reaction asked for one
+    pub fn take_backoff(&self) -> Option<Duration> {
+        self.backoff.lock().unwrap().take()
+    }
+
+    pub fn last(&self) -> Option<RegistryError> {
+        self.last.lock().unwrap().clone()
+    }
+
+    // `errors` is a GraphQL error list, a single error object or a string
+    pub fn handle(&self, errors: &Value) -> Reaction {
+        let list = match errors {
+            Value::Array(list) => list.clone(),
+            other => vec![other.clone()],
+        };
+        let kind = classify(&list);
+        let message = list.iter().map(message_of).collect::<Vec<_>>().join("; ");
+        let code = list
+            .first()
+            .and_then(|error| error["extensions"]["code"].as_str())
+            .map(str::to_string);
+
+        let reaction = match kind {
+            RegistryErrorKind::AuthExpired => {
+                if self.auth_failures.fetch_add(1, Ordering::Relaxed) + 1 >= MAX_REAUTH_ATTEMPTS {
+                    self.auth_failures.store(0, Ordering::Relaxed);
+                    Reaction::BackOff(self.error_backoff)
+                } else {
+                    self.reload_token();
+                    Reaction::Reauthenticate
+                }
+            }
+            RegistryErrorKind::SubscriptionInvalid => {
+                let mut query = self.query.lock().unwrap();
+                match corrected(&query, &list) {
+                    Some(fixed) => {
+                        *query = fixed.clone();
+                        Reaction::Resubscribe(fixed)
+                    }
+                    None => Reaction::BackOff(self.error_backoff),
+                }
+            }
+            RegistryErrorKind::ServerRestarting => {
+                let retry_after = list
+                    .iter()
+                    .find_map(|error| error["extensions"]["retryAfter"].as_u64())
+                    .map(Duration::from_secs);
+                Reaction::BackOff(retry_after.unwrap_or(self.restart_backoff))
+            }
+            RegistryErrorKind::Other => Reaction::Ignore,
+        };
+
+        let described = match &reaction {
+            Reaction::Reauthenticate => "reauthenticating".to_string(),
+            Reaction::Resubscribe(query) => format!("resubscribed with {query}"),
+            Reaction::BackOff(wait) => format!("backing off for {wait:?}"),
+            Reaction::Ignore => "logged".to_string(),
+        };
+        match kind {
+            RegistryErrorKind::Other => error!("❌ Daemon: GraphQL error from registry: {}", message),
+            _ => warn!("⚠️ Daemon: Registry error ({}), {}: {}", kind.label(), described, message),
+        }
+        match &reaction {
+            Reaction::Reauthenticate => self.reconnect.store(true, Ordering::Relaxed),
+            Reaction::BackOff(wait) => {
+                *self.backoff.lock().unwrap() = Some(*wait);
+                self.reconnect.store(true, Ordering::Relaxed);
+            }
+            Reaction::Resubscribe(_) | Reaction::Ignore => {}
+        }
+
+        *self.counts.entry(kind).or_insert(0) += 1;
+        *self.last.lock().unwrap() = Some(RegistryError {
+            kind,
+            message,
+            code,
+            reaction: described,
+            at: Utc::now(),
+        });
+        reaction
+    }
+
+    pub fn render(&self, out: &mut String) {
+        out.push_str("# HELP daemon_registry_errors_total GraphQL errors from the registry by kind.\n");
+        out.push_str("# TYPE daemon_registry_errors_total counter\n");
+        let mut counts: Vec<(&str, u64)> = self.counts.iter().map(|entry| (entry.key().label(), *entry.value())).collect();
+        counts.sort();
+        for (kind, count) in counts {
+            let _ = writeln!(out, "daemon_registry_errors_total{{kind=\"{}\"}} {}", kind, count);
+        }
+    }
+}
+
+fn message_of(error: &Value) -> String {
+    match error {
+        Value::String(message) => message.clone(),
+        error => error["message"].as_str().map(str::to_string).unwrap_or_else(|| error.to_string()),
+    }
+}
+
+// By `extensions.code` where the registry sends one, otherwise by message.
+// Auth comes first: a restarting server may also reject stale sessions.
+fn classify(errors: &[Value]) -> RegistryErrorKind {
+    let matches = |codes: &[&str], phrases: &[&str]| {
+        errors.iter().any(|error| {
+            let code = error["extensions"]["code"].as_str().unwrap_or_default();
+            let message = message_of(error).to_lowercase();
+            codes.contains(&code) || phrases.iter().any(|phrase| message.contains(phrase))
+        })
+    };
+    if matches(
+        &["UNAUTHENTICATED", "AUTH_EXPIRED", "TOKEN_EXPIRED", "INVALID_TOKEN"],
+        &["token expired", "jwt expired", "session expired", "unauthenticated", "unauthorized", "not authenticated"],
+    ) {
+        RegistryErrorKind::AuthExpired
+    } else if matches(
+        &["SERVER_RESTARTING", "SERVICE_UNAVAILABLE", "UNAVAILABLE", "SHUTTING_DOWN"],
+        &["restarting", "shutting down", "temporarily unavailable"],
+    ) {
+        RegistryErrorKind::ServerRestarting
+    } else if matches(
+        &["GRAPHQL_VALIDATION_FAILED", "GRAPHQL_PARSE_FAILED", "BAD_USER_INPUT"],
+        &["cannot query field", "unknown argument", "syntax error"],
+    ) {
+        RegistryErrorKind::SubscriptionInvalid
+    } else {
+        RegistryErrorKind::Other
+    }
+}
+
+// Drops the fields named in `Cannot query field "x"` errors from the
+// selection. None when nothing can be dropped, e.g. the field is required.
+fn corrected(query: &str, errors: &[Value]) -> Option<String> {
+    let unknown: Vec<String> = errors
+        .iter()
+        .filter_map(|error| {
+            let message = message_of(error);
+            let rest = message.strip_prefix("Cannot query field \"")?;
+            Some(rest[..rest.find('"')?].to_string())
+        })
+        .collect();
+    if unknown.is_empty() || unknown.iter().any(|field| REQUIRED_FIELDS.contains(&field.as_str())) {
+        return None;
+    }
+    // Braces are split off so `createdAt}` still matches
+    let spaced = query.replace('{', " { ").replace('}', " } ");
+    let kept: Vec<&str> = spaced
+        .split_whitespace()
+        .filter(|token| !unknown.iter().any(|field| field == token))
+        .collect();
+    let fixed = kept.join(" ");
+    (fixed != spaced.split_whitespace().collect::<Vec<_>>().join(" ")).then(|| {
+        info!("🩹 Daemon: Dropping {} from the registry subscription", unknown.join(", "));
+        fixed
+    })
+}
@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{sleep, timeout};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message as ClientMessage;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+use warp::ws::{Message, WebSocket, Ws};
+use warp::Filter;
+
+use crate::channels::ComponentChange;
+use crate::config::env_parse;
+use crate::{Component, ComponentDaemon, ComponentRemoval};
+
+// ========================
+// HOT STANDBY REPLICATION
+// ========================
+
+// A follower started with REPLICATE_FROM (the primary's
+// ws://host:port/replication) doesn't talk to the registry. It takes a full
+// snapshot from the primary, then every upsert and removal, keeping the
+// primary's versions and seqs so renderers can resume on it with the cursor
+// they had. REPLICATE_TOKEN is sent as the bearer token, since the endpoint
+// sits behind the admin listener and its auth. Once the primary has been
+// unreachable for REPLICATE_PROMOTE_SECS (default 15, 0 = only by hand), the
+// follower promotes itself and connects to the registry, provided it has
+// been warm at least once. Blobs aren't
+// replicated; a follower refetches attachments from their origin.
+pub struct Replication {
+    source: Option<String>,
+    token: Option<String>,
+    promote_after: Option<Duration>,
+    heartbeat: Duration,
+    reconnect_delay: Duration,
+    following: AtomicBool,
+    // The follower holds a complete snapshot
+    warm: AtomicBool,
+    connected: AtomicBool,
+    // Followers attached to this daemon as primary
+    followers: AtomicU64,
+    applied: AtomicU64,
+    last_message_at: Mutex<Option<DateTime<Utc>>>,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicationStatus {
+    // "primary" or "follower"
+    pub role: &'static str,
+    pub source: Option<String>,
+    pub connected: bool,
+    pub warm: bool,
+    pub followers: u64,
+    // Upserts and removals applied from the primary
+    pub applied: u64,
+    pub last_message_at: Option<DateTime<Utc>>,
+}
+
+// Sent by the primary, one JSON text frame each
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Frame {
+    Snapshot {
+        #[serde(rename = "latestSeq")]
+        latest_seq: u64,
+        components: Vec<Component>,
+    },
+    Upsert {
+        component: Component,
+    },
+    Removed {
+        removal: ComponentRemoval,
+    },
+    Heartbeat {
+        #[serde(rename = "latestSeq")]
+        latest_seq: u64,
+    },
+}
+
+impl Replication {
+    pub fn from_env() -> Self {
+        let source = std::env::var("REPLICATE_FROM").ok().filter(|url| !url.trim().is_empty());
+        let promote_after = env_parse("REPLICATE_PROMOTE_SECS", 15u64);
+        Self {
+            following: AtomicBool::new(source.is_some()),
+            source,
+            token: std::env::var("REPLICATE_TOKEN").ok(),
+            promote_after: (promote_after > 0).then(|| Duration::from_secs(promote_after)),
+            heartbeat: Duration::from_secs(env_parse("REPLICATION_HEARTBEAT_SECS", 5u64).max(1)),
+            reconnect_delay: Duration::from_secs(env_parse("REPLICATE_RECONNECT_SECS", 2)),
+            warm: AtomicBool::new(false),
+            connected: AtomicBool::new(false),
+            followers: AtomicU64::new(0),
+            applied: AtomicU64::new(0),
+            last_message_at: Mutex::new(None),
+        }
+    }
+
+    pub fn is_following(&self) -> bool {
+        self.following.load(Ordering::Relaxed)
+    }
+
+    // A follower isn't ready to take renderers until its first snapshot
+    pub fn is_warm(&self) -> bool {
+        !self.is_following() || self.warm.load(Ordering::Relaxed)
+    }
+
+    pub fn status(&self) -> ReplicationStatus {
+        ReplicationStatus {
+            role: if self.is_following() { "follower" } else { "primary" },
+            source: self.source.clone(),
+            connected: self.connected.load(Ordering::Relaxed),
+            warm: self.warm.load(Ordering::Relaxed),
+            followers: self.followers.load(Ordering::Relaxed),
+            applied: self.applied.load(Ordering::Relaxed),
+            last_message_at: *self.last_message_at.lock().unwrap(),
+        }
+    }
+
+    // Stops following; false if this daemon already was a primary
+    fn take_over(&self) -> bool {
+        self.following.swap(false, Ordering::Relaxed)
+    }
+
+    pub fn render(&self, out: &mut String) {
+        out.push_str("# HELP daemon_replication_following Whether this daemon is a hot standby follower.\n");
+        out.push_str("# TYPE daemon_replication_following gauge\n");
+        let _ = writeln!(out, "daemon_replication_following {}", u8::from(self.is_following()));
+        out.push_str("# HELP daemon_replication_connected Whether the follower's stream from the primary is up.\n");
+        out.push_str("# TYPE daemon_replication_connected gauge\n");
+        let _ = writeln!(out, "daemon_replication_connected {}", u8::from(self.connected.load(Ordering::Relaxed)));
+        out.push_str("# HELP daemon_replication_followers Followers streaming from this daemon.\n");
+        out.push_str("# TYPE daemon_replication_followers gauge\n");
+        let _ = writeln!(out, "daemon_replication_followers {}", self.followers.load(Ordering::Relaxed));
+        out.push_str("# HELP daemon_replication_applied_total Changes applied from the primary.\n");
+        out.push_str("# TYPE daemon_replication_applied_total counter\n");
+        let _ = writeln!(out, "daemon_replication_applied_total {}", self.applied.load(Ordering::Relaxed));
+    }
+}
+
+// Promotes a follower now, e.g. when the load balancer already failed over
+pub fn promote(daemon: &ComponentDaemon, reason: &str) -> bool {
+    if !daemon.replication().take_over() {
+        return false;
+    }
+    warn!("👑 Daemon: Promoted to primary ({}), connecting to the registry", reason);
+    daemon.replication().warm.store(true, Ordering::Relaxed);
+    let daemon = daemon.clone();
+    tokio::spawn(async move {
+        daemon.connect_to_registry().await;
+    });
+    true
+}
+
+// ------------------------
+// Primary side
+// ------------------------
+
+pub fn routes(daemon: ComponentDaemon) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::path!("replication")
+        .and(warp::ws())
+        .map(move |ws: Ws| {
+            let daemon = daemon.clone();
+            ws.on_upgrade(move |socket| async move {
+                info!("🪞 Daemon: Follower connected for replication");
+                daemon.replication().followers.fetch_add(1, Ordering::Relaxed);
+                if let Err(e) = stream_to_follower(&daemon, socket).await {
+                    warn!("🪞 Daemon: Replication stream ended: {:#}", e);
+                }
+                daemon.replication().followers.fetch_sub(1, Ordering::Relaxed);
+                info!("🪞 Daemon: Follower disconnected");
+            })
+        })
+}
+
+async fn stream_to_follower(daemon: &ComponentDaemon, socket: WebSocket) -> Result<()> {
+    let (mut write, mut read) = socket.split();
+    // Subscribe before taking the snapshot so nothing falls in between
+    let mut changes = daemon.subscribe_to_changes();
+    let mut snapshot = send_snapshot(daemon, &mut write).await?;
+    let mut ticker = tokio::time::interval(daemon.replication().heartbeat);
+    ticker.tick().await;
+
+    loop {
+        let frame = tokio::select! {
+            change = changes.recv() => match change {
+                // The snapshot is read shard by shard, so only its own entry
+                // for the id says whether it already covers this upsert
+                Ok(ComponentChange::Updated(component))
+                    if snapshot.get(&component.id).is_none_or(|seq| component.seq > *seq) =>
+                {
+                    json!({ "type": "upsert", "component": component })
+                }
+                Ok(ComponentChange::Updated(_)) => continue,
+                Ok(ComponentChange::Removed(removal)) => json!({ "type": "removed", "removal": removal }),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("🪞 Daemon: Follower lagged by {} changes, resending snapshot", skipped);
+                    snapshot = send_snapshot(daemon, &mut write).await?;
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            message = read.next() => match message {
+                Some(Ok(message)) if message.is_close() => return Ok(()),
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(e.into()),
+                None => return Ok(()),
+            },
+            _ = ticker.tick() => json!({ "type": "heartbeat", "latestSeq": daemon.history().latest_seq() }),
+        };
+        write.send(Message::text(frame.to_string())).await?;
+    }
+}
+
+// Every stored component in seq order; returns the seq included for each id
+// so live updates it already covers are skipped
+async fn send_snapshot<S>(daemon: &ComponentDaemon, write: &mut S) -> Result<HashMap<String, u64>>
+where
+    S: futures_util::Sink<Message, Error = warp::Error> + Unpin,
+{
+    let latest_seq = daemon.history().latest_seq();
+    let mut components = daemon.get_components();
+    components.sort_by_key(|component| component.seq);
+    let seqs = components.iter().map(|component| (component.id.clone(), component.seq)).collect();
+    let components: Vec<&Component> = components.iter().map(|component| &**component).collect();
+    let frame = json!({ "type": "snapshot", "latestSeq": latest_seq, "components": components });
+    write.send(Message::text(frame.to_string())).await?;
+    Ok(seqs)
+}
+
+// ------------------------
+// Follower side
+// ------------------------
+
+pub fn spawn_follower(daemon: &ComponentDaemon) {
+    let Some(source) = daemon.replication().source.clone() else {
+        return;
+    };
+    let daemon = daemon.clone();
+    tokio::spawn(async move {
+        info!("🪞 Daemon: Running as hot standby of {}", source);
+        let mut last_connected = Instant::now();
+        while daemon.replication().is_following() {
+            match follow(&daemon, &source).await {
+                Ok(()) => warn!("🪞 Daemon: Primary {} closed the replication stream", source),
+                Err(e) => error!("❌ Daemon: Replication from {} failed: {:#}", source, e),
+            }
+            let replication = daemon.replication();
+            if replication.connected.swap(false, Ordering::Relaxed) {
+                last_connected = Instant::now();
+            }
+            // A follower that never got a snapshot has nothing to serve, so
+            // it keeps retrying instead of promoting with an empty store
+            if let Some(promote_after) = replication.promote_after.filter(|_| replication.warm.load(Ordering::Relaxed)) {
+                if last_connected.elapsed() >= promote_after {
+                    promote(&daemon, &format!("primary unreachable for {:?}", last_connected.elapsed()));
+                    return;
+                }
+            }
+            sleep(replication.reconnect_delay).await;
+        }
+    });
+}
+
+async fn follow(daemon: &ComponentDaemon, source: &str) -> Result<()> {
+    let replication = daemon.replication();
+    let mut request = source.into_client_request()?;
+    if let Some(token) = &replication.token {
+        request.headers_mut().insert("authorization", format!("Bearer {token}").parse()?);
+    }
+    let (socket, _) = connect_async(request).await?;
+    let (mut write, mut read) = socket.split();
+    replication.connected.store(true, Ordering::Relaxed);
+    info!("🪞 Daemon: Replicating from {}", source);
+
+    // A primary that stops sending heartbeats is treated as gone
+    let silence = replication.heartbeat * 3;
+    while replication.is_following() {
+        let message = timeout(silence, read.next())
+            .await
+            .map_err(|_| anyhow!("no heartbeat from the primary for {:?}", silence))?;
+        let text = match message {
+            Some(Ok(ClientMessage::Text(text))) => text,
+            Some(Ok(ClientMessage::Ping(data))) => {
+                write.send(ClientMessage::Pong(data)).await?;
+                continue;
+            }
+            Some(Ok(ClientMessage::Close(_))) | None => return Ok(()),
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(e.into()),
+        };
+        *replication.last_message_at.lock().unwrap() = Some(Utc::now());
+        match serde_json::from_str::<Frame>(&text)? {
+            Frame::Snapshot { latest_seq, components } => {
+                let count = components.len();
+                daemon.apply_replicated_snapshot(latest_seq, components);
+                if !replication.warm.swap(true, Ordering::Relaxed) {
+                    info!("🪞 Daemon: Warm with {} components from the primary", count);
+                }
+            }
+            Frame::Upsert { component } => {
+                daemon.apply_replicated(component);
+                replication.applied.fetch_add(1, Ordering::Relaxed);
+            }
+            Frame::Removed { removal } => {
+                daemon.remove_component(&removal.id, removal.reason);
+                replication.applied.fetch_add(1, Ordering::Relaxed);
+            }
+            Frame::Heartbeat { latest_seq } => daemon.history().advance_past(latest_seq),
+        }
+    }
+    Ok(())
+}
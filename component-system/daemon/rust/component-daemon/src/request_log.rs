@@ -0,0 +1,115 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextPrepareRequest, NextRequest,
+};
+use async_graphql::{Request, Response, ServerResult};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::metrics::Metrics;
+
+// ========================
+// REQUEST LOGGING
+// ========================
+
+pub struct RequestLog {
+    metrics: Arc<Metrics>,
+}
+
+impl RequestLog {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        Self { metrics }
+    }
+}
+
+impl ExtensionFactory for RequestLog {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(RequestLogExtension {
+            metrics: self.metrics.clone(),
+            request_id: Uuid::new_v4(),
+            state: Mutex::new(OperationState::default()),
+        })
+    }
+}
+
+#[derive(Default)]
+struct OperationState {
+    operation: Option<String>,
+    variables_bytes: usize,
+}
+
+// A fresh instance is created for every request, so per-request state lives here.
+struct RequestLogExtension {
+    metrics: Arc<Metrics>,
+    request_id: Uuid,
+    state: Mutex<OperationState>,
+}
+
+#[async_trait::async_trait]
+impl Extension for RequestLogExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let started = Instant::now();
+        let mut response = next.run(ctx).await;
+        let duration = started.elapsed();
+
+        let (operation, variables_bytes) = {
+            let state = self.state.lock().unwrap();
+            (
+                state.operation.clone().unwrap_or_else(|| "anonymous".to_string()),
+                state.variables_bytes,
+            )
+        };
+        let status = if response.is_ok() { "ok" } else { "error" };
+
+        if response.is_ok() {
+            info!(
+                "🧾 Daemon: [{}] {} ok in {:.1}ms (variables {} bytes)",
+                self.request_id,
+                operation,
+                duration.as_secs_f64() * 1000.0,
+                variables_bytes
+            );
+        } else {
+            warn!(
+                "🧾 Daemon: [{}] {} failed in {:.1}ms (variables {} bytes): {}",
+                self.request_id,
+                operation,
+                duration.as_secs_f64() * 1000.0,
+                variables_bytes,
+                response
+                    .errors
+                    .iter()
+                    .map(|e| e.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            );
+        }
+
+        self.metrics
+            .operations
+            .record(&operation, status, duration, variables_bytes);
+
+        if let Ok(value) = self.request_id.to_string().parse() {
+            response.http_headers.insert("x-request-id", value);
+        }
+        response
+    }
+
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.operation = request.operation_name.clone();
+            state.variables_bytes = serde_json::to_string(&request.variables)
+                .map(|s| s.len())
+                .unwrap_or(0);
+        }
+        next.run(ctx, request).await
+    }
+}
@@ -0,0 +1,391 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use warp::http::{header, StatusCode};
+use warp::reply::{Reply, Response};
+use warp::Filter;
+
+use crate::audit::RequestOrigin;
+use crate::errors::ErrorCode;
+use crate::labels::LabelSelector;
+use crate::listeners::BearerAuth;
+use crate::metrics::Metrics;
+use crate::{request_origin, Component, ComponentDaemon, WriteError};
+
+// ========================
+// REST API
+// ========================
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateBody {
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListParams {
+    pub labels: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetParams {
+    pub wait_for_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiError {
+    pub error: String,
+    pub code: &'static str,
+}
+
+pub fn routes(
+    daemon: ComponentDaemon,
+    auth: &BearerAuth,
+) -> impl Filter<Extract = (Response,), Error = warp::Rejection> + Clone {
+    let with_daemon = warp::any().map(move || daemon.clone());
+
+    let list = warp::path!("api" / "components")
+        .and(warp::get())
+        .and(warp::query::<ListParams>())
+        .and(warp::header::optional::<String>("if-none-match"))
+        .and(with_daemon.clone())
+        .and_then(list_components);
+
+    let get = warp::path!("api" / "components" / String)
+        .and(warp::get())
+        .and(warp::query::<GetParams>())
+        .and(warp::header::optional::<String>("if-none-match"))
+        .and(with_daemon.clone())
+        .and_then(get_component);
+
+    let update = warp::path!("api" / "components" / String)
+        .and(warp::put())
+        .and(warp::header::optional::<String>("if-match"))
+        .and(warp::body::json())
+        .and(request_origin(auth))
+        .and(with_daemon.clone())
+        .and_then(update_component);
+
+    let delete = warp::path!("api" / "components" / String)
+        .and(warp::delete())
+        .and(warp::header::optional::<String>("if-match"))
+        .and(request_origin(auth))
+        .and(with_daemon)
+        .and_then(delete_component);
+
+    list.or(get).unify().or(update).unify().or(delete).unify()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/components",
+    tag = "components",
+    params(
+        ("labels" = Option<String>, Query, description = "Label selector, e.g. `env=prod,team in (web,ops)`"),
+        ("if-none-match" = Option<String>, Header, description = "State ETag from a previous listing"),
+    ),
+    responses(
+        (status = 200, description = "All stored components", body = [Component],
+            headers(("etag" = String, description = "Changes whenever any component is stored or removed"))),
+        (status = 304, description = "Nothing changed since the given ETag"),
+        (status = 400, description = "Invalid label selector", body = ApiError),
+    )
+)]
+async fn list_components(
+    params: ListParams,
+    if_none_match: Option<String>,
+    daemon: ComponentDaemon,
+) -> Result<Response, Infallible> {
+    let selector = match params.labels.as_deref().map(LabelSelector::parse).transpose() {
+        Ok(selector) => selector,
+        Err(e) => {
+            let error = ApiError {
+                error: e.to_string(),
+                code: ErrorCode::InvalidSelector.as_str(),
+            };
+            return Ok(
+                warp::reply::with_status(warp::reply::json(&error), StatusCode::BAD_REQUEST)
+                    .into_response(),
+            );
+        }
+    };
+    // The same state gives the same filtered listing, so the state ETag holds
+    let etag = daemon.state_etag();
+    if etag_matches(if_none_match.as_deref(), &etag) {
+        return Ok(not_modified(&etag));
+    }
+    let mut components = daemon.get_components();
+    if let Some(selector) = selector {
+        components.retain(|component| selector.matches(&component.labels));
+    }
+    let chunks = JsonArrayChunks::new(components, daemon.metrics());
+    let body = warp::hyper::Body::wrap_stream(futures_util::stream::iter(chunks));
+    Ok(warp::http::Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::ETAG, etag)
+        .body(body)
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response()))
+}
+
+// Components per body chunk of the streamed listing
+const LIST_CHUNK: usize = 256;
+
+// Serializes a snapshot as a JSON array a chunk at a time, so a listing never
+// holds more than one chunk of encoded JSON in memory.
+struct JsonArrayChunks {
+    components: std::vec::IntoIter<Arc<Component>>,
+    started: bool,
+    done: bool,
+    allocated: usize,
+    metrics: Arc<Metrics>,
+}
+
+impl JsonArrayChunks {
+    fn new(snapshot: Vec<Arc<Component>>, metrics: Arc<Metrics>) -> Self {
+        Self {
+            allocated: snapshot.capacity() * std::mem::size_of::<Arc<Component>>(),
+            components: snapshot.into_iter(),
+            started: false,
+            done: false,
+            metrics,
+        }
+    }
+}
+
+impl Iterator for JsonArrayChunks {
+    type Item = Result<Vec<u8>, serde_json::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut chunk = Vec::new();
+        for (i, component) in self.components.by_ref().take(LIST_CHUNK).enumerate() {
+            chunk.push(match (self.started, i) {
+                (false, 0) => b'[',
+                _ => b',',
+            });
+            self.started = true;
+            if let Err(e) = serde_json::to_writer(&mut chunk, &*component) {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        if chunk.is_empty() || self.components.len() == 0 {
+            if !self.started {
+                chunk.push(b'[');
+            }
+            chunk.push(b']');
+            self.done = true;
+        }
+        self.allocated += chunk.capacity();
+        if self.done {
+            self.metrics
+                .read_alloc_bytes
+                .observe(&["rest"], self.allocated as f64);
+        }
+        Some(Ok(chunk))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/components/{id}",
+    tag = "components",
+    params(
+        ("id" = String, Path, description = "Component id"),
+        ("waitForMs" = Option<u64>, Query, description = "On a miss, wait up to this long (max 30000) for the component to arrive"),
+        ("if-none-match" = Option<String>, Header, description = "ETag of a cached copy"),
+    ),
+    responses(
+        (status = 200, description = "The component, with its ETag", body = Component,
+            headers(("etag" = String, description = "Quoted `<id>-v<version>` tag"))),
+        (status = 304, description = "The cached copy is current"),
+        (status = 404, description = "Unknown component", body = ApiError),
+    )
+)]
+async fn get_component(
+    id: String,
+    params: GetParams,
+    if_none_match: Option<String>,
+    daemon: ComponentDaemon,
+) -> Result<Response, Infallible> {
+    let wait = Duration::from_millis(params.wait_for_ms.unwrap_or(0));
+    Ok(match daemon.wait_for_component(&id, wait).await {
+        Some(component) if etag_matches(if_none_match.as_deref(), &component.etag()) => {
+            not_modified(&component.etag())
+        }
+        Some(component) => with_etag(&component, StatusCode::OK),
+        None => error_reply(&WriteError::NotFound),
+    })
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/components/{id}",
+    tag = "components",
+    params(
+        ("id" = String, Path, description = "Component id"),
+        ("if-match" = Option<String>, Header, description = "ETag the update is based on; `*` only requires existence"),
+    ),
+    request_body = UpdateBody,
+    responses(
+        (status = 200, description = "Updated component", body = Component),
+        (status = 404, description = "Unknown component and no If-Match", body = ApiError),
+        (status = 412, description = "If-Match does not match the stored version, or the component doesn't exist", body = ApiError),
+        (status = 413, description = "Data is over COMPONENT_MAX_BYTES and the policy is reject", body = ApiError),
+        (status = 421, description = "This daemon is a replication follower; write to the primary", body = ApiError),
+        (status = 422, description = "Data fails validation", body = ApiError),
+    )
+)]
+async fn update_component(
+    id: String,
+    if_match: Option<String>,
+    body: UpdateBody,
+    origin: RequestOrigin,
+    daemon: ComponentDaemon,
+) -> Result<Response, Infallible> {
+    let expected = match expected_version(&id, if_match.as_deref()) {
+        Ok(expected) => expected,
+        Err(message) => return Ok(precondition_failed(message)),
+    };
+
+    let result = daemon.update_component(&id, body.data, expected);
+    daemon.audit_log().record(
+        &origin,
+        "rest.updateComponent",
+        serde_json::json!({ "id": id, "expectedVersion": expected }),
+        if result.is_ok() { "ok" } else { "error" },
+    );
+
+    Ok(match result {
+        Ok(component) => with_etag(&component, StatusCode::OK),
+        Err(e) => conditional_error_reply(if_match.as_deref(), &e),
+    })
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/components/{id}",
+    tag = "components",
+    params(
+        ("id" = String, Path, description = "Component id"),
+        ("if-match" = Option<String>, Header, description = "ETag the delete is based on"),
+    ),
+    responses(
+        (status = 204, description = "Component removed"),
+        (status = 404, description = "Unknown component and no If-Match", body = ApiError),
+        (status = 412, description = "If-Match does not match the stored version, or the component doesn't exist", body = ApiError),
+        (status = 421, description = "This daemon is a replication follower; write to the primary", body = ApiError),
+    )
+)]
+async fn delete_component(
+    id: String,
+    if_match: Option<String>,
+    origin: RequestOrigin,
+    daemon: ComponentDaemon,
+) -> Result<Response, Infallible> {
+    let expected = match expected_version(&id, if_match.as_deref()) {
+        Ok(expected) => expected,
+        Err(message) => return Ok(precondition_failed(message)),
+    };
+
+    let result = daemon.delete_component(&id, expected);
+    daemon.audit_log().record(
+        &origin,
+        "rest.deleteComponent",
+        serde_json::json!({ "id": id, "expectedVersion": expected }),
+        if result.is_ok() { "ok" } else { "error" },
+    );
+
+    Ok(match result {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => conditional_error_reply(if_match.as_deref(), &e),
+    })
+}
+
+// Maps an If-Match header onto the expected version; the version check
+// itself happens atomically in the daemon. `*` only requires existence.
+fn expected_version(id: &str, if_match: Option<&str>) -> Result<Option<u64>, &'static str> {
+    let Some(if_match) = if_match.map(str::trim) else {
+        return Ok(None);
+    };
+    if if_match == "*" {
+        return Ok(None);
+    }
+
+    let prefix = format!("{id}-v");
+    if_match
+        .split(',')
+        .find_map(|tag| {
+            tag.trim()
+                .trim_start_matches("W/")
+                .trim_matches('"')
+                .strip_prefix(&prefix)?
+                .parse()
+                .ok()
+        })
+        .map(Some)
+        .ok_or("If-Match does not reference a version of this component")
+}
+
+// Any If-Match, `*` included, fails on a component that doesn't exist
+fn conditional_error_reply(if_match: Option<&str>, e: &WriteError) -> Response {
+    match (if_match, e) {
+        (Some(_), WriteError::NotFound) => precondition_failed("If-Match given but the component does not exist"),
+        _ => error_reply(e),
+    }
+}
+
+fn precondition_failed(message: &str) -> Response {
+    warp::reply::with_status(
+        warp::reply::json(&ApiError {
+            error: message.to_string(),
+            code: ErrorCode::VersionConflict.as_str(),
+        }),
+        StatusCode::PRECONDITION_FAILED,
+    )
+    .into_response()
+}
+
+// If-None-Match semantics: weak comparison, `*` matches anything
+pub(crate) fn etag_matches(if_none_match: Option<&str>, etag: &str) -> bool {
+    let Some(if_none_match) = if_none_match.map(str::trim) else {
+        return false;
+    };
+    if_none_match == "*"
+        || if_none_match
+            .split(',')
+            .any(|tag| tag.trim().trim_start_matches("W/") == etag)
+}
+
+pub(crate) fn not_modified(etag: &str) -> Response {
+    warp::reply::with_header(StatusCode::NOT_MODIFIED, header::ETAG, etag).into_response()
+}
+
+fn with_etag(component: &Component, status: StatusCode) -> Response {
+    let reply = warp::reply::with_status(warp::reply::json(component), status);
+    warp::reply::with_header(reply, header::ETAG, component.etag()).into_response()
+}
+
+fn error_reply(e: &WriteError) -> Response {
+    let status = match e {
+        WriteError::NotFound => StatusCode::NOT_FOUND,
+        WriteError::VersionConflict { .. } => StatusCode::PRECONDITION_FAILED,
+        WriteError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        WriteError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+        WriteError::Unsigned(_) => StatusCode::FORBIDDEN,
+        WriteError::ReadOnly => StatusCode::MISDIRECTED_REQUEST,
+    };
+    warp::reply::with_status(
+        warp::reply::json(&ApiError {
+            error: e.to_string(),
+            code: e.code().as_str(),
+        }),
+        status,
+    )
+    .into_response()
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tracing::info;
+
+use crate::config::{env_parse, parse_duration, per_type_overrides};
+use crate::{Component, ComponentType};
+
+// ========================
+// RETENTION
+// ========================
+
+pub struct RetentionPolicy {
+    default_max_age: Option<Duration>,
+    max_age: HashMap<ComponentType, Duration>,
+    max_count: Option<usize>,
+    max_count_per_type: HashMap<ComponentType, usize>,
+    pub interval: Duration,
+}
+
+// A component chosen for eviction and the rule that chose it, e.g.
+// "max_age:NOTIFICATION" or "max_count:total".
+pub struct Eviction {
+    pub id: String,
+    pub version: u64,
+    pub rule: String,
+}
+
+impl RetentionPolicy {
+    // RETENTION_MAX_AGE: per-type ages ("NOTIFICATION=1h,CARD=24h"), with
+    // RETENTION_MAX_AGE_DEFAULT for the rest. RETENTION_MAX_COUNT caps the
+    // total, RETENTION_MAX_COUNT_PER_TYPE caps individual types. Rules are
+    // checked every RETENTION_INTERVAL_SECS.
+    pub fn from_env() -> Self {
+        let policy = Self {
+            default_max_age: std::env::var("RETENTION_MAX_AGE_DEFAULT")
+                .ok()
+                .and_then(|raw| parse_duration(&raw)),
+            max_age: per_type_overrides("RETENTION_MAX_AGE", parse_duration),
+            max_count: std::env::var("RETENTION_MAX_COUNT")
+                .ok()
+                .and_then(|raw| raw.trim().parse().ok()),
+            max_count_per_type: per_type_overrides("RETENTION_MAX_COUNT_PER_TYPE", |raw| {
+                raw.parse().ok()
+            }),
+            interval: Duration::from_secs(env_parse("RETENTION_INTERVAL_SECS", 60).max(1)),
+        };
+        if policy.enabled() {
+            info!(
+                "🗓️ Daemon: Retention every {:?}: max age {:?} (default {:?}), max count {:?} {:?}",
+                policy.interval,
+                policy.max_age,
+                policy.default_max_age,
+                policy.max_count,
+                policy.max_count_per_type
+            );
+        }
+        policy
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.default_max_age.is_some()
+            || !self.max_age.is_empty()
+            || self.max_count.is_some()
+            || !self.max_count_per_type.is_empty()
+    }
+
+    // Age rules run first; count rules then drop the oldest survivors.
+    pub fn plan(&self, mut components: Vec<Arc<Component>>, now: DateTime<Utc>) -> Vec<Eviction> {
+        let mut evictions = Vec::new();
+        let type_name = |r#type: ComponentType| {
+            serde_json::to_value(r#type)
+                .ok()
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_default()
+        };
+
+        components.retain(|component| {
+            let max_age = self
+                .max_age
+                .get(&component.r#type)
+                .copied()
+                .or(self.default_max_age);
+            let expired = max_age.is_some_and(|max_age| {
+                now.signed_duration_since(component.created_at)
+                    .to_std()
+                    .is_ok_and(|age| age > max_age)
+            });
+            if expired {
+                evictions.push(Eviction {
+                    id: component.id.clone(),
+                    version: component.version,
+                    rule: format!("max_age:{}", type_name(component.r#type)),
+                });
+            }
+            !expired
+        });
+
+        // Oldest first, so the count rules keep the newest components
+        components.sort_by_key(|component| (component.created_at, component.seq));
+
+        for (r#type, limit) in &self.max_count_per_type {
+            let of_type: Vec<usize> = components
+                .iter()
+                .enumerate()
+                .filter(|(_, component)| component.r#type == *r#type)
+                .map(|(i, _)| i)
+                .collect();
+            let excess = of_type.len().saturating_sub(*limit);
+            for &i in of_type.iter().take(excess).rev() {
+                let component = components.remove(i);
+                evictions.push(Eviction {
+                    id: component.id.clone(),
+                    version: component.version,
+                    rule: format!("max_count:{}", type_name(*r#type)),
+                });
+            }
+        }
+
+        if let Some(limit) = self.max_count {
+            let excess = components.len().saturating_sub(limit);
+            for component in components.drain(..excess) {
+                evictions.push(Eviction {
+                    id: component.id.clone(),
+                    version: component.version,
+                    rule: "max_count:total".to_string(),
+                });
+            }
+        }
+
+        evictions
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::Notify;
+
+use crate::config::env_parse;
+use crate::Component;
+
+// ========================
+// SCHEDULED DELIVERY
+// ========================
+
+// Explicit `deliverAt` wins, then an RFC 3339 `data.deliverAt`
+pub fn deliver_at(component: &Component) -> Option<DateTime<Utc>> {
+    component.deliver_at.or_else(|| {
+        component
+            .data
+            .get("deliverAt")
+            .and_then(|at| at.as_str())
+            .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+            .map(|at| at.with_timezone(&Utc))
+    })
+}
+
+#[derive(Default)]
+struct Pending {
+    // (deliverAt, insertion order) keeps same-instant items in arrival order
+    queue: BTreeMap<(DateTime<Utc>, u64), Component>,
+    by_id: HashMap<String, (DateTime<Utc>, u64)>,
+    next: u64,
+}
+
+// Components held back until their deliverAt. One pending entry per id: a
+// newer schedule for the same id replaces the older one.
+pub struct Scheduler {
+    pending: Mutex<Pending>,
+    changed: Notify,
+    max_pending: usize,
+}
+
+impl Scheduler {
+    // SCHEDULE_MAX_PENDING caps how many components can wait at once
+    pub fn from_env() -> Self {
+        Self {
+            pending: Mutex::new(Pending::default()),
+            changed: Notify::new(),
+            max_pending: env_parse("SCHEDULE_MAX_PENDING", 10_000),
+        }
+    }
+
+    pub fn hold(&self, component: Component, at: DateTime<Utc>) -> Result<(), String> {
+        let mut pending = self.pending.lock().unwrap();
+        let replaced = pending.by_id.remove(&component.id);
+        if let Some(key) = replaced {
+            pending.queue.remove(&key);
+        } else if pending.queue.len() >= self.max_pending {
+            return Err(format!("Schedule full ({} pending)", self.max_pending));
+        }
+        let key = (at, pending.next);
+        pending.next += 1;
+        pending.by_id.insert(component.id.clone(), key);
+        pending.queue.insert(key, component);
+        drop(pending);
+        self.changed.notify_one();
+        Ok(())
+    }
+
+    pub fn cancel(&self, id: &str) -> Option<Component> {
+        let mut pending = self.pending.lock().unwrap();
+        let key = pending.by_id.remove(id)?;
+        pending.queue.remove(&key)
+    }
+
+    // Pending components, soonest first
+    pub fn pending(&self) -> Vec<Component> {
+        self.pending
+            .lock()
+            .unwrap()
+            .queue
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().unwrap().queue.len()
+    }
+
+    // Waits until at least one component is due and returns everything due
+    pub async fn next_due(&self) -> Vec<Component> {
+        loop {
+            // Registered before checking, so a hold in between still wakes us
+            let changed = self.changed.notified();
+            let earliest = {
+                let mut pending = self.pending.lock().unwrap();
+                let now = Utc::now();
+                let mut due = Vec::new();
+                while let Some(entry) = pending.queue.first_entry() {
+                    if entry.key().0 > now {
+                        break;
+                    }
+                    let component = entry.remove();
+                    pending.by_id.remove(&component.id);
+                    due.push(component);
+                }
+                if !due.is_empty() {
+                    return due;
+                }
+                pending.queue.keys().next().map(|(at, _)| *at)
+            };
+            match earliest {
+                Some(at) => {
+                    let wait = (at - Utc::now()).to_std().unwrap_or_default();
+                    tokio::select! {
+                        _ = tokio::time::sleep(wait) => {}
+                        _ = changed => {}
+                    }
+                }
+                None => changed.await,
+            }
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use std::ffi::{OsStr, OsString};
+use std::fmt::Write as _;
+use std::iter::once;
+use std::os::windows::ffi::OsStrExt;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use tokio::sync::oneshot;
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::{error, info, Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context as LayerContext, Layer};
+use tracing_subscriber::registry::LookupSpan;
+use windows_service::service::{
+    ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+    ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+use windows_service::{define_windows_service, service_dispatcher};
+use windows_sys::Win32::Foundation::HANDLE;
+use windows_sys::Win32::System::EventLog::{
+    RegisterEventSourceW, ReportEventW, EVENTLOG_ERROR_TYPE, EVENTLOG_INFORMATION_TYPE,
+    EVENTLOG_WARNING_TYPE,
+};
+use winreg::enums::HKEY_LOCAL_MACHINE;
+use winreg::RegKey;
+
+// ========================
+// WINDOWS SERVICE
+// ========================
+
+// `install-service` registers the binary with `run-service` as its first
+// argument; any KEY=VALUE arguments given at install time are passed through
+// and set as environment variables before the daemon starts, since services
+// don't inherit a shell environment. `--seed <file>` is passed through too.
+const SERVICE_NAME: &str = "ComponentDaemon";
+const DISPLAY_NAME: &str = "Component Daemon";
+const SERVICE_PORT: u16 = 3001;
+const EVENT_SOURCE_KEY: &str =
+    r"SYSTEM\CurrentControlSet\Services\EventLog\Application\ComponentDaemon";
+// Ships with the .NET Framework and maps every event id to "%1", so the
+// message we report is shown as-is in Event Viewer.
+const EVENT_MESSAGE_FILE: &str =
+    r"%SystemRoot%\Microsoft.NET\Framework64\v4.0.30319\EventLogMessages.dll";
+
+static RUNNING_AS_SERVICE: OnceLock<()> = OnceLock::new();
+
+pub fn install(args: &[String]) -> Result<()> {
+    let mut launch_arguments = vec![OsString::from("run-service")];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--seed" {
+            // Services start in System32, so relative paths won't resolve
+            let path = args
+                .next()
+                .ok_or_else(|| anyhow!("--seed needs a file path"))?;
+            launch_arguments.push(arg.into());
+            launch_arguments.push(std::fs::canonicalize(path)?.into());
+        } else if arg.contains('=') {
+            launch_arguments.push(arg.into());
+        } else {
+            return Err(anyhow!("Expected KEY=VALUE or --seed <file>, got '{arg}'"));
+        }
+    }
+
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )?;
+    let info = ServiceInfo {
+        name: SERVICE_NAME.into(),
+        display_name: DISPLAY_NAME.into(),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: std::env::current_exe()?,
+        launch_arguments,
+        dependencies: vec![],
+        account_name: None,
+        account_password: None,
+    };
+    let service = manager
+        .create_service(&info, ServiceAccess::CHANGE_CONFIG)
+        .context("Failed to create service (is this an elevated prompt?)")?;
+    service.set_description("Bridges the component registry to local renderers")?;
+
+    let (source, _) = RegKey::predef(HKEY_LOCAL_MACHINE).create_subkey(EVENT_SOURCE_KEY)?;
+    source.set_raw_value(
+        "EventMessageFile",
+        &winreg::RegValue {
+            bytes: wide(OsStr::new(EVENT_MESSAGE_FILE))
+                .iter()
+                .flat_map(|unit| unit.to_le_bytes())
+                .collect(),
+            vtype: winreg::enums::REG_EXPAND_SZ,
+        },
+    )?;
+    source.set_value("TypesSupported", &7u32)?;
+
+    println!("Installed service '{SERVICE_NAME}'; start it with `sc start {SERVICE_NAME}`");
+    Ok(())
+}
+
+pub fn uninstall() -> Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    let service = manager.open_service(
+        SERVICE_NAME,
+        ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+    )?;
+    if service.query_status()?.current_state != ServiceState::Stopped {
+        service.stop()?;
+    }
+    // Removal completes once the service has stopped and all handles are closed
+    service.delete()?;
+    let _ = RegKey::predef(HKEY_LOCAL_MACHINE).delete_subkey_all(EVENT_SOURCE_KEY);
+
+    println!("Uninstalled service '{SERVICE_NAME}'");
+    Ok(())
+}
+
+// Entry point when launched by the service control manager; blocks until the
+// service stops.
+pub fn run() -> Result<()> {
+    let _ = RUNNING_AS_SERVICE.set(());
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+        .context("Not started by the service control manager; use install-service instead")
+}
+
+define_windows_service!(ffi_service_main, service_main);
+
+fn service_main(arguments: Vec<OsString>) {
+    if let Err(e) = run_service(arguments) {
+        error!("❌ Daemon: Service failed: {:#}", e);
+    }
+}
+
+fn run_service(_arguments: Vec<OsString>) -> Result<()> {
+    // Launch arguments reach the process argv, not service_main
+    let args: Vec<String> = std::env::args().skip(2).collect();
+    let seed = crate::import::seed_arg(&args)?;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--seed" {
+            args.next();
+        } else if let Some((key, value)) = arg.split_once('=') {
+            std::env::set_var(key, value);
+        }
+    }
+
+    let (stop_tx, stop_rx) = oneshot::channel();
+    let mut stop_tx = Some(stop_tx);
+    let status = service_control_handler::register(SERVICE_NAME, move |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            if let Some(stop_tx) = stop_tx.take() {
+                let _ = stop_tx.send(());
+            }
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    })?;
+    let report = |state, controls_accepted, wait_hint| {
+        status.set_service_status(ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted,
+            exit_code: ServiceExitCode::Win32(0),
+            checkpoint: 0,
+            wait_hint,
+            process_id: None,
+        })
+    };
+
+    report(
+        ServiceState::StartPending,
+        ServiceControlAccept::empty(),
+        Duration::from_secs(10),
+    )?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    report(
+        ServiceState::Running,
+        ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+        Duration::ZERO,
+    )?;
+    let result = runtime.block_on(crate::serve_until(SERVICE_PORT, seed, async move {
+        let _ = stop_rx.await;
+        info!("🛑 Daemon: Service stop requested");
+    }));
+    report(
+        ServiceState::StopPending,
+        ServiceControlAccept::empty(),
+        Duration::from_secs(5),
+    )?;
+    runtime.shutdown_timeout(Duration::from_secs(3));
+    report(
+        ServiceState::Stopped,
+        ServiceControlAccept::empty(),
+        Duration::ZERO,
+    )?;
+    result
+}
+
+// ========================
+// EVENT LOG
+// ========================
+
+// Only installed when running as a service. EVENT_LOG_LEVEL (default "warn")
+// keeps routine ingest logging out of the Application log.
+pub fn event_log_layer<S>() -> Option<impl Layer<S>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    RUNNING_AS_SERVICE.get()?;
+    let handle =
+        unsafe { RegisterEventSourceW(std::ptr::null(), wide(OsStr::new(SERVICE_NAME)).as_ptr()) };
+    if handle == 0 {
+        return None;
+    }
+    let level = std::env::var("EVENT_LOG_LEVEL")
+        .ok()
+        .and_then(|level| level.parse::<LevelFilter>().ok())
+        .unwrap_or(LevelFilter::WARN);
+    Some(tracing_subscriber::Layer::with_filter(
+        EventLogLayer { handle },
+        level,
+    ))
+}
+
+struct EventLogLayer {
+    handle: HANDLE,
+}
+
+impl<S: Subscriber> Layer<S> for EventLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        let mut message = MessageVisitor(String::new());
+        event.record(&mut message);
+        let kind = match *event.metadata().level() {
+            Level::ERROR => EVENTLOG_ERROR_TYPE,
+            Level::WARN => EVENTLOG_WARNING_TYPE,
+            _ => EVENTLOG_INFORMATION_TYPE,
+        };
+        let text = wide(OsStr::new(&message.0));
+        let strings = [text.as_ptr()];
+        unsafe {
+            ReportEventW(
+                self.handle,
+                kind,
+                0,
+                0,
+                std::ptr::null_mut(),
+                1,
+                0,
+                strings.as_ptr(),
+                std::ptr::null(),
+            );
+        }
+    }
+}
+
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
+
+fn wide(text: &OsStr) -> Vec<u16> {
+    text.encode_wide().chain(once(0)).collect()
+}
@@ -0,0 +1,275 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::config::env_parse;
+use crate::labels::{LabelRules, Labels};
+use crate::priority::PriorityConfig;
+use crate::validation::{ValidationMode, Validator};
+use crate::{Component, ComponentType};
+
+// ========================
+// SHADOW RULES
+// ========================
+
+// Rule settings a candidate set can replace; each SHADOW_<name> falls back
+// to the active <name>, so only the rules being changed need setting
+const RULE_VARS: [&str; 4] = ["VALIDATION_MODE", "COMPONENT_SCHEMA_DIR", "PRIORITY_DEFAULTS", "LABEL_RULES"];
+
+// What the active rules did with a component
+pub enum Outcome<'a> {
+    Dropped,
+    Kept { priority: i32, labels: &'a Labels },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum DivergenceKind {
+    // Kept by the active rules, dropped by the candidate
+    Drop,
+    // Dropped by the active rules, kept by the candidate
+    Keep,
+    // Kept by both, with a different priority or labels
+    Modify,
+}
+
+impl DivergenceKind {
+    fn label(&self) -> &'static str {
+        match self {
+            DivergenceKind::Drop => "would_drop",
+            DivergenceKind::Keep => "would_keep",
+            DivergenceKind::Modify => "would_modify",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Divergence {
+    pub id: String,
+    pub r#type: ComponentType,
+    pub kind: DivergenceKind,
+    // Failed rule names or changed fields; never payload values
+    pub detail: String,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShadowStats {
+    pub enabled: bool,
+    // The SHADOW_ variables in effect
+    pub overrides: Vec<String>,
+    pub evaluated: u64,
+    pub agreed: u64,
+    pub would_drop: u64,
+    pub would_keep: u64,
+    pub would_modify: u64,
+    // Most recent first
+    pub samples: Vec<Divergence>,
+}
+
+// Evaluates a candidate rule set next to the active one on every ingested
+// component and records where they would disagree, without changing what is
+// stored or delivered. Enabled by setting any SHADOW_VALIDATION_MODE,
+// SHADOW_COMPONENT_SCHEMA_DIR, SHADOW_PRIORITY_DEFAULTS or SHADOW_LABEL_RULES.
+// Promoting the candidate means moving those values to the active variables.
+pub struct ShadowRules {
+    overrides: Vec<String>,
+    validation_mode: ValidationMode,
+    // None validates with the active schemas
+    validator: Option<Validator>,
+    priorities: PriorityConfig,
+    label_rules: LabelRules,
+    evaluated: AtomicU64,
+    agreed: AtomicU64,
+    would_drop: AtomicU64,
+    would_keep: AtomicU64,
+    would_modify: AtomicU64,
+    // Last SHADOW_SAMPLE_SIZE divergences (default 20)
+    sample_size: usize,
+    samples: Mutex<VecDeque<Divergence>>,
+}
+
+impl ShadowRules {
+    pub fn from_env() -> Result<Self> {
+        let overrides: Vec<String> = RULE_VARS
+            .iter()
+            .map(|name| format!("SHADOW_{name}"))
+            .filter(|name| std::env::var(name).is_ok())
+            .collect();
+        let candidate = |name: &str| {
+            let shadow = format!("SHADOW_{name}");
+            if overrides.contains(&shadow) {
+                shadow
+            } else {
+                name.to_string()
+            }
+        };
+        let validator = match overrides.iter().any(|name| name == "SHADOW_COMPONENT_SCHEMA_DIR") {
+            true => Some(Validator::from_var("SHADOW_COMPONENT_SCHEMA_DIR")?),
+            false => None,
+        };
+        let rules = Self {
+            validation_mode: ValidationMode::from_var(&candidate("VALIDATION_MODE")),
+            validator,
+            priorities: PriorityConfig::from_var(&candidate("PRIORITY_DEFAULTS")),
+            label_rules: LabelRules::from_var(&candidate("LABEL_RULES")),
+            overrides,
+            evaluated: AtomicU64::new(0),
+            agreed: AtomicU64::new(0),
+            would_drop: AtomicU64::new(0),
+            would_keep: AtomicU64::new(0),
+            would_modify: AtomicU64::new(0),
+            sample_size: env_parse("SHADOW_SAMPLE_SIZE", 20),
+            samples: Mutex::new(VecDeque::new()),
+        };
+        if rules.enabled() {
+            info!("🌓 Daemon: Evaluating candidate rules in shadow ({})", rules.overrides.join(", "));
+        }
+        Ok(rules)
+    }
+
+    pub fn disabled() -> Self {
+        Self {
+            overrides: Vec::new(),
+            validation_mode: ValidationMode::Off,
+            validator: None,
+            priorities: PriorityConfig::from_env(),
+            label_rules: LabelRules::from_env(),
+            evaluated: AtomicU64::new(0),
+            agreed: AtomicU64::new(0),
+            would_drop: AtomicU64::new(0),
+            would_keep: AtomicU64::new(0),
+            would_modify: AtomicU64::new(0),
+            sample_size: 0,
+            samples: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.overrides.is_empty()
+    }
+
+    // `component` is the one the active rules started from, before they
+    // resolved its priority and labels
+    pub fn compare(&self, active_validator: &Validator, component: &Component, active: Outcome<'_>) {
+        self.evaluated.fetch_add(1, Ordering::Relaxed);
+        let failed = match self.validation_mode {
+            ValidationMode::Reject => {
+                let validator = self.validator.as_ref().unwrap_or(active_validator);
+                let mut rules: Vec<String> = validator
+                    .validate(component.r#type, &component.data)
+                    .violations
+                    .into_iter()
+                    .map(|v| v.rule)
+                    .collect();
+                rules.dedup();
+                rules
+            }
+            ValidationMode::Off | ValidationMode::Warn => Vec::new(),
+        };
+
+        let divergence = match active {
+            Outcome::Dropped if failed.is_empty() => {
+                Some((DivergenceKind::Keep, "passes candidate validation".to_string()))
+            }
+            Outcome::Dropped => None,
+            Outcome::Kept { .. } if !failed.is_empty() => {
+                Some((DivergenceKind::Drop, format!("fails {}", failed.join(", "))))
+            }
+            Outcome::Kept { priority, labels } => {
+                let mut changes = Vec::new();
+                let candidate_priority = self.priorities.resolve(component);
+                if candidate_priority != priority {
+                    changes.push(format!("priority {} → {}", priority, candidate_priority));
+                }
+                let candidate_labels = self.label_rules.resolve(component);
+                if &candidate_labels != labels {
+                    changes.push(format!("labels {} → {}", describe(labels), describe(&candidate_labels)));
+                }
+                (!changes.is_empty()).then(|| (DivergenceKind::Modify, changes.join("; ")))
+            }
+        };
+        match divergence {
+            Some((kind, detail)) => self.record(component, kind, detail),
+            None => {
+                self.agreed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn record(&self, component: &Component, kind: DivergenceKind, detail: String) {
+        let counter = match kind {
+            DivergenceKind::Drop => &self.would_drop,
+            DivergenceKind::Keep => &self.would_keep,
+            DivergenceKind::Modify => &self.would_modify,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        info!("🌓 Daemon: Candidate rules diverge on {} ({}): {}", component.id, kind.label(), detail);
+        if self.sample_size == 0 {
+            return;
+        }
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == self.sample_size {
+            samples.pop_back();
+        }
+        samples.push_front(Divergence {
+            id: component.id.clone(),
+            r#type: component.r#type,
+            kind,
+            detail,
+            at: Utc::now(),
+        });
+    }
+
+    pub fn stats(&self) -> ShadowStats {
+        // Separate counters, since concurrent compares make any difference of
+        // them racy
+        ShadowStats {
+            enabled: self.enabled(),
+            overrides: self.overrides.clone(),
+            evaluated: self.evaluated.load(Ordering::Relaxed),
+            agreed: self.agreed.load(Ordering::Relaxed),
+            would_drop: self.would_drop.load(Ordering::Relaxed),
+            would_keep: self.would_keep.load(Ordering::Relaxed),
+            would_modify: self.would_modify.load(Ordering::Relaxed),
+            samples: self.samples.lock().unwrap().iter().cloned().collect(),
+        }
+    }
+
+    pub fn render(&self, out: &mut String) {
+        if !self.enabled() {
+            return;
+        }
+        out.push_str("# HELP daemon_shadow_evaluated_total Components evaluated against the candidate rules.\n");
+        out.push_str("# TYPE daemon_shadow_evaluated_total counter\n");
+        let _ = writeln!(out, "daemon_shadow_evaluated_total {}", self.evaluated.load(Ordering::Relaxed));
+        out.push_str("# HELP daemon_shadow_divergence_total Components the candidate rules would have handled differently.\n");
+        out.push_str("# TYPE daemon_shadow_divergence_total counter\n");
+        for (kind, counter) in [
+            (DivergenceKind::Drop, &self.would_drop),
+            (DivergenceKind::Keep, &self.would_keep),
+            (DivergenceKind::Modify, &self.would_modify),
+        ] {
+            let _ = writeln!(
+                out,
+                "daemon_shadow_divergence_total{{kind=\"{}\"}} {}",
+                kind.label(),
+                counter.load(Ordering::Relaxed)
+            );
+        }
+    }
+}
+
+fn describe(labels: &Labels) -> String {
+    let pairs: Vec<String> = labels.iter().map(|(key, value)| format!("{key}={value}")).collect();
+    format!("{{{}}}", pairs.join(","))
+}
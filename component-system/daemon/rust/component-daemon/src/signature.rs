@@ -0,0 +1,399 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::Serialize;
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::config::env_parse;
+
+// ========================
+// SIGNATURE VERIFICATION
+// ========================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignaturePolicy {
+    Off,
+    // Signed components must verify; unsigned ones pass
+    VerifyIfPresent,
+    // Every component must carry a valid signature
+    Require,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureAction {
+    Reject,
+    Quarantine,
+}
+
+pub struct SignatureVerifier {
+    keys: Vec<(String, VerifyingKey)>,
+    policy: SignaturePolicy,
+    action: FailureAction,
+}
+
+impl SignatureVerifier {
+    // REGISTRY_PUBLIC_KEYS: comma-separated `keyId=<base64 key>` (or bare keys).
+    // SIGNATURE_POLICY: off | verify-if-present | require; defaults to
+    // verify-if-present once keys are configured.
+    // SIGNATURE_FAILURE_ACTION: reject | quarantine.
+    pub fn from_env() -> Result<Self> {
+        let mut keys = Vec::new();
+        if let Ok(raw) = std::env::var("REGISTRY_PUBLIC_KEYS") {
+            for (i, entry) in raw.split(',').map(str::trim).filter(|e| !e.is_empty()).enumerate() {
+                let (key_id, encoded) = match entry.split_once('=') {
+                    // Base64 padding also contains '=', so only split on a
+                    // separator that leaves a non-empty key id.
+                    Some((id, key)) if !id.is_empty() && !key.is_empty() => (id.to_string(), key),
+                    _ => (format!("key-{i}"), entry),
+                };
+                let bytes: [u8; 32] = BASE64
+                    .decode(encoded)
+                    .with_context(|| format!("Public key {key_id} is not valid base64"))?
+                    .try_into()
+                    .map_err(|_| anyhow!("Public key {key_id} must be 32 bytes"))?;
+                let key = VerifyingKey::from_bytes(&bytes)
+                    .with_context(|| format!("Public key {key_id} is not a valid Ed25519 key"))?;
+                keys.push((key_id, key));
+            }
+        }
+
+        let policy = match std::env::var("SIGNATURE_POLICY").as_deref() {
+            Ok("off") => SignaturePolicy::Off,
+            Ok("require") => SignaturePolicy::Require,
+            Ok("verify-if-present") => SignaturePolicy::VerifyIfPresent,
+            Ok(other) => {
+                return Err(anyhow!(
+                    "Unknown SIGNATURE_POLICY '{other}', expected off, verify-if-present or require"
+                ))
+            }
+            Err(_) if keys.is_empty() => SignaturePolicy::Off,
+            Err(_) => SignaturePolicy::VerifyIfPresent,
+        };
+        if policy != SignaturePolicy::Off && keys.is_empty() {
+            return Err(anyhow!("SIGNATURE_POLICY is enabled but REGISTRY_PUBLIC_KEYS is empty"));
+        }
+
+        let action = match std::env::var("SIGNATURE_FAILURE_ACTION").as_deref() {
+            Ok("quarantine") => FailureAction::Quarantine,
+            _ => FailureAction::Reject,
+        };
+
+        if policy != SignaturePolicy::Off {
+            info!(
+                "🔏 Daemon: Signature verification {:?} with {} key(s), failures: {:?}",
+                policy,
+                keys.len(),
+                action
+            );
+        }
+
+        Ok(Self { keys, policy, action })
+    }
+
+    pub fn action(&self) -> FailureAction {
+        self.action
+    }
+
+    // Local writes (updateComponent, importComponents, --seed) carry no
+    // registry signature, so `require` turns them away
+    pub fn check_local(&self) -> Result<(), String> {
+        match self.policy {
+            SignaturePolicy::Require => Err("local writes are unsigned".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    // Checks the raw `componentUpdate` object. The signed message is the
+    // canonical JSON (sorted keys, no whitespace) of the object without its
+    // `signature` field.
+    pub fn check(&self, raw: &serde_json::Value) -> Result<(), String> {
+        if self.policy == SignaturePolicy::Off {
+            return Ok(());
+        }
+
+        let Some(signature) = raw.get("signature").filter(|s| !s.is_null()) else {
+            return match self.policy {
+                SignaturePolicy::Require => Err("component is unsigned".to_string()),
+                _ => Ok(()),
+            };
+        };
+
+        let (key_id, encoded) = match signature {
+            serde_json::Value::String(sig) => (None, sig.as_str()),
+            serde_json::Value::Object(obj) => (
+                obj.get("keyId").and_then(|k| k.as_str()),
+                obj.get("value")
+                    .and_then(|v| v.as_str())
+                    .ok_or("signature object has no 'value'")?,
+            ),
+            _ => return Err("signature must be a string or object".to_string()),
+        };
+
+        let bytes: [u8; 64] = BASE64
+            .decode(encoded)
+            .map_err(|e| format!("signature is not valid base64: {e}"))?
+            .try_into()
+            .map_err(|_| "signature must be 64 bytes".to_string())?;
+        let signature = Signature::from_bytes(&bytes);
+
+        let mut unsigned = raw.clone();
+        if let Some(obj) = unsigned.as_object_mut() {
+            obj.remove("signature");
+        }
+        let message = canonical_json(&unsigned);
+
+        let verified = self
+            .keys
+            .iter()
+            .filter(|(id, _)| key_id.is_none_or(|wanted| wanted == id))
+            .any(|(_, key)| key.verify(message.as_bytes(), &signature).is_ok());
+
+        if verified {
+            Ok(())
+        } else if let Some(key_id) = key_id.filter(|id| !self.keys.iter().any(|(k, _)| k == id)) {
+            Err(format!("unknown signing key '{key_id}'"))
+        } else {
+            Err("signature does not verify".to_string())
+        }
+    }
+}
+
+pub fn canonical_json(value: &serde_json::Value) -> String {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys
+                .into_iter()
+                .map(|k| {
+                    format!(
+                        "{}:{}",
+                        serde_json::to_string(k).unwrap_or_default(),
+                        canonical_json(&map[k])
+                    )
+                })
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+// ========================
+// QUARANTINE
+// ========================
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantinedComponent {
+    pub id: String,
+    pub upstream: String,
+    pub reason: String,
+    pub payload: serde_json::Value,
+    pub quarantined_at: DateTime<Utc>,
+}
+
+// Bounded holding area for components that failed verification, so an
+// operator can inspect them and release or drop them explicitly.
+pub struct Quarantine {
+    entries: Mutex<VecDeque<QuarantinedComponent>>,
+    capacity: usize,
+}
+
+impl Quarantine {
+    pub fn from_env() -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::new()),
+            capacity: env_parse("QUARANTINE_CAPACITY", 100).max(1),
+        }
+    }
+
+    pub fn hold(&self, upstream: &str, reason: String, payload: serde_json::Value) {
+        let id = payload
+            .get("id")
+            .and_then(|id| id.as_str())
+            .unwrap_or("unknown")
+            .to_string();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| entry.id != id);
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(QuarantinedComponent {
+            id,
+            upstream: upstream.to_string(),
+            reason,
+            payload,
+            quarantined_at: Utc::now(),
+        });
+    }
+
+    pub fn list(&self) -> Vec<QuarantinedComponent> {
+        self.entries.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<QuarantinedComponent> {
+        self.entries.lock().unwrap().iter().find(|entry| entry.id == id).cloned()
+    }
+
+    pub fn take(&self, id: &str) -> Option<QuarantinedComponent> {
+        let mut entries = self.entries.lock().unwrap();
+        let index = entries.iter().position(|entry| entry.id == id)?;
+        entries.remove(index)
+    }
+
+    // Removes `released` unless the id was quarantined again since
+    pub fn release(&self, released: &QuarantinedComponent) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| entry.id != released.id || entry.quarantined_at != released.quarantined_at);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer, SigningKey};
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    fn verifier(policy: SignaturePolicy) -> SignatureVerifier {
+        SignatureVerifier {
+            keys: vec![("k1".to_string(), signing_key().verifying_key())],
+            policy,
+            action: FailureAction::Reject,
+        }
+    }
+
+    fn component(id: &str) -> Value {
+        json!({
+            "id": id,
+            "type": "CARD",
+            "data": { "title": "Hello", "count": 1 },
+            "createdAt": "2024-01-01T00:00:00Z"
+        })
+    }
+
+    fn signed(mut raw: Value, key_id: &str) -> Value {
+        let signature = signing_key().sign(canonical_json(&raw).as_bytes());
+        raw["signature"] = json!({ "keyId": key_id, "value": BASE64.encode(signature.to_bytes()) });
+        raw
+    }
+
+    #[test]
+    fn accepts_a_valid_signature() {
+        let raw = signed(component("c1"), "k1");
+        assert_eq!(verifier(SignaturePolicy::Require).check(&raw), Ok(()));
+
+        // A bare signature string is tried against every key
+        let mut bare = raw.clone();
+        bare["signature"] = raw["signature"]["value"].clone();
+        assert_eq!(verifier(SignaturePolicy::Require).check(&bare), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_tampered_payload() {
+        let mut raw = signed(component("c1"), "k1");
+        raw["data"]["title"] = json!("Goodbye");
+        assert_eq!(
+            verifier(SignaturePolicy::VerifyIfPresent).check(&raw),
+            Err("signature does not verify".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_an_unknown_key_id() {
+        let raw = signed(component("c1"), "k2");
+        assert_eq!(
+            verifier(SignaturePolicy::VerifyIfPresent).check(&raw),
+            Err("unknown signing key 'k2'".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_a_malformed_signature() {
+        let mut raw = component("c1");
+        raw["signature"] = json!("not base64!");
+        assert!(verifier(SignaturePolicy::VerifyIfPresent).check(&raw).is_err());
+        raw["signature"] = json!(42);
+        assert!(verifier(SignaturePolicy::VerifyIfPresent).check(&raw).is_err());
+    }
+
+    #[test]
+    fn policy_decides_unsigned_and_local_writes() {
+        let unsigned = component("c1");
+        assert_eq!(
+            verifier(SignaturePolicy::Require).check(&unsigned),
+            Err("component is unsigned".to_string())
+        );
+        assert_eq!(verifier(SignaturePolicy::VerifyIfPresent).check(&unsigned), Ok(()));
+
+        let mut tampered = signed(component("c1"), "k1");
+        tampered["id"] = json!("c2");
+        assert_eq!(verifier(SignaturePolicy::Off).check(&tampered), Ok(()));
+
+        assert!(verifier(SignaturePolicy::Require).check_local().is_err());
+        assert_eq!(verifier(SignaturePolicy::VerifyIfPresent).check_local(), Ok(()));
+        assert_eq!(verifier(SignaturePolicy::Off).check_local(), Ok(()));
+    }
+
+    #[test]
+    fn quarantine_keeps_one_entry_per_id_within_capacity() {
+        let quarantine = Quarantine {
+            entries: Mutex::new(VecDeque::new()),
+            capacity: 2,
+        };
+        let ids = |quarantine: &Quarantine| -> Vec<String> { quarantine.list().into_iter().map(|e| e.id).collect() };
+
+        quarantine.hold("registry", "signature does not verify".to_string(), component("a"));
+        quarantine.hold("registry", "signature does not verify".to_string(), component("b"));
+        quarantine.hold("registry", "component is unsigned".to_string(), component("a"));
+        assert_eq!(ids(&quarantine), ["b", "a"]);
+        assert_eq!(quarantine.list()[1].reason, "component is unsigned");
+
+        quarantine.hold("registry", "signature does not verify".to_string(), component("c"));
+        assert_eq!(ids(&quarantine), ["a", "c"]);
+
+        let released = quarantine.take("a").expect("a is quarantined");
+        assert_eq!(released.payload, component("a"));
+        assert!(quarantine.take("a").is_none());
+        assert_eq!(ids(&quarantine), ["c"]);
+    }
+
+    #[test]
+    fn release_keeps_an_entry_quarantined_again_meanwhile() {
+        let quarantine = Quarantine {
+            entries: Mutex::new(VecDeque::new()),
+            capacity: 2,
+        };
+        quarantine.hold("registry", "signature does not verify".to_string(), component("a"));
+        let first = quarantine.get("a").expect("a is quarantined");
+
+        // Peeking leaves it in place
+        assert!(quarantine.get("a").is_some());
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        quarantine.hold("registry", "component is unsigned".to_string(), component("a"));
+        quarantine.release(&first);
+        assert_eq!(quarantine.get("a").unwrap().reason, "component is unsigned");
+
+        let second = quarantine.get("a").unwrap();
+        quarantine.release(&second);
+        assert!(quarantine.get("a").is_none());
+    }
+}
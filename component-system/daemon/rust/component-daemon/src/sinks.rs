@@ -0,0 +1,306 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request};
+use tokio::sync::{mpsc, Semaphore};
+use tracing::{error, warn};
+
+use crate::at_rest::AtRestCipher;
+use crate::config::env_parse;
+use crate::redaction::Redactor;
+
+// ========================
+// OUTBOUND SINKS
+// ========================
+
+#[async_trait::async_trait]
+pub trait Sink: Send + Sync {
+    // Used for metrics labels and the dead-letter file name
+    fn name(&self) -> &str;
+    async fn deliver(&self, event: &serde_json::Value) -> Result<()>;
+}
+
+// JSON POST to a plain http:// endpoint
+pub struct WebhookSink {
+    name: String,
+    url: String,
+    client: Client<HttpConnector>,
+    timeout: Duration,
+}
+
+impl WebhookSink {
+    pub fn new(name: impl Into<String>, url: &str, timeout: Duration) -> Result<Self> {
+        if !url.starts_with("http://") {
+            bail!("only http:// webhooks are supported: {url}");
+        }
+        Ok(Self {
+            name: name.into(),
+            url: url.to_string(),
+            client: Client::new(),
+            timeout,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Sink for WebhookSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn deliver(&self, event: &serde_json::Value) -> Result<()> {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.url)
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_vec(event)?))?;
+        let response = tokio::time::timeout(self.timeout, self.client.request(request))
+            .await
+            .map_err(|_| anyhow::anyhow!("no response within {:?}", self.timeout))??;
+        if !response.status().is_success() {
+            bail!("responded with {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+// Shared by every sink worker:
+// SINK_QUEUE_CAPACITY   events buffered per sink before new ones are dead-lettered
+// SINK_CONCURRENCY      deliveries in flight per sink
+// SINK_MAX_ATTEMPTS     tries per event, with exponential backoff from SINK_RETRY_BASE_MS
+// SINK_RETRY_BUDGET     retries per sink per second; beyond it failures dead-letter at once
+// SINK_DEAD_LETTER_DIR  one <sink>.jsonl per sink, lines sealed with AT_REST_KEYS; unset only logs dead letters
+#[derive(Clone, Debug)]
+pub struct SinkConfig {
+    pub queue_capacity: usize,
+    pub concurrency: usize,
+    pub max_attempts: u32,
+    pub retry_base: Duration,
+    pub retry_budget: f64,
+    pub dead_letter_dir: Option<PathBuf>,
+}
+
+impl SinkConfig {
+    pub fn from_env() -> Self {
+        Self {
+            queue_capacity: env_parse("SINK_QUEUE_CAPACITY", 1000).max(1),
+            concurrency: env_parse("SINK_CONCURRENCY", 4).max(1),
+            max_attempts: env_parse("SINK_MAX_ATTEMPTS", 5).max(1),
+            retry_base: Duration::from_millis(env_parse("SINK_RETRY_BASE_MS", 200)),
+            retry_budget: env_parse("SINK_RETRY_BUDGET", 10.0),
+            dead_letter_dir: std::env::var("SINK_DEAD_LETTER_DIR").ok().map(PathBuf::from),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SinkStats {
+    pub delivered: AtomicU64,
+    pub retried: AtomicU64,
+    pub dead_lettered: AtomicU64,
+    pub queued: AtomicU64,
+}
+
+// Token bucket refilled at `per_sec`, holding at most one second's worth
+struct RetryBudget {
+    per_sec: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl RetryBudget {
+    fn new(per_sec: f64) -> Self {
+        Self {
+            per_sec,
+            state: Mutex::new((per_sec, Instant::now())),
+        }
+    }
+
+    fn try_take(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let (tokens, refilled) = &mut *state;
+        let now = Instant::now();
+        *tokens = (*tokens + now.duration_since(*refilled).as_secs_f64() * self.per_sec).min(self.per_sec);
+        *refilled = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+struct DeadLetters {
+    sink: String,
+    path: Option<PathBuf>,
+    file: Mutex<()>,
+    // Payloads are redacted before they reach the file or the log
+    redactor: Arc<Redactor>,
+    // Seals each line when encryption at rest is on
+    cipher: Arc<AtRestCipher>,
+}
+
+impl DeadLetters {
+    fn write(self: &Arc<Self>, event: &serde_json::Value, attempts: u32, reason: &str) {
+        let reason = self.redactor.redact_text(reason).into_owned();
+        if self.path.is_none() {
+            warn!("🪦 Daemon: Dropped event for sink {} after {} attempts: {}", self.sink, attempts, reason);
+            return;
+        }
+        let line = serde_json::json!({
+            "sink": self.sink,
+            "failedAt": Utc::now().to_rfc3339(),
+            "attempts": attempts,
+            "error": reason,
+            "event": self.redactor.redact_event(event),
+        });
+        // File writes block, so they stay off the runtime threads
+        let dead_letters = self.clone();
+        tokio::task::spawn_blocking(move || dead_letters.append(&line, &reason));
+    }
+
+    fn append(&self, line: &serde_json::Value, reason: &str) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let sealed = match self.cipher.seal(&line.to_string()) {
+            Ok(sealed) => sealed,
+            Err(e) => {
+                error!("❌ Daemon: Failed to seal dead letter for sink {}: {:#}", self.sink, e);
+                return;
+            }
+        };
+        let _guard = self.file.lock().unwrap();
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| OpenOptions::new().create(true).append(true).open(path))
+            .and_then(|mut file| writeln!(file, "{sealed}"));
+        match written {
+            Ok(()) => warn!("🪦 Daemon: Dead-lettered event for sink {}: {}", self.sink, reason),
+            Err(e) => error!("❌ Daemon: Failed to write dead letter {}: {}", path.display(), e),
+        }
+    }
+}
+
+// A bounded queue in front of one sink. `offer` never waits, so a slow sink
+// only ever backs up its own queue.
+pub struct SinkWorker {
+    name: String,
+    tx: mpsc::Sender<serde_json::Value>,
+    stats: Arc<SinkStats>,
+    dead_letters: Arc<DeadLetters>,
+}
+
+impl SinkWorker {
+    pub fn spawn(sink: Arc<dyn Sink>, config: &SinkConfig, redactor: Arc<Redactor>, cipher: Arc<AtRestCipher>) -> Self {
+        let name = sink.name().to_string();
+        let (tx, mut rx) = mpsc::channel::<serde_json::Value>(config.queue_capacity);
+        let stats = Arc::new(SinkStats::default());
+        let file_name: String = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        let dead_letters = Arc::new(DeadLetters {
+            sink: name.clone(),
+            path: config.dead_letter_dir.as_ref().map(|dir| dir.join(format!("{file_name}.jsonl"))),
+            file: Mutex::new(()),
+            redactor,
+            cipher,
+        });
+        let budget = Arc::new(RetryBudget::new(config.retry_budget));
+        let permits = Arc::new(Semaphore::new(config.concurrency));
+        let config = config.clone();
+
+        let worker_stats = stats.clone();
+        let worker_dead_letters = dead_letters.clone();
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                worker_stats.queued.fetch_sub(1, Ordering::Relaxed);
+                let Ok(permit) = permits.clone().acquire_owned().await else {
+                    break;
+                };
+                let sink = sink.clone();
+                let stats = worker_stats.clone();
+                let dead_letters = worker_dead_letters.clone();
+                let budget = budget.clone();
+                let config = config.clone();
+                tokio::spawn(async move {
+                    deliver_with_retries(&*sink, &event, &config, &budget, &stats, &dead_letters).await;
+                    drop(permit);
+                });
+            }
+        });
+
+        Self {
+            name,
+            tx,
+            stats,
+            dead_letters,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn stats(&self) -> Arc<SinkStats> {
+        self.stats.clone()
+    }
+
+    // False when the queue was full and the event went to the dead letters
+    pub fn offer(&self, event: serde_json::Value) -> bool {
+        // Counted before sending so the worker never decrements first
+        self.stats.queued.fetch_add(1, Ordering::Relaxed);
+        match self.tx.try_send(event) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full(event) | mpsc::error::TrySendError::Closed(event)) => {
+                self.stats.queued.fetch_sub(1, Ordering::Relaxed);
+                self.stats.dead_lettered.fetch_add(1, Ordering::Relaxed);
+                self.dead_letters.write(&event, 0, "queue full");
+                false
+            }
+        }
+    }
+}
+
+async fn deliver_with_retries(
+    sink: &dyn Sink,
+    event: &serde_json::Value,
+    config: &SinkConfig,
+    budget: &RetryBudget,
+    stats: &SinkStats,
+    dead_letters: &Arc<DeadLetters>,
+) {
+    let mut attempt = 1;
+    loop {
+        let error = match sink.deliver(event).await {
+            Ok(()) => {
+                stats.delivered.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            Err(e) => format!("{e:#}"),
+        };
+        if attempt >= config.max_attempts {
+            stats.dead_lettered.fetch_add(1, Ordering::Relaxed);
+            dead_letters.write(event, attempt, &error);
+            return;
+        }
+        if !budget.try_take() {
+            stats.dead_lettered.fetch_add(1, Ordering::Relaxed);
+            dead_letters.write(event, attempt, &format!("retry budget exhausted: {error}"));
+            return;
+        }
+        warn!("🔁 Daemon: Sink {} attempt {} failed, retrying: {}", sink.name(), attempt, error);
+        stats.retried.fetch_add(1, Ordering::Relaxed);
+        tokio::time::sleep(config.retry_base * 2u32.pow((attempt - 1).min(10))).await;
+        attempt += 1;
+    }
+}
@@ -0,0 +1,353 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use async_graphql::Schema;
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::config::env_parse;
+use crate::lifecycle::InternalEventKind;
+use crate::{ComponentDaemon, Mutation, Query, Subscription};
+
+// ========================
+// SOAK TEST
+// ========================
+
+// `component-daemon soak [seconds] [components/s] [report.json]`
+//
+// Runs a daemon in-process against a mock registry that publishes at a steady
+// rate and drops the connection every SOAK_DROP_SECS, with
+// SOAK_SUBSCRIBERS renderer subscriptions attached. Every SOAK_SAMPLE_SECS it
+// checks that:
+// - tracked memory and RSS don't keep growing past SOAK_MAX_GROWTH_PCT once
+//   SOAK_WARMUP_SECS have filled the store and history,
+// - no subscription goes SOAK_STALL_SECS without an update while the mock
+//   is publishing,
+// - the daemon reconnected about once per drop (± SOAK_RECONNECT_SLACK).
+// The report is printed as JSON (and written to the given path); the command
+// fails if any check did.
+pub async fn run(args: &[String]) -> Result<()> {
+    let duration = Duration::from_secs(match args.first() {
+        Some(raw) => raw.parse().context("seconds must be a number")?,
+        None => 3600,
+    });
+    let rate: u64 = match args.get(1) {
+        Some(raw) => raw.parse().context("components/s must be a number")?,
+        None => 50,
+    };
+    let report_path = args.get(2).cloned();
+    let config = SoakConfig {
+        duration,
+        rate: rate.max(1),
+        ids: env_parse("SOAK_IDS", 1000).max(1),
+        drop_every: Duration::from_secs(env_parse("SOAK_DROP_SECS", 300).max(1)),
+        sample_every: Duration::from_secs(env_parse("SOAK_SAMPLE_SECS", 10).max(1)),
+        subscribers: env_parse("SOAK_SUBSCRIBERS", 4),
+        stall: Duration::from_secs(env_parse("SOAK_STALL_SECS", 30)),
+        warmup: Duration::from_secs(env_parse("SOAK_WARMUP_SECS", 300)),
+        max_growth_pct: env_parse("SOAK_MAX_GROWTH_PCT", 20.0),
+        reconnect_slack: env_parse("SOAK_RECONNECT_SLACK", 2),
+    };
+    println!(
+        "soak: {:?} at {} components/s, registry drop every {:?}, {} subscribers",
+        config.duration, config.rate, config.drop_every, config.subscribers
+    );
+
+    let started = Instant::now();
+    let registry = Arc::new(MockRegistry::default());
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let port = listener.local_addr()?.port();
+    tokio::spawn(registry.clone().serve(listener, config.clone(), started));
+
+    // Read when the daemon connects, so this points it at the mock
+    std::env::set_var("REGISTRY_HOST", "127.0.0.1");
+    std::env::set_var("REGISTRY_PORT", port.to_string());
+    let daemon = ComponentDaemon::new()?;
+    let mut lifecycle = daemon.lifecycle().subscribe();
+    let connects = Arc::new(AtomicU64::new(0));
+    {
+        let connects = connects.clone();
+        tokio::spawn(async move {
+            while let Ok(event) = lifecycle.recv().await {
+                if event.kind == InternalEventKind::UpstreamConnected {
+                    connects.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+    }
+    daemon.start().await?;
+
+    let schema = Schema::build(Query, Mutation, Subscription)
+        .data(daemon.clone())
+        .finish();
+    let subscribers: Vec<Arc<Progress>> = (0..config.subscribers)
+        .map(|_| {
+            let progress = Arc::new(Progress::default());
+            let schema = schema.clone();
+            let tracked = progress.clone();
+            tokio::spawn(async move {
+                let mut stream = schema.execute_stream("subscription { rendererUpdate { id seq } }");
+                while let Some(response) = stream.next().await {
+                    if response.errors.is_empty() {
+                        tracked.mark(started);
+                    }
+                }
+            });
+            progress
+        })
+        .collect();
+
+    let mut samples = Vec::new();
+    let mut violations = Vec::new();
+    let mut ticker = tokio::time::interval(config.sample_every);
+    ticker.tick().await;
+    while started.elapsed() < config.duration {
+        ticker.tick().await;
+        let elapsed = started.elapsed();
+        let sample = Sample {
+            elapsed_secs: elapsed.as_secs(),
+            tracked_bytes: daemon.memory_used(),
+            rss_bytes: rss_bytes(),
+            published: registry.published.load(Ordering::Relaxed),
+            delivered: subscribers.iter().map(|progress| progress.delivered.load(Ordering::Relaxed)).collect(),
+            connects: connects.load(Ordering::Relaxed),
+        };
+
+        // Stuck means the registry kept publishing but nothing arrived
+        let publishing = registry.last_published.load(Ordering::Relaxed);
+        for (i, progress) in subscribers.iter().enumerate() {
+            let last = progress.last_at.load(Ordering::Relaxed);
+            let idle = Duration::from_millis(publishing.saturating_sub(last));
+            if last > 0 && idle > config.stall {
+                violations.push(format!(
+                    "subscriber {} received nothing for {:?} at {:?} while the registry kept publishing",
+                    i, idle, elapsed
+                ));
+            }
+        }
+        println!(
+            "soak: {:>6}s published={} tracked={}B rss={} connects={}",
+            sample.elapsed_secs,
+            sample.published,
+            sample.tracked_bytes,
+            sample.rss_bytes.map_or("n/a".to_string(), |rss| format!("{rss}B")),
+            sample.connects
+        );
+        samples.push(sample);
+    }
+
+    if subscribers.iter().any(|progress| progress.delivered.load(Ordering::Relaxed) == 0) {
+        violations.push("a subscriber never received an update".to_string());
+    }
+    let settled: Vec<&Sample> = samples
+        .iter()
+        .filter(|sample| sample.elapsed_secs >= config.warmup.as_secs())
+        .collect();
+    let tracked_growth = growth_pct(&settled, |sample| Some(sample.tracked_bytes));
+    let rss_growth = growth_pct(&settled, |sample| sample.rss_bytes);
+    for (label, growth) in [("tracked memory", tracked_growth), ("RSS", rss_growth)] {
+        if let Some(growth) = growth.filter(|growth| *growth > config.max_growth_pct) {
+            violations.push(format!(
+                "{} grew {:.1}% between the first and last quarter after warmup (limit {}%)",
+                label, growth, config.max_growth_pct
+            ));
+        }
+    }
+    let cycle = config.drop_every + crate::REGISTRY_RECONNECT_BACKOFF;
+    let expected_connects = config.duration.as_secs() / cycle.as_secs() + 1;
+    let observed_connects = connects.load(Ordering::Relaxed);
+    if observed_connects.abs_diff(expected_connects) > config.reconnect_slack {
+        violations.push(format!(
+            "connected {} times, expected {} ± {}",
+            observed_connects, expected_connects, config.reconnect_slack
+        ));
+    }
+
+    let report = SoakReport {
+        passed: violations.is_empty(),
+        duration_secs: started.elapsed().as_secs(),
+        rate: config.rate,
+        published: registry.published.load(Ordering::Relaxed),
+        delivered: subscribers.iter().map(|progress| progress.delivered.load(Ordering::Relaxed)).collect(),
+        expected_connects,
+        observed_connects,
+        tracked_growth_pct: tracked_growth,
+        rss_growth_pct: rss_growth,
+        violations,
+        samples,
+    };
+    let json = serde_json::to_string_pretty(&report)?;
+    println!("{json}");
+    if let Some(path) = report_path {
+        std::fs::write(&path, &json).with_context(|| format!("Failed to write report to {path}"))?;
+    }
+    if !report.passed {
+        bail!("soak failed: {}", report.violations.join("; "));
+    }
+    Ok(())
+}
+
+#[derive(Clone)]
+struct SoakConfig {
+    duration: Duration,
+    rate: u64,
+    // Published ids cycle through this many, so the store itself stays bounded
+    ids: u64,
+    drop_every: Duration,
+    sample_every: Duration,
+    subscribers: usize,
+    stall: Duration,
+    warmup: Duration,
+    max_growth_pct: f64,
+    reconnect_slack: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Sample {
+    elapsed_secs: u64,
+    tracked_bytes: u64,
+    rss_bytes: Option<u64>,
+    published: u64,
+    delivered: Vec<u64>,
+    connects: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SoakReport {
+    passed: bool,
+    duration_secs: u64,
+    rate: u64,
+    published: u64,
+    delivered: Vec<u64>,
+    expected_connects: u64,
+    observed_connects: u64,
+    tracked_growth_pct: Option<f64>,
+    rss_growth_pct: Option<f64>,
+    violations: Vec<String>,
+    samples: Vec<Sample>,
+}
+
+// Milliseconds are measured from the start of the run; 0 means never
+#[derive(Default)]
+struct Progress {
+    delivered: AtomicU64,
+    last_at: AtomicU64,
+}
+
+impl Progress {
+    fn mark(&self, started: Instant) {
+        self.delivered.fetch_add(1, Ordering::Relaxed);
+        self.last_at.store(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+#[derive(Default)]
+struct MockRegistry {
+    published: AtomicU64,
+    last_published: AtomicU64,
+}
+
+impl MockRegistry {
+    async fn serve(self: Arc<Self>, listener: TcpListener, config: SoakConfig, started: Instant) {
+        while let Ok((stream, _)) = listener.accept().await {
+            let registry = self.clone();
+            let config = config.clone();
+            tokio::spawn(async move {
+                if let Err(e) = registry.connection(stream, &config, started).await {
+                    eprintln!("soak: mock registry connection failed: {e:#}");
+                }
+            });
+        }
+    }
+
+    // Speaks just enough subscriptions-transport-ws for the daemon, then hangs
+    // up after `drop_every`
+    async fn connection(&self, stream: tokio::net::TcpStream, config: &SoakConfig, started: Instant) -> Result<()> {
+        // The handshake callback's error type is tungstenite's, not ours
+        #[allow(clippy::result_large_err)]
+        let echo_protocol = |request: &Request, mut response: Response| {
+            if let Some(protocol) = request.headers().get("sec-websocket-protocol") {
+                response.headers_mut().insert("sec-websocket-protocol", protocol.clone());
+            }
+            Ok(response)
+        };
+        let (mut write, mut read) = tokio_tungstenite::accept_hdr_async(stream, echo_protocol).await?.split();
+        let deadline = tokio::time::sleep(config.drop_every);
+        tokio::pin!(deadline);
+        let mut publish = tokio::time::interval(Duration::from_micros(1_000_000 / config.rate));
+        let mut subscribed = false;
+        loop {
+            tokio::select! {
+                _ = &mut deadline => break,
+                message = read.next() => {
+                    let Some(Ok(Message::Text(text))) = message else {
+                        return Ok(());
+                    };
+                    let message: serde_json::Value = serde_json::from_str(&text)?;
+                    match (message["type"].as_str(), message["id"].as_str()) {
+                        (Some("connection_init"), _) => {
+                            write.send(Message::Text(r#"{"type":"connection_ack"}"#.to_string())).await?;
+                        }
+                        (Some("start"), Some(id)) if id == crate::hydration::OPERATION_ID => {
+                            let data = serde_json::json!({
+                                "id": id,
+                                "type": "data",
+                                "payload": { "data": { "components": [] } },
+                            });
+                            write.send(Message::Text(data.to_string())).await?;
+                        }
+                        (Some("start"), _) => subscribed = true,
+                        _ => {}
+                    }
+                }
+                _ = publish.tick(), if subscribed => {
+                    let n = self.published.fetch_add(1, Ordering::Relaxed);
+                    let data = serde_json::json!({
+                        "id": "registry-sub",
+                        "type": "data",
+                        "payload": { "data": { "componentUpdate": {
+                            "id": format!("soak-{}", n % config.ids),
+                            "type": "CARD",
+                            "data": { "title": "Soak", "content": format!("update {n}") },
+                            "createdAt": chrono::Utc::now(),
+                        } } },
+                    });
+                    write.send(Message::Text(data.to_string())).await?;
+                    self.last_published.store(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+                }
+            }
+        }
+        let _ = write.send(Message::Close(None)).await;
+        Ok(())
+    }
+}
+
+// Mean of the last quarter of samples against the mean of the first quarter
+fn growth_pct(samples: &[&Sample], value: impl Fn(&Sample) -> Option<u64>) -> Option<f64> {
+    let quarter = samples.len() / 4;
+    if quarter == 0 {
+        return None;
+    }
+    let mean = |window: &[&Sample]| -> Option<f64> {
+        let values: Vec<u64> = window.iter().filter_map(|sample| value(sample)).collect();
+        (!values.is_empty()).then(|| values.iter().sum::<u64>() as f64 / values.len() as f64)
+    };
+    let first = mean(&samples[..quarter])?;
+    let last = mean(&samples[samples.len() - quarter..])?;
+    (first > 0.0).then(|| (last - first) / first * 100.0)
+}
+
+// Resident set size, where /proc has it
+fn rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
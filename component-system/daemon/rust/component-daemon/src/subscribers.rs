@@ -0,0 +1,308 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::at_rest::AtRestCipher;
+use crate::blobs::{BackendStatus, DegradationPolicy};
+use crate::config::env_parse;
+
+// ========================
+// SUBSCRIBER REGISTRY
+// ========================
+
+// The `clientId` a renderer sent in connection_init, kept in the
+// connection's data so subscriptions can report deliveries against it
+#[derive(Clone, Debug)]
+pub struct SubscriberId(pub String);
+
+#[derive(Clone, Debug, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriberConnection {
+    pub connected_at: DateTime<Utc>,
+    pub disconnected_at: Option<DateTime<Utc>>,
+    pub protocol: String,
+    // Updates delivered over this connection
+    pub delivered: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct Subscriber {
+    pub client_id: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub active_connections: u32,
+    // Every update up to this seq has been delivered to the renderer
+    pub last_delivered_seq: Option<u64>,
+    // Oldest first, bounded by SUBSCRIBER_CONNECTIONS_MAX
+    pub connections: Vec<SubscriberConnection>,
+}
+
+// Renderers that identify themselves with a `clientId` in connection_init
+// are remembered here. A renderer that reconnects within
+// SUBSCRIBER_RESUME_SECS and subscribes without `afterSeq` resumes after the
+// last seq it was sent. With SUBSCRIBER_REGISTRY_PATH set, the registry is
+// written there every SUBSCRIBER_FLUSH_SECS, sealed when AT_REST_KEYS is
+// set, and reloaded on startup.
+// Entries are keyed by the connection's principal and clientId, so one
+// caller can't resume or inspect another's renderer. clientIds themselves
+// aren't authenticated, so renderers disconnected for longer than
+// SUBSCRIBER_RETAIN_SECS (default 7 days) are forgotten, and at most
+// SUBSCRIBER_REGISTRY_MAX (default 10000) are kept, evicting the longest
+// disconnected first. A failed save leaves the registry in memory and is
+// retried on the next flush; SUBSCRIBER_REGISTRY_DEGRADATION (memory, unready
+// or off; default memory) decides whether that reports degraded, fails
+// readiness, or is only logged.
+pub struct SubscriberRegistry {
+    subscribers: DashMap<String, Subscriber>,
+    path: Option<PathBuf>,
+    flush_every: Duration,
+    resume_window: Duration,
+    retain: Duration,
+    max: usize,
+    connections_max: usize,
+    dirty: AtomicBool,
+    policy: DegradationPolicy,
+    degraded: AtomicBool,
+    // When the file last failed, and why
+    failure: Mutex<Option<(DateTime<Utc>, String)>>,
+    // Seals the file as a single record when encryption at rest is on
+    cipher: Arc<AtRestCipher>,
+}
+
+impl SubscriberRegistry {
+    pub fn from_env(cipher: Arc<AtRestCipher>) -> Self {
+        let path = std::env::var("SUBSCRIBER_REGISTRY_PATH").ok().map(PathBuf::from);
+        let subscribers = DashMap::new();
+        if let Some(path) = path.as_ref().filter(|path| path.exists()) {
+            match load(path, &cipher) {
+                Ok(loaded) => {
+                    info!("👥 Daemon: Loaded {} known subscribers from {}", loaded.len(), path.display());
+                    for subscriber in loaded {
+                        subscribers.insert(subscriber.client_id.clone(), subscriber);
+                    }
+                }
+                Err(e) => error!("❌ Daemon: Failed to load subscriber registry: {:#}", e),
+            }
+        }
+        let registry = Self {
+            subscribers,
+            path,
+            flush_every: Duration::from_secs(env_parse("SUBSCRIBER_FLUSH_SECS", 5).max(1)),
+            resume_window: Duration::from_secs(env_parse("SUBSCRIBER_RESUME_SECS", 300)),
+            retain: Duration::from_secs(env_parse("SUBSCRIBER_RETAIN_SECS", 7 * 24 * 3600)),
+            max: env_parse("SUBSCRIBER_REGISTRY_MAX", 10_000usize).max(1),
+            connections_max: env_parse("SUBSCRIBER_CONNECTIONS_MAX", 20usize).max(1),
+            dirty: AtomicBool::new(false),
+            policy: DegradationPolicy::from_var("SUBSCRIBER_REGISTRY_DEGRADATION"),
+            degraded: AtomicBool::new(false),
+            failure: Mutex::new(None),
+            cipher,
+        };
+        registry.prune();
+        registry
+    }
+
+    // Forgets renderers disconnected for longer than SUBSCRIBER_RETAIN_SECS
+    fn prune(&self) {
+        let now = Utc::now();
+        let before = self.subscribers.len();
+        self.subscribers.retain(|_, subscriber| {
+            subscriber.active_connections > 0 || (now - subscriber.last_seen).to_std().unwrap_or_default() <= self.retain
+        });
+        if self.subscribers.len() < before {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    // Makes room for a new renderer; false if every one tracked is connected
+    fn make_room(&self) -> bool {
+        if self.subscribers.len() < self.max {
+            return true;
+        }
+        self.prune();
+        while self.subscribers.len() >= self.max {
+            let oldest = self
+                .subscribers
+                .iter()
+                .filter(|entry| entry.active_connections == 0)
+                .min_by_key(|entry| entry.last_seen)
+                .map(|entry| entry.key().clone());
+            let Some(oldest) = oldest else {
+                return false;
+            };
+            self.subscribers.remove(&oldest);
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        true
+    }
+
+    pub fn connected(&self, client_id: &str, protocol: &str) {
+        if !self.subscribers.contains_key(client_id) && !self.make_room() {
+            warn!("👥 Daemon: Subscriber registry full, not tracking {}", client_id);
+            return;
+        }
+        let now = Utc::now();
+        let mut subscriber = self.subscribers.entry(client_id.to_string()).or_insert_with(|| Subscriber {
+            client_id: client_id.to_string(),
+            first_seen: now,
+            last_seen: now,
+            active_connections: 0,
+            last_delivered_seq: None,
+            connections: Vec::new(),
+        });
+        subscriber.last_seen = now;
+        subscriber.active_connections += 1;
+        subscriber.connections.push(SubscriberConnection {
+            connected_at: now,
+            disconnected_at: None,
+            protocol: protocol.to_string(),
+            delivered: 0,
+        });
+        let excess = subscriber.connections.len().saturating_sub(self.connections_max);
+        subscriber.connections.drain(..excess);
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    pub fn disconnected(&self, client_id: &str) {
+        let Some(mut subscriber) = self.subscribers.get_mut(client_id) else {
+            return;
+        };
+        let now = Utc::now();
+        subscriber.last_seen = now;
+        subscriber.active_connections = subscriber.active_connections.saturating_sub(1);
+        if let Some(connection) = subscriber.connections.iter_mut().find(|c| c.disconnected_at.is_none()) {
+            connection.disconnected_at = Some(now);
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    pub fn delivered(&self, client_id: &str, seq: u64) {
+        let Some(mut subscriber) = self.subscribers.get_mut(client_id) else {
+            return;
+        };
+        subscriber.last_seen = Utc::now();
+        subscriber.last_delivered_seq = Some(subscriber.last_delivered_seq.map_or(seq, |last| last.max(seq)));
+        if let Some(connection) = subscriber.connections.iter_mut().rev().find(|c| c.disconnected_at.is_none()) {
+            connection.delivered += 1;
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    // Where a renderer that just reconnected should pick up, if its previous
+    // connection ended recently enough
+    pub fn resume_cursor(&self, client_id: &str) -> Option<u64> {
+        let subscriber = self.subscribers.get(client_id)?;
+        let previous_end = subscriber
+            .connections
+            .iter()
+            .filter_map(|connection| connection.disconnected_at)
+            .max()?;
+        let gap = (Utc::now() - previous_end).to_std().unwrap_or_default();
+        (gap <= self.resume_window).then_some(subscriber.last_delivered_seq).flatten()
+    }
+
+    pub fn list(&self) -> Vec<Subscriber> {
+        let mut subscribers: Vec<Subscriber> = self.subscribers.iter().map(|entry| entry.clone()).collect();
+        subscribers.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+        subscribers
+    }
+
+    fn save_succeeded(&self) {
+        let mut failure = self.failure.lock().unwrap();
+        if self.degraded.swap(false, Ordering::Relaxed) {
+            info!("👥 Daemon: Subscriber registry file recovered");
+        }
+        *failure = None;
+    }
+
+    fn save_failed(&self, e: &anyhow::Error) {
+        if self.policy == DegradationPolicy::Off {
+            error!("❌ Daemon: Failed to save subscriber registry: {:#}", e);
+            return;
+        }
+        // Saved again on the next flush
+        self.dirty.store(true, Ordering::Relaxed);
+        let mut failure = self.failure.lock().unwrap();
+        let since = failure.as_ref().map_or_else(Utc::now, |(since, _)| *since);
+        *failure = Some((since, format!("{e:#}")));
+        if !self.degraded.swap(true, Ordering::Relaxed) {
+            error!("❌ Daemon: Subscriber registry degraded, keeping it in memory until the file recovers: {:#}", e);
+        }
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    // Whether readiness should fail while degraded
+    pub fn blocks_readiness(&self) -> bool {
+        self.policy == DegradationPolicy::Unready && self.is_degraded()
+    }
+
+    pub fn status(&self) -> BackendStatus {
+        let failure = self.failure.lock().unwrap();
+        let degraded = self.is_degraded();
+        BackendStatus {
+            degraded,
+            since: failure.as_ref().map(|(since, _)| *since),
+            last_error: failure.as_ref().map(|(_, error)| error.clone()),
+            // The whole registry is rewritten on the next successful flush
+            pending_writes: usize::from(degraded),
+            pending_bytes: 0,
+        }
+    }
+
+    pub fn spawn_persistence(self: &Arc<Self>) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        let registry = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(registry.flush_every);
+            loop {
+                ticker.tick().await;
+                registry.prune();
+                if !registry.dirty.swap(false, Ordering::Relaxed) {
+                    continue;
+                }
+                match save(&path, &registry.cipher, &registry.list()) {
+                    Ok(()) => registry.save_succeeded(),
+                    Err(e) => registry.save_failed(&e),
+                }
+            }
+        });
+    }
+}
+
+fn load(path: &Path, cipher: &AtRestCipher) -> Result<Vec<Subscriber>> {
+    let raw = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let raw = cipher.open(raw.trim_end()).with_context(|| format!("Failed to decrypt {}", path.display()))?;
+    let mut subscribers: Vec<Subscriber> =
+        serde_json::from_str(&raw).with_context(|| format!("Invalid subscriber registry {}", path.display()))?;
+    // Connections open at the last save ended with the previous process
+    for subscriber in &mut subscribers {
+        subscriber.active_connections = 0;
+        for connection in subscriber.connections.iter_mut().filter(|c| c.disconnected_at.is_none()) {
+            connection.disconnected_at = Some(subscriber.last_seen);
+        }
+    }
+    Ok(subscribers)
+}
+
+// Written to a sibling file first so a crash never leaves a torn registry
+fn save(path: &Path, cipher: &AtRestCipher, subscribers: &[Subscriber]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, cipher.seal(&serde_json::to_string_pretty(subscribers)?)?)
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
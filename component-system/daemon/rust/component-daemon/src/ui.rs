@@ -0,0 +1,22 @@
+use warp::Filter;
+
+// ========================
+// DEBUG UI
+// ========================
+
+// Self-contained live view of the component stream for operators; it talks to
+// the same /graphql endpoints as a renderer. DEBUG_UI=off disables it.
+const PAGE: &str = include_str!("ui.html");
+
+pub fn routes() -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let enabled = !matches!(std::env::var("DEBUG_UI").as_deref(), Ok("off") | Ok("none"));
+    warp::path!("ui")
+        .and(warp::get())
+        .and_then(move || async move {
+            if enabled {
+                Ok(warp::reply::html(PAGE))
+            } else {
+                Err(warp::reject::not_found())
+            }
+        })
+}
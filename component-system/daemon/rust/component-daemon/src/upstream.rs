@@ -0,0 +1,112 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use dashmap::DashMap;
+use tokio::sync::{mpsc, oneshot, Mutex, MutexGuard};
+use tracing::warn;
+
+use crate::config::env_parse;
+
+// ========================
+// UPSTREAM OPERATIONS
+// ========================
+
+pub const OPERATION_ID_PREFIX: &str = "upstream-";
+
+// With BIDIRECTIONAL=true the daemon sends GraphQL operations (acks and the
+// like) back to the registry over the subscription connection. Operations are
+// queued while the registry is unreachable, up to UPSTREAM_QUEUE_SIZE.
+pub struct Upstream {
+    enabled: bool,
+    // Set once the registry acknowledged the connection
+    ready: AtomicBool,
+    tx: mpsc::Sender<String>,
+    rx: Mutex<mpsc::Receiver<String>>,
+    // Operations whose caller waits for the registry's response payload
+    waiting: DashMap<String, oneshot::Sender<serde_json::Value>>,
+}
+
+impl Upstream {
+    pub fn from_env() -> Self {
+        let (tx, rx) = mpsc::channel(env_parse("UPSTREAM_QUEUE_SIZE", 1000));
+        Self {
+            enabled: env_parse("BIDIRECTIONAL", false),
+            ready: AtomicBool::new(false),
+            tx,
+            rx: Mutex::new(rx),
+            waiting: DashMap::new(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_ready(&self, ready: bool) {
+        self.ready.store(ready, Ordering::Relaxed);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.enabled && self.ready.load(Ordering::Relaxed)
+    }
+
+    // Queues a `start` message; false when disabled or the queue is full
+    pub fn send_operation(&self, query: &str, variables: serde_json::Value) -> bool {
+        let id = format!("{}{}", OPERATION_ID_PREFIX, uuid::Uuid::new_v4().simple());
+        self.enqueue(&id, query, variables)
+    }
+
+    // Sends an operation and waits for the registry's first response payload.
+    // On timeout the operation may still reach the registry later.
+    pub async fn request(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+        timeout: Duration,
+    ) -> Result<serde_json::Value, String> {
+        let id = format!("{}{}", OPERATION_ID_PREFIX, uuid::Uuid::new_v4().simple());
+        let (tx, rx) = oneshot::channel();
+        self.waiting.insert(id.clone(), tx);
+        if !self.enqueue(&id, query, variables) {
+            self.waiting.remove(&id);
+            return Err("registry queue unavailable".to_string());
+        }
+        let response = tokio::time::timeout(timeout, rx).await;
+        self.waiting.remove(&id);
+        match response {
+            Ok(Ok(payload)) => Ok(payload),
+            Ok(Err(_)) => Err("registry closed the operation without a result".to_string()),
+            Err(_) => Err(format!("no response from registry within {timeout:?}")),
+        }
+    }
+
+    // Hands a response to the waiting caller, if any
+    pub fn complete(&self, id: &str, payload: serde_json::Value) {
+        if let Some((_, tx)) = self.waiting.remove(id) {
+            let _ = tx.send(payload);
+        }
+    }
+
+    fn enqueue(&self, id: &str, query: &str, variables: serde_json::Value) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let message = serde_json::json!({
+            "id": id,
+            "type": "start",
+            "payload": { "query": query, "variables": variables },
+        });
+        match self.tx.try_send(message.to_string()) {
+            Ok(()) => true,
+            Err(_) => {
+                warn!("⚠️ Daemon: Upstream queue full, dropping operation to registry");
+                false
+            }
+        }
+    }
+
+    // Held by the registry connection for as long as it is up
+    pub async fn outbox(&self) -> MutexGuard<'_, mpsc::Receiver<String>> {
+        self.rx.lock().await
+    }
+}
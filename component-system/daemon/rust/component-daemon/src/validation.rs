@@ -0,0 +1,201 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use async_graphql::SimpleObject;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::payload::TypedComponent;
+use crate::ComponentType;
+
+// ========================
+// VALIDATION
+// ========================
+
+#[derive(Clone, Debug, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct Violation {
+    pub path: String,
+    pub rule: String,
+    pub message: String,
+}
+
+impl Violation {
+    pub(crate) fn new(path: impl Into<String>, rule: &str, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            rule: rule.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, SimpleObject)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub violations: Vec<Violation>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidationMode {
+    Off,
+    Warn,
+    Reject,
+}
+
+impl ValidationMode {
+    pub fn from_env() -> Self {
+        Self::from_var("VALIDATION_MODE")
+    }
+
+    pub fn from_var(name: &str) -> Self {
+        match std::env::var(name).as_deref() {
+            Ok("off") => ValidationMode::Off,
+            Ok("reject") => ValidationMode::Reject,
+            _ => ValidationMode::Warn,
+        }
+    }
+}
+
+pub struct Validator {
+    schemas: HashMap<ComponentType, jsonschema::Validator>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self {
+            schemas: HashMap::new(),
+        }
+    }
+
+    // Loads `card.schema.json`, `notification.schema.json` and `form.schema.json`
+    // from COMPONENT_SCHEMA_DIR when set; missing files simply skip the schema step.
+    pub fn from_env() -> Result<Self> {
+        Self::from_var("COMPONENT_SCHEMA_DIR")
+    }
+
+    pub fn from_var(name: &str) -> Result<Self> {
+        let mut validator = Self::new();
+        let Ok(dir) = std::env::var(name) else {
+            return Ok(validator);
+        };
+
+        for (r#type, file) in [
+            (ComponentType::Card, "card.schema.json"),
+            (ComponentType::Notification, "notification.schema.json"),
+            (ComponentType::Form, "form.schema.json"),
+        ] {
+            let path = Path::new(&dir).join(file);
+            if !path.exists() {
+                continue;
+            }
+            let raw = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read schema {}", path.display()))?;
+            let schema: serde_json::Value = serde_json::from_str(&raw)
+                .with_context(|| format!("Failed to parse schema {}", path.display()))?;
+            let compiled = jsonschema::validator_for(&schema)
+                .map_err(|e| anyhow::anyhow!("Invalid schema {}: {}", path.display(), e))?;
+            info!("📐 Daemon: Loaded JSON Schema for {:?} from {}", r#type, path.display());
+            validator.schemas.insert(r#type, compiled);
+        }
+
+        Ok(validator)
+    }
+
+    pub fn validate(&self, r#type: ComponentType, data: &serde_json::Value) -> ValidationReport {
+        let mut violations = Vec::new();
+
+        if let Some(schema) = self.schemas.get(&r#type) {
+            violations.extend(schema.iter_errors(data).map(|e| {
+                Violation::new(e.instance_path().to_string(), "schema", e.to_string())
+            }));
+        }
+
+        match TypedComponent::parse(r#type, data) {
+            Ok(typed) => violations.extend(type_rules(&typed)),
+            Err(e) => violations.push(Violation::new("", "shape", e.to_string())),
+        }
+
+        ValidationReport {
+            valid: violations.is_empty(),
+            violations,
+        }
+    }
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn type_rules(typed: &TypedComponent) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    match typed {
+        TypedComponent::Card(card) => {
+            if card.title.is_none() && card.content.is_none() {
+                violations.push(Violation::new("", "card.empty", "Card needs a title or content"));
+            }
+            for (i, button) in card.buttons.iter().enumerate() {
+                if button.text.trim().is_empty() {
+                    violations.push(Violation::new(
+                        format!("/buttons/{i}/text"),
+                        "card.button_text",
+                        "Button text must not be empty",
+                    ));
+                }
+            }
+        }
+        TypedComponent::Notification(notification) => {
+            if notification.message.trim().is_empty() {
+                violations.push(Violation::new(
+                    "/message",
+                    "notification.message",
+                    "Notification message must not be empty",
+                ));
+            }
+            if matches!(notification.auto_remove, Some(ms) if ms <= 0) {
+                violations.push(Violation::new(
+                    "/autoRemove",
+                    "notification.auto_remove",
+                    "autoRemove must be a positive number of milliseconds",
+                ));
+            }
+        }
+        TypedComponent::Form(form) => {
+            if form.fields.is_empty() {
+                violations.push(Violation::new("/fields", "form.fields", "Form needs at least one field"));
+            }
+            let mut seen = HashSet::new();
+            for (i, field) in form.fields.iter().enumerate() {
+                if field.name.trim().is_empty() {
+                    violations.push(Violation::new(
+                        format!("/fields/{i}/name"),
+                        "form.field_name",
+                        "Field name must not be empty",
+                    ));
+                } else if !seen.insert(field.name.as_str()) {
+                    violations.push(Violation::new(
+                        format!("/fields/{i}/name"),
+                        "form.field_name",
+                        format!("Duplicate field name '{}'", field.name),
+                    ));
+                }
+            }
+        }
+        TypedComponent::Raw(_) => {}
+    }
+
+    violations
+}
+
+pub fn log_violations(id: &str, report: &ValidationReport) {
+    for violation in &report.violations {
+        warn!(
+            "⚠️ Daemon: Component {} failed {} at '{}': {}",
+            id, violation.rule, violation.path, violation.message
+        );
+    }
+}
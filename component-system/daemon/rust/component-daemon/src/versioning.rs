@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use async_graphql::extensions::{Extension, ExtensionContext, ExtensionFactory, NextResolve, ResolveInfo};
+use async_graphql::{Name, ServerResult, SimpleObject, Value};
+
+// ========================
+// API VERSIONS
+// ========================
+
+// 1: the original component shape (id, type, data, createdAt)
+// 2: typed payloads, versions and seq, priority, relationships, labels,
+//    deliverAt, removals
+// 3: acknowledgements and upstreamSeq
+pub const API_VERSION: u32 = 3;
+pub const MIN_API_VERSION: u32 = 1;
+
+// Component fields and the version that introduced them
+const FIELDS: &[(&str, &str, u32)] = &[
+    ("Component", "typedData", 2),
+    ("Component", "priority", 2),
+    ("Component", "version", 2),
+    ("Component", "seq", 2),
+    ("Component", "parentId", 2),
+    ("Component", "relatedIds", 2),
+    ("Component", "parent", 2),
+    ("Component", "children", 2),
+    ("Component", "related", 2),
+    ("Component", "labels", 2),
+    ("Component", "deliverAt", 2),
+    ("Component", "acknowledged", 3),
+    ("Component", "upstreamSeq", 3),
+];
+
+// Enum values older renderers see as OTHER
+const ENUM_VALUES: &[(&str, &str, u32)] = &[("RemovalReason", "ACKNOWLEDGED", 3)];
+
+// The version a renderer declared, clamped to what this daemon serves.
+// Renderers that declare nothing get the current version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ApiVersion(pub u32);
+
+impl ApiVersion {
+    pub fn parse(raw: &str) -> Option<Self> {
+        raw.trim()
+            .trim_start_matches(['v', 'V'])
+            .parse::<u32>()
+            .ok()
+            .map(|version| Self(version.clamp(MIN_API_VERSION, API_VERSION)))
+    }
+
+    // `apiVersion` in the connection_init payload, as a number or a string
+    pub fn from_init_payload(payload: &serde_json::Value) -> Option<Self> {
+        match &payload["apiVersion"] {
+            serde_json::Value::Number(version) => version.as_u64().and_then(|v| Self::parse(&v.to_string())),
+            serde_json::Value::String(version) => Self::parse(version),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ApiVersion {
+    fn default() -> Self {
+        Self(API_VERSION)
+    }
+}
+
+#[derive(Clone, Debug, SimpleObject)]
+pub struct ApiVersionInfo {
+    pub current: u32,
+    pub minimum: u32,
+    // What this request is being served as
+    pub negotiated: u32,
+}
+
+impl ApiVersionInfo {
+    pub fn new(negotiated: ApiVersion) -> Self {
+        Self {
+            current: API_VERSION,
+            minimum: MIN_API_VERSION,
+            negotiated: negotiated.0,
+        }
+    }
+}
+
+// Shapes responses for renderers on an older version: fields newer than the
+// declared version resolve to null without running, and enum values they
+// don't know come back as OTHER.
+pub struct ApiVersioning;
+
+impl ExtensionFactory for ApiVersioning {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(ApiVersioningExtension)
+    }
+}
+
+struct ApiVersioningExtension;
+
+#[async_trait::async_trait]
+impl Extension for ApiVersioningExtension {
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        let version = ctx.data_opt::<ApiVersion>().copied().unwrap_or_default().0;
+        if version >= API_VERSION {
+            return next.run(ctx, info).await;
+        }
+        let newer = FIELDS
+            .iter()
+            .any(|(parent, field, since)| *parent == info.parent_type && *field == info.name && *since > version);
+        if newer {
+            return Ok(None);
+        }
+
+        let return_type = info.return_type.trim_matches(|c| matches!(c, '[' | ']' | '!'));
+        let mut value = next.run(ctx, info).await?;
+        if let Some(value) = value.as_mut() {
+            map_enum_values(value, return_type, version);
+        }
+        Ok(value)
+    }
+}
+
+fn map_enum_values(value: &mut Value, type_name: &str, version: u32) {
+    match value {
+        Value::Enum(name) => {
+            let unknown = ENUM_VALUES
+                .iter()
+                .any(|(ty, enum_value, since)| *ty == type_name && name.as_str() == *enum_value && *since > version);
+            if unknown {
+                *name = Name::new("OTHER");
+            }
+        }
+        Value::List(items) => {
+            for item in items {
+                map_enum_values(item, type_name, version);
+            }
+        }
+        _ => {}
+    }
+}
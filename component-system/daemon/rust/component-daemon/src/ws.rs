@@ -0,0 +1,352 @@
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_graphql::http::{WebSocket as GraphqlWebSocket, WebSocketProtocols, WsMessage};
+use async_graphql::{Data, Executor};
+use futures_util::{future, SinkExt, StreamExt};
+use tracing::{info, warn};
+use warp::ws::{Message, WebSocket};
+use warp::{Filter, Reply};
+
+use crate::auth::Principal;
+use crate::config::env_parse;
+use crate::listeners::BearerAuth;
+use crate::metrics::Metrics;
+use crate::quotas::ApiKey;
+use crate::subscribers::{SubscriberId, SubscriberRegistry};
+use crate::versioning::ApiVersion;
+
+// ========================
+// DOWNSTREAM WEBSOCKETS
+// ========================
+
+// Close code sent to peers that stopped answering keep-alives
+const IDLE_CLOSE_CODE: u16 = 4408;
+
+#[derive(Clone, Copy, Debug)]
+pub struct KeepAliveConfig {
+    // None disables pings and reaping
+    pub interval: Option<Duration>,
+    pub idle_timeout: Duration,
+}
+
+impl KeepAliveConfig {
+    // WS_KEEPALIVE_SECS (0 = off) sets the ping period; peers silent for
+    // WS_IDLE_TIMEOUT_SECS are closed and counted as reaped.
+    pub fn from_env() -> Self {
+        let interval = env_parse("WS_KEEPALIVE_SECS", 15u64);
+        Self {
+            interval: (interval > 0).then(|| Duration::from_secs(interval)),
+            idle_timeout: Duration::from_secs(env_parse("WS_IDLE_TIMEOUT_SECS", interval * 3)),
+        }
+    }
+}
+
+// How the subscription protocol is chosen for each connection.
+// WS_SUBPROTOCOLS lists the accepted subprotocols in preference order; when a
+// client offers several, the first one listed here wins. Clients that offer
+// none get WS_DEFAULT_SUBPROTOCOL, or with `auto` (the default) the protocol
+// is detected from their first operation message, which has to arrive
+// within WS_DETECT_TIMEOUT_SECS (default 10).
+#[derive(Clone, Debug)]
+pub struct SubprotocolConfig {
+    accepted: Vec<WebSocketProtocols>,
+    unnegotiated: Option<WebSocketProtocols>,
+    detect_timeout: Duration,
+}
+
+impl SubprotocolConfig {
+    pub fn from_env() -> Self {
+        let mut accepted: Vec<WebSocketProtocols> = std::env::var("WS_SUBPROTOCOLS")
+            .unwrap_or_else(|_| "graphql-transport-ws,graphql-ws".to_string())
+            .split(',')
+            .filter_map(|name| {
+                name.trim().parse().inspect_err(|_| {
+                    warn!("⚠️ Daemon: Ignoring unknown subprotocol '{}' in WS_SUBPROTOCOLS", name.trim());
+                }).ok()
+            })
+            .collect();
+        accepted.dedup();
+        if accepted.is_empty() {
+            accepted = vec![WebSocketProtocols::GraphQLWS, WebSocketProtocols::SubscriptionsTransportWS];
+        }
+        let unnegotiated = match std::env::var("WS_DEFAULT_SUBPROTOCOL").as_deref().map(str::trim) {
+            Ok("auto") | Err(_) => None,
+            Ok(name) => match name.parse() {
+                Ok(protocol) => Some(protocol),
+                Err(_) => {
+                    warn!("⚠️ Daemon: Ignoring invalid WS_DEFAULT_SUBPROTOCOL='{}'", name);
+                    None
+                }
+            },
+        };
+        Self {
+            accepted,
+            unnegotiated,
+            // Not the keep-alive idle timeout, which is 0 with keep-alives off
+            detect_timeout: Duration::from_secs(env_parse("WS_DETECT_TIMEOUT_SECS", 10u64).max(1)),
+        }
+    }
+
+    // Ok(None) means detect; Err lists what the client would have to offer
+    fn negotiate(&self, offered: Option<&str>) -> Result<Option<WebSocketProtocols>, String> {
+        let Some(offered) = offered.filter(|offered| !offered.trim().is_empty()) else {
+            return Ok(self.unnegotiated);
+        };
+        let offered: Vec<WebSocketProtocols> = offered
+            .split(',')
+            .filter_map(|name| name.trim().parse().ok())
+            .collect();
+        self.accepted
+            .iter()
+            .find(|protocol| offered.contains(protocol))
+            .map(|protocol| Some(*protocol))
+            .ok_or_else(|| {
+                let names: Vec<&str> = self.accepted.iter().map(|p| p.sec_websocket_protocol()).collect();
+                format!("Unsupported Sec-WebSocket-Protocol, expected one of: {}", names.join(", "))
+            })
+    }
+}
+
+// Keeps the active-subscriber gauge right however the connection ends
+struct ActiveGuard(Arc<Metrics>);
+
+impl ActiveGuard {
+    fn new(metrics: Arc<Metrics>) -> Self {
+        metrics.subscribers_active.fetch_add(1, Ordering::Relaxed);
+        Self(metrics)
+    }
+}
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        self.0.subscribers_active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// Drop-in for `async_graphql_warp::graphql_subscription` that also sends
+// keep-alives, reaps peers that stop responding and serves both graphql-ws
+// variants per SubprotocolConfig.
+pub fn graphql_subscription<E: Executor>(
+    executor: E,
+    metrics: Arc<Metrics>,
+    keepalive: KeepAliveConfig,
+    subprotocols: SubprotocolConfig,
+    subscribers: Arc<SubscriberRegistry>,
+    auth: BearerAuth,
+) -> impl Filter<Extract = (warp::reply::Response,), Error = warp::Rejection> + Clone {
+    warp::ws()
+        .and(warp::header::optional::<String>("sec-websocket-protocol"))
+        .and(crate::api_version())
+        .and(auth.principal())
+        .map(move |ws: warp::ws::Ws, offered: Option<String>, version: ApiVersion, principal: Option<Principal>| {
+            let protocol = match subprotocols.negotiate(offered.as_deref()) {
+                Ok(protocol) => protocol,
+                Err(message) => {
+                    warn!("🚫 Daemon: Refused subscriber offering '{}'", offered.unwrap_or_default());
+                    return warp::reply::with_status(message, warp::http::StatusCode::BAD_REQUEST)
+                        .into_response();
+                }
+            };
+            let executor = executor.clone();
+            let metrics = metrics.clone();
+            let subscribers = subscribers.clone();
+            let detect_timeout = subprotocols.detect_timeout;
+            // The upgrade request's API version and key; connection_init can
+            // override the version
+            let mut connection_data = Data::default();
+            connection_data.insert(version);
+            let key = ApiKey::from_principal(principal.as_ref());
+            let owner = key.0.clone();
+            connection_data.insert(key);
+            let reply = ws.on_upgrade(move |socket| {
+                let choice = match protocol {
+                    Some(protocol) => Choice::Negotiated(protocol),
+                    None => Choice::Detect { within: detect_timeout },
+                };
+                serve(socket, executor, choice, connection_data, metrics, keepalive, subscribers, owner)
+            });
+            // Echo a subprotocol only to clients that offered one
+            match protocol.filter(|_| offered.is_some()) {
+                Some(protocol) => warp::reply::with_header(
+                    reply,
+                    "Sec-WebSocket-Protocol",
+                    protocol.sec_websocket_protocol(),
+                )
+                .into_response(),
+                None => reply.into_response(),
+            }
+        })
+}
+
+// What the upgrade settled on for a connection
+enum Choice {
+    Negotiated(WebSocketProtocols),
+    // Detect from the first messages, giving up after `within`
+    Detect { within: Duration },
+}
+
+// Reads until a message only one protocol uses. The shared connection_init
+// is acknowledged right away, since graphql-transport-ws clients wait for
+// the ack before sending anything else.
+async fn detect_protocol<S, W>(
+    incoming: &mut S,
+    sink: &mut W,
+    within: Duration,
+) -> Option<(WebSocketProtocols, Vec<Vec<u8>>, bool)>
+where
+    S: futures_util::Stream<Item = Vec<u8>> + Unpin,
+    W: futures_util::Sink<Message> + Unpin,
+{
+    let mut buffered = Vec::new();
+    let mut acked = false;
+    let detect = async {
+        while let Some(bytes) = incoming.next().await {
+            let kind = serde_json::from_slice::<serde_json::Value>(&bytes)
+                .ok()
+                .and_then(|message| message["type"].as_str().map(str::to_string));
+            buffered.push(bytes);
+            let protocol = match kind.as_deref() {
+                Some("connection_init") if !acked => {
+                    acked = true;
+                    sink.send(Message::text(r#"{"type":"connection_ack"}"#)).await.ok()?;
+                    continue;
+                }
+                Some("start" | "stop" | "connection_terminate") => WebSocketProtocols::SubscriptionsTransportWS,
+                Some("subscribe" | "complete" | "ping" | "pong") => WebSocketProtocols::GraphQLWS,
+                _ => continue,
+            };
+            return Some(protocol);
+        }
+        None
+    };
+    let protocol = tokio::time::timeout(within, detect).await.ok()??;
+    Some((protocol, buffered, acked))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn serve<E: Executor>(
+    socket: WebSocket,
+    executor: E,
+    protocol: Choice,
+    connection_data: Data,
+    metrics: Arc<Metrics>,
+    keepalive: KeepAliveConfig,
+    subscribers: Arc<SubscriberRegistry>,
+    // Subject of the authenticated principal; clientIds are registered under it
+    owner: Arc<str>,
+) {
+    let _active = ActiveGuard::new(metrics.clone());
+    let (mut sink, stream) = socket.split();
+
+    // Any frame from the peer, including pongs, counts as a sign of life
+    let last_seen = Arc::new(Mutex::new(Instant::now()));
+    let seen = last_seen.clone();
+    let mut incoming = Box::pin(
+        stream
+            .take_while(|msg| future::ready(msg.is_ok()))
+            .map(Result::unwrap)
+            .inspect(move |_| *seen.lock().unwrap() = Instant::now())
+            .filter(|msg| future::ready(msg.is_text() || msg.is_binary()))
+            .map(Message::into_bytes),
+    );
+
+    let (protocol, buffered, mut pre_acked) = match protocol {
+        Choice::Negotiated(protocol) => (protocol, Vec::new(), false),
+        Choice::Detect { within } => match detect_protocol(&mut incoming, &mut sink, within).await {
+            Some(detected) => detected,
+            None => {
+                warn!("🚫 Daemon: Subscriber sent no recognizable protocol messages, closing");
+                let _ = sink.close().await;
+                return;
+            }
+        },
+    };
+    info!(
+        "🔗 Daemon: Subscriber connected ({})",
+        protocol.sec_websocket_protocol()
+    );
+
+    let incoming = futures_util::stream::iter(buffered).chain(incoming);
+    // `apiVersion` in connection_init overrides the upgrade request's header;
+    // a `clientId` there registers the renderer for delivery tracking. Usage
+    // is charged to the key from the upgrade request.
+    let client_id = Arc::new(Mutex::new(None::<String>));
+    let registered = client_id.clone();
+    let registry = subscribers.clone();
+    let mut outgoing = GraphqlWebSocket::new(executor, incoming, protocol)
+        .connection_data(connection_data)
+        .on_connection_init(move |payload| async move {
+            let mut data = Data::default();
+            if let Some(version) = ApiVersion::from_init_payload(&payload) {
+                data.insert(version);
+            }
+            if let Some(id) = payload["clientId"].as_str().map(str::trim).filter(|id| !id.is_empty()) {
+                info!("🪪 Daemon: Subscriber identified as {} ({})", id, owner);
+                let id = format!("{}/{}", owner, id);
+                let id = id.as_str();
+                registry.connected(id, protocol.sec_websocket_protocol());
+                *registered.lock().unwrap() = Some(id.to_string());
+                data.insert(SubscriberId(id.to_string()));
+            }
+            Ok(data)
+        });
+
+    // The tick branch is disabled when keep-alives are off
+    let mut ticker = tokio::time::interval(keepalive.interval.unwrap_or(Duration::from_secs(3600)));
+    ticker.tick().await;
+    let mut acknowledged = pre_acked;
+
+    loop {
+        tokio::select! {
+            message = outgoing.next() => {
+                let message = match message {
+                    Some(WsMessage::Text(text)) => {
+                        acknowledged = true;
+                        // Detection already sent the ack for the replayed init
+                        if std::mem::take(&mut pre_acked) && text.contains("connection_ack") {
+                            continue;
+                        }
+                        Message::text(text)
+                    }
+                    Some(WsMessage::Close(code, reason)) => Message::close_with(code, reason),
+                    None => break,
+                };
+                let closing = message.is_close();
+                if sink.send(message).await.is_err() || closing {
+                    break;
+                }
+            }
+            _ = ticker.tick(), if keepalive.interval.is_some() => {
+                let idle = last_seen.lock().unwrap().elapsed();
+                if idle > keepalive.idle_timeout {
+                    warn!("💀 Daemon: Reaping subscriber idle for {:?}", idle);
+                    metrics.subscribers_reaped.fetch_add(1, Ordering::Relaxed);
+                    let _ = sink.send(Message::close_with(IDLE_CLOSE_CODE, "keep-alive timeout")).await;
+                    break;
+                }
+                if sink.send(Message::ping(Vec::new())).await.is_err() {
+                    break;
+                }
+                // Protocol-level keep-alive for clients that ignore WS pings;
+                // graphql-transport-ws peers must answer with a pong.
+                if acknowledged {
+                    let ka = match protocol {
+                        WebSocketProtocols::SubscriptionsTransportWS => r#"{"type":"ka"}"#,
+                        WebSocketProtocols::GraphQLWS => r#"{"type":"ping"}"#,
+                    };
+                    if sink.send(Message::text(ka)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    let _ = sink.close().await;
+    if let Some(id) = client_id.lock().unwrap().take() {
+        subscribers.disconnected(&id);
+    }
+    info!("👋 Daemon: Subscriber disconnected");
+}